@@ -0,0 +1,130 @@
+//! Measures how fast the client builds place, cancel and settle instructions, without a node.
+//!
+//! ```text
+//! cargo run --release --example bench_instructions -- [ITERATIONS]
+//! ```
+//!
+//! The client is built with `OBClient::offline` from a fixed market fixture, and every
+//! transaction is signed against a fixed blockhash, so the numbers are the CPU cost of
+//! assembling and signing a transaction, apart from any network time.
+
+use openbook::matching::Side;
+use openbook::pubkey::Pubkey;
+use openbook::signature::Keypair;
+use openbook::v1::market::Market;
+use openbook::v1::ob_client::OBClient;
+use openbook::v1::orders::OrderReturnType;
+use solana_sdk::{hash::Hash, instruction::Instruction, signer::Signer, transaction::Transaction};
+use std::time::{Duration, Instant};
+
+/// Iterations per operation when none is given.
+const DEFAULT_ITERATIONS: u32 = 10_000;
+
+/// Orders resting on each side of the fixture open orders account, cancelled by each run.
+const RESTING_PER_SIDE: u128 = 4;
+
+/// Blockhash every transaction is signed with.
+const BLOCKHASH: Hash = Hash::new_from_array([7; 32]);
+
+/// A 9/6 decimals market with a 0.001 base lot and a 0.001 quote tick, on fixed accounts.
+fn fixture_market() -> Market {
+    let key = |seed: u8| Pubkey::new_from_array([seed; 32]);
+    Market {
+        program_id: key(1),
+        market_address: key(2),
+        coin_decimals: 9,
+        pc_decimals: 6,
+        coin_lot_size: 1_000_000,
+        pc_lot_size: 1,
+        base_mint: key(3),
+        quote_mint: key(4),
+        coin_vault: key(5),
+        pc_vault: key(6),
+        vault_signer_key: key(7),
+        event_queue: key(8),
+        request_queue: key(9),
+        bids_address: key(10),
+        asks_address: key(11),
+        ..Market::default()
+    }
+}
+
+/// Unwraps the instructions of a dry run.
+fn instructions(built: Option<OrderReturnType>) -> Vec<Instruction> {
+    match built {
+        Some(OrderReturnType::Instructions(instructions)) => instructions,
+        other => panic!("expected instructions, got {other:?}"),
+    }
+}
+
+/// Runs `build` `iterations` times, signing what it returns, and prints the time per set.
+async fn bench<F, Fut>(name: &str, iterations: u32, owner: &Keypair, mut build: F)
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Vec<Instruction>>,
+{
+    let mut building = Duration::ZERO;
+    let mut signing = Duration::ZERO;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let ixs = build().await;
+        let built = Instant::now();
+        let tx =
+            Transaction::new_signed_with_payer(&ixs, Some(&owner.pubkey()), &[owner], BLOCKHASH);
+        signing += built.elapsed();
+        building += built - start;
+        std::hint::black_box(tx);
+    }
+    let per_set = |total: Duration| total / iterations;
+    println!(
+        "{name:<8} build {:>9.2?} + sign {:>9.2?} per set, {:>9.0} sets/s",
+        per_set(building),
+        per_set(signing),
+        f64::from(iterations) / (building + signing).as_secs_f64()
+    );
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let iterations = match std::env::args().nth(1) {
+        Some(iterations) => iterations.parse()?,
+        None => DEFAULT_ITERATIONS,
+    };
+    let owner = Keypair::new();
+    let mut ob_client = OBClient::offline(
+        fixture_market(),
+        owner.insecure_clone(),
+        Pubkey::new_from_array([12; 32]),
+    )?;
+    // A dry-run placement is priced off the account's best bid and ask, in price lots.
+    ob_client.open_orders.max_bid = 2_500;
+    ob_client.open_orders.min_ask = 2_510;
+    ob_client.open_orders.open_bids = (1..=RESTING_PER_SIDE).collect();
+    ob_client.open_orders.open_asks = (1..=RESTING_PER_SIDE).map(|id| id << 64).collect();
+    let client = &ob_client;
+
+    println!("[*] {iterations} iterations per operation");
+    bench("place", iterations, &owner, || async move {
+        let mut ixs = Vec::new();
+        for side in [Side::Bid, Side::Ask] {
+            ixs.extend(instructions(
+                client
+                    .place_limit_order(25.0, side, 0.0, false, 0.0)
+                    .await
+                    .unwrap(),
+            ));
+        }
+        ixs
+    })
+    .await;
+    bench("cancel", iterations, &owner, || async move {
+        instructions(client.cancel_orders(false).await.unwrap())
+    })
+    .await;
+    bench("settle", iterations, &owner, || async move {
+        instructions(client.settle_balance(false).await.unwrap())
+    })
+    .await;
+
+    Ok(())
+}
@@ -0,0 +1,94 @@
+//! Places a post-only limit order through the library, waits for it to fill, and settles.
+//!
+//! ```text
+//! KEY_PATH=/path/to/keypair.json RPC_URL=https://api.mainnet-beta.solana.com \
+//!   cargo run --example place_and_settle -- <MARKET_ID> <bid|ask> <SIZE_QUOTE> <PRICE>
+//! ```
+//!
+//! The order is cancelled if it still rests after `FILL_TIMEOUT`. Everything the open orders
+//! account holds is settled back to the wallet at the end.
+
+use openbook::commitment_config::CommitmentConfig;
+use openbook::matching::Side;
+use openbook::v1::ob_client::OBClient;
+use openbook::v1::orders::{NamedParam, OrderReturnType, SubmittedOrder};
+use std::time::{Duration, Instant};
+
+/// How long the order may rest before it is cancelled.
+const FILL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Delay between two reads of the book while waiting for the fill.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [market_id, side, size, price] = args.as_slice() else {
+        return Err("usage: place_and_settle <MARKET_ID> <bid|ask> <SIZE_QUOTE> <PRICE>".into());
+    };
+    let side = Side::from_name(side)?;
+    let size: f64 = size.parse()?;
+    let price: f64 = price.parse()?;
+
+    let mut ob_client = OBClient::new(
+        CommitmentConfig::confirmed(),
+        market_id.parse()?,
+        true,
+        1000,
+    )
+    .await?;
+
+    // 1. Make sure the wallet has an open orders account on the market, creating it if needed.
+    let (rent, created) = ob_client.ensure_open_orders_pool(1, true).await?;
+    for (oo_key, _) in &created {
+        println!("[*] Created open orders account {oo_key} ({rent} lamports of rent)");
+    }
+    let oo_key = ob_client.next_open_orders();
+    ob_client.load_bids_asks_info().await?;
+    println!("[*] Trading through open orders account {oo_key}");
+
+    // 2. Place the limit order.
+    let order: SubmittedOrder = match ob_client
+        .place_limit_order(size, side, 0.0, true, price)
+        .await?
+    {
+        Some(OrderReturnType::Placed(signature, order)) => {
+            println!(
+                "[*] Placed {:?} {} @ {} (client order id {}): {signature}",
+                order.side, order.size, order.price, order.client_order_id
+            );
+            order
+        }
+        Some(other) => return Err(format!("Unexpected placement result {other:?}").into()),
+        None => return Err("Order size rounds to zero lots".into()),
+    };
+
+    // 3. Poll the book until the order no longer rests, or cancel it after the timeout.
+    let started = Instant::now();
+    while ob_client
+        .find_resting_order(order.client_order_id)
+        .await?
+        .is_some()
+    {
+        if started.elapsed() >= FILL_TIMEOUT {
+            println!("[*] Not filled after {FILL_TIMEOUT:?}, cancelling");
+            ob_client.cancel_orders(true).await?;
+            break;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+    match ob_client.order_outcome(&order).await {
+        Ok(outcome) => println!("[*] Order {}: {outcome}", order.client_order_id),
+        Err(err) => println!("[*] Unable to read the order outcome: {err}"),
+    }
+
+    // 4. Crank our fill events so the proceeds are credited, then settle them to the wallet.
+    if let Some((_, signature, consumed)) = ob_client.consume_events_for_owner(10).await? {
+        println!("[*] Consumed {consumed} events: {signature}");
+    }
+    if let Some(OrderReturnType::Signature(signature)) = ob_client.settle_balance(true).await? {
+        println!("[*] Settled: {signature}");
+    }
+
+    Ok(())
+}
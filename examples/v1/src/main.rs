@@ -35,7 +35,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             OrderReturnType::Instructions(insts) => {
                 println!("[*] Got Instructions: {:?}", insts);
             }
-            OrderReturnType::Signature(sign) => {
+            OrderReturnType::Signature(sign) | OrderReturnType::Placed(sign, _) => {
                 println!("[*] Transaction successful, signature: {:?}", sign);
             }
         }
@@ -52,7 +52,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             OrderReturnType::Instructions(insts) => {
                 println!("[*] Got Instructions: {:?}", insts);
             }
-            OrderReturnType::Signature(sign) => {
+            OrderReturnType::Signature(sign) | OrderReturnType::Placed(sign, _) => {
                 println!("[*] Transaction successful, signature: {:?}", sign);
             }
         }
@@ -69,7 +69,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             OrderReturnType::Instructions(insts) => {
                 println!("[*] Got Instructions: {:?}", insts);
             }
-            OrderReturnType::Signature(sign) => {
+            OrderReturnType::Signature(sign) | OrderReturnType::Placed(sign, _) => {
                 println!("[*] Transaction successful, signature: {:?}", sign);
             }
         }
@@ -4,6 +4,8 @@
 #[cfg(feature = "cli")]
 pub mod cli;
 pub mod rpc;
+pub mod rpc_sender;
+pub mod signer;
 #[cfg(feature = "cli")]
 pub mod tui;
 pub mod utils;
@@ -14,6 +16,8 @@ pub mod v2;
 
 // Re-export common func
 #[cfg(feature = "v1")]
+pub use openbook_dex::instruction;
+#[cfg(feature = "v1")]
 pub use openbook_dex::matching;
 #[cfg(feature = "v1")]
 pub use openbook_dex::state;
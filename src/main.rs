@@ -1,25 +1,40 @@
 use anyhow::{anyhow, Result};
 use clap::{Args, Parser, Subcommand};
+use futures::StreamExt;
 use solana_cli_output::display::println_transaction;
-use tokio::time::{sleep, Duration};
-use tracing::{error, info};
-use tracing_subscriber::EnvFilter;
+#[cfg(unix)]
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::time::{sleep, Duration, Instant};
+use tracing::{error, info, warn};
+use tracing_subscriber::{fmt::writer::BoxMakeWriter, EnvFilter};
 
 // Re-exports from the openbook crate
 use openbook::commitment_config::CommitmentConfig;
 use openbook::matching::Side;
-use openbook::v1::ob_client::{OBClient, PROGRAM_ID_ENV, SRM_PROGRAM_ID};
-use openbook::v1::orders::OrderReturnType;
+use openbook::utils::message_base64;
+use openbook::v1::ob_client::{
+    CancelPolicy, ComputeOp, LadderDistribution, OBClient, OffsetKind, PriorityFee,
+    RequestQueueStats, SkipPreflight, BASE_DECIMALS_ENV, DEFAULT_CANCEL_RETRIES,
+    DEFAULT_MARKET_SLIPPAGE_BPS, DEFAULT_MATCH_LIMIT, DEFAULT_MATCH_RETRIES,
+    DEFAULT_MAX_REF_DEVIATION_BPS, OPENBOOK_V1_PROGRAM_ID, PROGRAM_ID_ENV, QUOTE_DECIMALS_ENV,
+    RATE_LIMIT_ENV, SERUM_V3_PROGRAM_ID,
+};
+use openbook::v1::orders::{NamedParam, OoSnapshot, OrderReturnType, PlaceSpec};
+use openbook::v1::record::{RecordWriter, DEFAULT_ROTATE_INTERVAL};
 
 use openbook::pubkey::Pubkey;
+use openbook::rpc::Rpc;
 use openbook::signature::Signature;
 
+use std::io::Write;
 use std::str::FromStr;
 
-const CRANK_DELAY_MS: u64 = 50_000;
 const MAX_CANCEL_ORDERS: usize = 5;
 const MAX_CANCEL_ORDERS_PER_TX: usize = 5;
 
+/// How often `--profile` logs the RPC latencies while a command runs.
+const PROFILE_LOG_INTERVAL: Duration = Duration::from_secs(60);
+
 /// Simple v1-only CLI for OpenBook.
 #[derive(Parser, Debug)]
 #[command(
@@ -37,20 +52,160 @@ struct Cli {
     #[arg(
         short,
         long,
-        default_value_t = String::from(
-            "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6"
-        )
+        value_parser = parse_pubkey,
+        default_value = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6"
     )]
-    market_id: String,
+    market_id: Pubkey,
 
     /// Program id of the DEX (OpenBook v1 by default, Serum v3 supported)
     #[arg(
         long,
-        default_value = SRM_PROGRAM_ID,
-        help = "DEX program id to target (e.g. 9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin for Serum v3)"
+        default_value = OPENBOOK_V1_PROGRAM_ID,
+        help = "DEX program id to target, OpenBook v1 by default. Pass 9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin for the legacy Serum v3"
     )]
     program_id: String,
 
+    /// Maximum RPC requests per second (0 = unlimited). Requests over the limit wait instead of failing
+    #[arg(long, default_value_t = 0)]
+    rate_limit: u32,
+
+    /// Token account holding the base mint (defaults to your associated token account)
+    #[arg(long, value_name = "PUBKEY")]
+    base_token_account: Option<String>,
+
+    /// Token account holding the quote mint (defaults to your associated token account)
+    #[arg(long, value_name = "PUBKEY")]
+    quote_token_account: Option<String>,
+
+    /// Decimals of the base mint, for when the mint account cannot be read
+    #[arg(long, value_name = "DECIMALS")]
+    base_decimals: Option<u8>,
+
+    /// Decimals of the quote mint, for when the mint account cannot be read
+    #[arg(long, value_name = "DECIMALS")]
+    quote_decimals: Option<u8>,
+
+    /// SPL token program id, for clusters where it is deployed at another address
+    #[arg(long, value_name = "PUBKEY")]
+    token_program: Option<String>,
+
+    /// Associated token account program id, for clusters where it is deployed at another address
+    #[arg(long, value_name = "PUBKEY")]
+    ata_program: Option<String>,
+
+    /// Inspect this wallet without loading a keypair (read-only, write commands fail)
+    #[arg(long, value_name = "PUBKEY")]
+    owner: Option<String>,
+
+    /// Ask for confirmation before sending orders, cancels or settlements
+    #[arg(long, global = true)]
+    interactive: bool,
+
+    /// Fetch and print the full transaction once a write is confirmed
+    #[arg(long, global = true)]
+    show_tx: bool,
+
+    /// Print one JSON object per sent transaction to stdout: signature, slot, err, confirmed
+    #[arg(long, global = true)]
+    result_json: bool,
+
+    /// With a dry run, also print the compute unit limit and price, signatures, estimated fee
+    /// and size of each unsent transaction
+    #[arg(long, global = true)]
+    dump_tx: bool,
+
+    /// Print unsent transactions as base64 messages for a multisig instead of instructions,
+    /// built for the vault given by `--owner` (or `--fee-payer`) as open orders owner
+    #[arg(long, global = true, value_name = "FORMAT", value_parser = ["squads"])]
+    export: Option<String>,
+
+    /// Fee payer of exported messages (default: the multisig vault)
+    #[arg(long, global = true, value_name = "PUBKEY", requires = "export")]
+    fee_payer: Option<String>,
+
+    /// On SIGINT / SIGTERM, cancel all your open orders and settle before exiting
+    #[arg(long, global = true)]
+    flatten_on_exit: bool,
+
+    /// Longest time the cancel and settle run by `--flatten-on-exit` may take, in seconds
+    #[arg(long, global = true, default_value_t = 30)]
+    flatten_timeout_secs: u64,
+
+    /// Reuse one blockhash for the transactions sent within this many milliseconds (0 = fetch one per transaction)
+    #[arg(long, global = true, default_value_t = 0)]
+    blockhash_reuse_ms: u64,
+
+    /// Compute unit price: micro-lamports, or auto:<PERCENTILE> of the market's recent fees
+    #[arg(long, global = true, value_name = "FEE")]
+    priority_fee: Option<String>,
+
+    /// Refuse to send transactions when the fee payer holds less than this much SOL
+    #[arg(long, global = true, value_name = "SOL")]
+    min_sol: Option<f64>,
+
+    /// Refuse to send transactions whose estimated fee (base + priority) exceeds this many lamports
+    #[arg(long, global = true, value_name = "LAMPORTS")]
+    max_fee_lamports: Option<u64>,
+
+    /// Simulate placements first and refuse them if the wallet cannot pay for the order
+    #[arg(long, global = true)]
+    check_funds: bool,
+
+    /// Read the event queue in slices of this many bytes, for RPCs that cap response sizes
+    #[arg(long, global = true, value_name = "BYTES")]
+    event_queue_chunk: Option<usize>,
+
+    /// Fail on dropped RPC connections instead of reconnecting and retrying the request once
+    #[arg(long, global = true)]
+    no_reconnect: bool,
+
+    /// Time every RPC request, logged at debug level, and print their p50/p99 every minute,
+    /// on Ctrl-C and at exit
+    #[arg(long, global = true)]
+    profile: bool,
+
+    /// Send transactions without simulating them first: never, cancels or always (the default
+    /// when no value is given)
+    #[arg(
+        long,
+        global = true,
+        value_name = "WHEN",
+        num_args = 0..=1,
+        default_missing_value = "always"
+    )]
+    skip_preflight: Option<String>,
+
+    /// Whether requote may cancel orders without placing their replacement in the same
+    /// transaction: allow-bare (default) or require-replacement
+    #[arg(long, global = true, value_name = "POLICY")]
+    cancel_policy: Option<String>,
+
+    /// Refuse orders priced too far from this external reference price (e.g. an oracle price)
+    #[arg(long, global = true, value_name = "PRICE")]
+    reference_price: Option<f64>,
+
+    /// Deviation from --reference-price, in bps, above which an order is refused
+    #[arg(long, global = true, value_name = "BPS", default_value_t = DEFAULT_MAX_REF_DEVIATION_BPS)]
+    max_ref_deviation_bps: f64,
+
+    /// Refuse limit orders that would leave more than this many of yours resting on one side
+    #[arg(long, global = true, value_name = "N")]
+    max_orders_per_side: Option<usize>,
+
+    /// Round orders below the market's minimum size (one base lot) up to it instead of
+    /// refusing them
+    #[arg(long, global = true)]
+    round_up_min_size: bool,
+
+    /// Confirm transactions of an operation at another commitment, as OP=LEVEL (e.g.
+    /// settle=finalized); OP is place, cancel, settle, match, consume, init-open-orders or other
+    #[arg(long, global = true, value_name = "OP=LEVEL")]
+    confirm_commitment: Vec<String>,
+
+    /// Remember the orders you place in this JSON file, reconciled with the book on startup
+    #[arg(long, global = true, value_name = "PATH")]
+    state_file: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -60,15 +215,45 @@ enum Commands {
     /// Fetch market info & current open orders
     Info,
 
+    /// Show your net base position: wallet and open orders base minus resting asks
+    Position,
+
     /// Display event queue status (pending events, head, seq)
     EventQueue,
 
+    /// Display request queue status, or why the market has none
+    RequestQueue,
+
+    /// Sample the event queue twice and estimate whether cranking keeps up
+    CrankStatus(CrankStatus),
+
+    /// Print the live best bid / ask, mid and spread until Ctrl-C
+    Watch(Watch),
+
+    /// Append the decoded book and new fills to rotating JSON lines files until Ctrl-C
+    Record(Record),
+
+    /// Follow your open orders account and log its balances and orders on each change
+    WatchOpenOrders,
+
     /// Place a limit order (bid / ask)
     Place(Place),
 
+    /// Cross the book with an immediate-or-cancel order (bid / ask)
+    MarketOrder(MarketOrder),
+
+    /// Time a small marketable order through send, confirm, crank and settle
+    PingFill(PingFill),
+
     /// Cancel all open orders for your OOS account
     Cancel(Cancel),
 
+    /// Cancel your orders priced within a band
+    CancelRange(CancelRange),
+
+    /// Cancel your orders whose client order id is in a range or starts with a bit prefix
+    CancelClientIds(CancelClientIds),
+
     /// Settle balances
     Settle(Settle),
 
@@ -84,6 +269,21 @@ enum Commands {
     /// Cancel, settle, place only ask
     CancelSettlePlaceAsk(CancelSettlePlaceAsk),
 
+    /// Cancel, settle, place bid & ask around a mid skewed by inventory
+    PlaceSkewed(PlaceSkewed),
+
+    /// Spread an order over several price levels stepping away from a base price
+    Ladder(Ladder),
+
+    /// Place a bid and an ask together in one transaction, without cancelling or settling
+    PlaceBoth(PlaceBoth),
+
+    /// Place a bid and an ask symmetrically around a fair value, spread apart by --spread-bps
+    QuoteAround(QuoteAround),
+
+    /// Diff your resting orders against desired quotes and only cancel / place what changed
+    Requote(Requote),
+
     /// Consume events
     Consume(Consume),
 
@@ -95,6 +295,39 @@ enum Commands {
 
     /// Find open orders accounts for owner
     FindOpenOrders,
+
+    /// List your open orders accounts on every market of the program, with orders and balances
+    Accounts,
+
+    /// Create open orders accounts until the wallet holds a pool of --count on the market
+    OoPool(OoPool),
+
+    /// Dump the decoded market, book, event queue, open orders and balances as JSON
+    Dump(Dump),
+
+    /// Run a crank daemon that consumes pending events in a loop
+    Crank(Crank),
+
+    /// Plan the consume transactions needed to drain the event queue, and run them
+    CrankPlan(CrankPlan),
+
+    /// Check that an order placed with a client order id is resting on the book
+    VerifyOrder(VerifyOrder),
+
+    /// List every market of the program where your open orders have pending events, and crank them
+    CrankMarkets(CrankMarkets),
+
+    /// Fetch a transaction and decode its OpenBook instructions
+    DecodeTx(DecodeTx),
+
+    /// List the transactions that touched your open orders account, newest first
+    History(History),
+
+    /// Build an order id from a price and sequence number, or split an order id into them
+    OrderId(OrderId),
+
+    /// Print the valid tick prices just above and below the current mid
+    TickLadder(TickLadder),
 }
 
 // Argument structs mirror `src/cli.rs` from the original repo.
@@ -106,13 +339,17 @@ struct Place {
     target_amount_quote: f64,
 
     /// Side: "bid" or "ask"
-    #[arg(short, long)]
-    side: String,
+    #[arg(short, long, value_parser = parse_side)]
+    side: Side,
 
     /// Best offset in USDC
-    #[arg(short, long)]
+    #[arg(short, long, default_value_t = 0.0)]
     best_offset_usdc: f64,
 
+    /// Best offset in bps of the best price, instead of --best-offset-usdc
+    #[arg(long, conflicts_with = "best_offset_usdc")]
+    offset_bps: Option<f64>,
+
     /// Execute on-chain (if false, only build instructions)
     #[arg(short, long)]
     execute: bool,
@@ -120,6 +357,115 @@ struct Place {
     /// Target price
     #[arg(short, long)]
     price_target: f64,
+
+    /// Unix timestamp after which the program rejects the order (not supported by Serum v3)
+    #[arg(long, value_name = "UNIX_TS")]
+    expiry_ts: Option<i64>,
+
+    /// Most resting orders the order may match against, to bound its compute
+    #[arg(long, default_value_t = DEFAULT_MATCH_LIMIT)]
+    match_limit: u16,
+
+    /// Client order id to place the order with (random if omitted), also used to detect a
+    /// placement that landed before resending it
+    #[arg(long, value_name = "ID")]
+    client_order_id: Option<u64>,
+
+    /// Times to resend the order if it is not confirmed and did not land
+    #[arg(long, default_value_t = 0)]
+    retries: u32,
+}
+
+#[derive(Args, Debug, Clone)]
+struct MarketOrder {
+    /// Order size in quote currency (e.g. USDC)
+    #[arg(short = 'q', long)]
+    size_quote: f64,
+
+    /// Side: "bid" or "ask"
+    #[arg(short, long, value_parser = parse_side)]
+    side: Side,
+
+    /// How far past the best opposing price the order may fill, in bps
+    #[arg(long, default_value_t = DEFAULT_MARKET_SLIPPAGE_BPS)]
+    slippage_bps: f64,
+
+    /// Send the order even if the book cannot fill the whole size within the slippage
+    #[arg(long)]
+    allow_partial: bool,
+
+    /// Most resting orders the order may match against, to bound its compute; the rest is cancelled
+    #[arg(long, default_value_t = DEFAULT_MATCH_LIMIT)]
+    match_limit: u16,
+
+    /// Execute on-chain (if false, only build instructions)
+    #[arg(short, long)]
+    execute: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+struct PingFill {
+    /// Order size in quote currency, at least the market's minimum order size
+    #[arg(short = 'q', long)]
+    size_quote: f64,
+
+    /// Side: "bid" or "ask"
+    #[arg(short, long, value_parser = parse_side)]
+    side: Side,
+
+    /// How far past the best opposing price the order may fill, in bps
+    #[arg(long, default_value_t = DEFAULT_MARKET_SLIPPAGE_BPS)]
+    slippage_bps: f64,
+
+    /// Most events scanned and consumed by the crank
+    #[arg(long, default_value_t = 10)]
+    crank_limit: u16,
+
+    /// Acknowledge that the order trades real funds and pays taker and transaction fees
+    #[arg(long)]
+    spend_funds: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+struct CancelRange {
+    /// Lowest price to cancel, inclusive
+    #[arg(long)]
+    min: f64,
+
+    /// Highest price to cancel, inclusive
+    #[arg(long)]
+    max: f64,
+
+    /// Side: "bid" or "ask" (both if omitted)
+    #[arg(short, long, value_parser = parse_side)]
+    side: Option<Side>,
+
+    /// Execute on-chain (if false, only build instructions)
+    #[arg(short, long)]
+    execute: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+struct CancelClientIds {
+    /// Lowest client order id to cancel, inclusive
+    #[arg(long, requires = "high", conflicts_with = "prefix")]
+    low: Option<u64>,
+
+    /// Highest client order id to cancel, inclusive
+    #[arg(long, requires = "low")]
+    high: Option<u64>,
+
+    /// Cancel the ids whose top --prefix-bits bits equal this value
+    #[arg(long, requires = "prefix_bits", required_unless_present = "low")]
+    prefix: Option<u64>,
+
+    /// Number of high bits the prefix covers (1 to 64)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=64))]
+    prefix_bits: Option<u32>,
+
+    /// Execute on-chain (if false, only build instructions)
+    #[arg(short, long)]
+    execute: bool,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -127,6 +473,23 @@ struct Cancel {
     /// Execute on-chain (if false, only build instructions)
     #[arg(short, long)]
     execute: bool,
+
+    /// Cancel everything and re-read the book until no order rests, for up to this many rounds
+    #[arg(long, value_name = "ROUNDS", requires = "execute")]
+    until_flat: Option<usize>,
+
+    /// Cancel the orders of every open orders account you hold on the market, not only --oos
+    #[arg(long, conflicts_with = "until_flat")]
+    all_accounts: bool,
+
+    /// Cancel the orders found on the book by their on-book ids, and report where the book
+    /// and your open orders account disagree
+    #[arg(long, conflicts_with_all = ["until_flat", "all_accounts"])]
+    from_book: bool,
+
+    /// Times to resend an unconfirmed cancel transaction with a fresh blockhash
+    #[arg(long, default_value_t = DEFAULT_CANCEL_RETRIES)]
+    retries: u32,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -134,6 +497,14 @@ struct Settle {
     /// Execute on-chain (if false, only build instructions)
     #[arg(short, long)]
     execute: bool,
+
+    /// Create your missing base/quote associated token accounts in the settle transaction
+    #[arg(long)]
+    create_ata: bool,
+
+    /// Settle to the associated token accounts of this wallet instead, creating them if missing
+    #[arg(long, value_name = "WALLET", conflicts_with = "create_ata")]
+    to_wallet: Option<Pubkey>,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -141,6 +512,14 @@ struct MatchOrders {
     /// Maximum number of orders to match
     #[arg(short, long)]
     limit: u16,
+
+    /// Times to resend the match with a fresh blockhash if it is not confirmed
+    #[arg(short, long, default_value_t = DEFAULT_MATCH_RETRIES)]
+    retries: u32,
+
+    /// Skip the transaction when no request is pending in the request queue
+    #[arg(long, value_name = "BOOL", default_value_t = true, action = clap::ArgAction::Set)]
+    skip_if_empty: bool,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -160,6 +539,10 @@ struct CancelSettlePlace {
     /// Ask price in JLP/USDC
     #[arg(short, long)]
     ask_price_jlp_usdc: f64,
+
+    /// Abort if the book mid moved more than this many bps since the prices were computed
+    #[arg(long, value_name = "BPS")]
+    max_book_move_bps: Option<f64>,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -184,6 +567,117 @@ struct CancelSettlePlaceAsk {
     ask_price_jlp_usdc: f64,
 }
 
+#[derive(Args, Debug, Clone)]
+struct PlaceSkewed {
+    /// Reference mid price
+    #[arg(short, long)]
+    mid: f64,
+
+    /// Distance of each quote from the skewed mid, in bps
+    #[arg(long)]
+    half_spread_bps: f64,
+
+    /// Size of each order in quote currency
+    #[arg(short, long)]
+    size_quote: f64,
+
+    /// Current base inventory relative to target (positive when long)
+    #[arg(short, long, allow_hyphen_values = true)]
+    inventory_base: f64,
+
+    /// Quote shift per unit of base inventory, in bps
+    #[arg(long, default_value_t = 0.0)]
+    skew_factor: f64,
+}
+
+#[derive(Args, Debug, Clone)]
+struct Ladder {
+    /// Side: "bid" or "ask"
+    #[arg(short, long, value_parser = parse_side)]
+    side: Side,
+
+    /// Total size in quote currency, split between the levels
+    #[arg(short = 'q', long)]
+    total_quote: f64,
+
+    /// Price of the first level
+    #[arg(short = 'p', long)]
+    base_price: f64,
+
+    /// Number of orders
+    #[arg(short, long)]
+    levels: usize,
+
+    /// Distance between two levels, in bps of the base price
+    #[arg(long)]
+    step_bps: f64,
+
+    /// Size split between levels: "flat" or "weighted" (more size further from the base price)
+    #[arg(short, long, default_value = "flat")]
+    distribution: String,
+
+    /// Execute on-chain (if false, only build instructions)
+    #[arg(short, long)]
+    execute: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+struct PlaceBoth {
+    /// Bid size in quote currency
+    #[arg(long)]
+    bid_size: f64,
+
+    /// Bid price
+    #[arg(long)]
+    bid_price: f64,
+
+    /// Ask size in quote currency
+    #[arg(long)]
+    ask_size: f64,
+
+    /// Ask price
+    #[arg(long)]
+    ask_price: f64,
+
+    /// Execute on-chain (if false, only build instructions)
+    #[arg(short, long)]
+    execute: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+struct QuoteAround {
+    /// Fair value to quote around
+    #[arg(short, long)]
+    fair: f64,
+
+    /// Distance between the bid and the ask, in bps of the fair value
+    #[arg(long)]
+    spread_bps: f64,
+
+    /// Size of each order in quote currency
+    #[arg(long)]
+    size_quote: f64,
+
+    /// Execute on-chain (if false, only build instructions)
+    #[arg(short, long)]
+    execute: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+struct Requote {
+    /// Desired bid as SIZE@PRICE, size in quote currency (repeat for several)
+    #[arg(short, long)]
+    bid: Vec<String>,
+
+    /// Desired ask as SIZE@PRICE, size in quote currency (repeat for several)
+    #[arg(short, long)]
+    ask: Vec<String>,
+
+    /// Execute on-chain (if false, only print the plan and build instructions)
+    #[arg(short, long)]
+    execute: bool,
+}
+
 #[derive(Args, Debug, Clone)]
 struct Consume {
     /// Limit for consume events instruction
@@ -198,6 +692,14 @@ struct Consume {
         num_args = 1..
     )]
     open_orders: Vec<String>,
+
+    /// Do nothing instead of cranking your own open orders account when the queue references none
+    #[arg(long)]
+    no_self_fallback: bool,
+
+    /// Only crank the events up to your own last pending event, to settle your fills first
+    #[arg(long, conflicts_with = "open_orders")]
+    own_events: bool,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -214,6 +716,185 @@ struct ConsumePermissioned {
         num_args = 1..
     )]
     open_orders: Vec<String>,
+
+    /// Do nothing instead of cranking your own open orders account when the queue references none
+    #[arg(long)]
+    no_self_fallback: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+struct Dump {
+    /// File to write the JSON document to (prints to stdout if omitted)
+    #[arg(short, long, value_name = "PATH")]
+    out: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+struct OoPool {
+    /// Number of open orders accounts the pool should hold
+    #[arg(short = 'n', long)]
+    count: usize,
+
+    /// Create the missing accounts (if false, only build instructions)
+    #[arg(short, long)]
+    execute: bool,
+
+    /// Lamports to fund each new account with on top of its rent exemption
+    #[arg(long, default_value_t = 0)]
+    extra_lamports: u64,
+}
+
+#[derive(Args, Debug, Clone)]
+struct OrderId {
+    /// Side: "bid" or "ask"
+    #[arg(short, long, value_parser = parse_side)]
+    side: Side,
+
+    /// Order id to decode, in decimal or 0x-prefixed hex
+    #[arg(long, conflicts_with_all = ["price", "seq"], required_unless_present = "price")]
+    id: Option<String>,
+
+    /// Price to encode
+    #[arg(short, long, requires = "seq")]
+    price: Option<f64>,
+
+    /// Sequence number to encode
+    #[arg(long, requires = "price")]
+    seq: Option<u64>,
+}
+
+#[derive(Args, Debug, Clone)]
+struct DecodeTx {
+    /// Signature of the transaction to decode
+    #[arg(short, long)]
+    signature: String,
+}
+
+#[derive(Args, Debug, Clone)]
+struct History {
+    /// Number of transactions to list (1 to 1000)
+    #[arg(short, long, default_value_t = 20)]
+    limit: usize,
+
+    /// Start after this signature, e.g. the last one of the previous page
+    #[arg(long)]
+    before: Option<Signature>,
+
+    /// Stop at this signature, excluded
+    #[arg(long)]
+    until: Option<Signature>,
+
+    /// Also fetch each transaction and decode its OpenBook instructions
+    #[arg(short, long)]
+    decode: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+struct CrankMarkets {
+    /// Events scanned, and consumed, per market
+    #[arg(short, long, default_value_t = 10)]
+    limit: u16,
+
+    /// Crank the markets, deepest queue first (if false, only list them)
+    #[arg(short, long)]
+    execute: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+struct Crank {
+    /// Limit for each consume events instruction
+    #[arg(short, long, default_value_t = 10)]
+    limit: u16,
+
+    /// Delay between two event queue checks, in milliseconds
+    #[arg(long, default_value_t = 1_000)]
+    interval_ms: u64,
+
+    /// Warn when the pending event count stays above this depth for `--alert-after-secs`
+    #[arg(long, value_name = "EVENTS")]
+    alert_queue_depth: Option<u64>,
+
+    /// How long the queue must stay above `--alert-queue-depth` before alerting, in seconds
+    #[arg(long, default_value_t = 60)]
+    alert_after_secs: u64,
+
+    /// Exit with a non-zero status when the queue depth alert fires
+    #[arg(long)]
+    exit_on_alert: bool,
+
+    /// Print one JSON line per consumed event: owner, side, maker, base / quote moved and fee
+    #[arg(long)]
+    audit_events: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+struct CrankPlan {
+    /// Most events consumed per transaction
+    #[arg(short, long, default_value_t = 20)]
+    limit: u16,
+
+    /// Send the passes, each confirmed before the next (if false, only print the plan)
+    #[arg(short, long)]
+    execute: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+struct CrankStatus {
+    /// Time between the two samples, in seconds
+    #[arg(short, long, default_value_t = 10)]
+    interval_secs: u64,
+
+    /// Limit of your crank's consume events instructions, to compare its throughput
+    #[arg(short, long)]
+    limit: Option<u16>,
+
+    /// Delay between two checks of your crank, in milliseconds
+    #[arg(long, default_value_t = 1_000)]
+    crank_interval_ms: u64,
+}
+
+#[derive(Args, Debug, Clone)]
+struct TickLadder {
+    /// Number of prices to list on each side of the mid
+    #[arg(short, long, default_value_t = 5)]
+    levels: usize,
+}
+
+#[derive(Args, Debug, Clone)]
+struct Watch {
+    /// Delay between two updates, in milliseconds
+    #[arg(long, default_value_t = 500)]
+    interval_ms: u64,
+}
+
+#[derive(Args, Debug, Clone)]
+struct Record {
+    /// Delay between two captures, in milliseconds
+    #[arg(long, default_value_t = 1000)]
+    interval_ms: u64,
+
+    /// Directory the record files are written to, created if missing
+    #[arg(long, value_name = "DIR")]
+    out: std::path::PathBuf,
+
+    /// Time covered by one record file, in seconds
+    #[arg(long, default_value_t = DEFAULT_ROTATE_INTERVAL.as_secs())]
+    rotate_secs: u64,
+}
+
+#[derive(Args, Debug, Clone)]
+struct VerifyOrder {
+    /// Client order id logged when the order was placed
+    #[arg(short, long)]
+    client_id: u64,
+
+    /// Expected limit price
+    #[arg(short, long)]
+    price: Option<f64>,
+
+    /// Expected resting size in base currency
+    #[arg(short, long)]
+    size: Option<f64>,
 }
 
 #[tokio::main]
@@ -224,95 +905,839 @@ async fn main() -> Result<()> {
         Err(_) => EnvFilter::new("info"),
     };
 
+    let cli = Cli::parse();
+
+    // Keep stdout clean for the JSON results
+    let writer = if cli.result_json {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
+
     tracing_subscriber::fmt()
         .with_env_filter(filter)
+        .with_writer(writer)
         .with_target(false)
         .with_thread_ids(false)
         .with_line_number(false)
         .compact()
         .init();
 
-    let cli = Cli::parse();
+    if cli.program_id == SERUM_V3_PROGRAM_ID {
+        warn!(
+            "[*] Targeting the legacy Serum v3 program {}, not OpenBook v1 ({})",
+            SERUM_V3_PROGRAM_ID, OPENBOOK_V1_PROGRAM_ID
+        );
+    }
 
     // Configure the target program id before instantiating the client
     std::env::set_var(PROGRAM_ID_ENV, &cli.program_id);
+    if cli.rate_limit > 0 {
+        std::env::set_var(RATE_LIMIT_ENV, cli.rate_limit.to_string());
+    }
+    if let Some(decimals) = cli.base_decimals {
+        std::env::set_var(BASE_DECIMALS_ENV, decimals.to_string());
+    }
+    if let Some(decimals) = cli.quote_decimals {
+        std::env::set_var(QUOTE_DECIMALS_ENV, decimals.to_string());
+    }
 
     // Instantiate OB v1 client
-    let market_id = cli.market_id.parse()?;
-    let mut ob_client = OBClient::new(
-        CommitmentConfig::confirmed(),
-        market_id,
-        true,          // use_cache
-        123456789_u128 // cache_ts (just a nonce)
-    )
-    .await?;
-
-    match cli.command {
-        Commands::Info => {
-            info!("[*] OB_V1_Client:\n{:#?}", ob_client);
+    let market_id = cli.market_id;
+    // Exported messages are signed by the multisig, so build them for its vault, not the wallet.
+    let readonly_owner = match (cli.export.is_some(), cli.owner.as_deref()) {
+        (true, None) => Some(cli.fee_payer.as_deref().ok_or_else(|| {
+            anyhow!("--export needs the multisig vault that owns the orders: pass --owner <VAULT>")
+        })?),
+        (_, owner) => owner,
+    };
+    let mut ob_client = match readonly_owner {
+        Some(owner) => {
+            let owner = Pubkey::from_str(owner).map_err(|e| anyhow!("Invalid owner: {e}"))?;
+            OBClient::new_readonly(CommitmentConfig::confirmed(), market_id, owner).await?
+        }
+        None => {
+            OBClient::new(
+                CommitmentConfig::confirmed(),
+                market_id,
+                true,           // use_cache
+                123456789_u128, // cache_ts (just a nonce)
+            )
+            .await?
+        }
+    };
+
+    if let Some(token_program) = cli.token_program.as_deref() {
+        let token_program =
+            Pubkey::from_str(token_program).map_err(|e| anyhow!("Invalid token program: {e}"))?;
+        ob_client.set_token_program(token_program);
+    }
+    if let Some(ata_program) = cli.ata_program.as_deref() {
+        let ata_program =
+            Pubkey::from_str(ata_program).map_err(|e| anyhow!("Invalid ATA program: {e}"))?;
+        ob_client.set_ata_program(ata_program);
+    }
+
+    let base_token_account = cli
+        .base_token_account
+        .as_deref()
+        .map(Pubkey::from_str)
+        .transpose()
+        .map_err(|e| anyhow!("Invalid base token account: {e}"))?;
+    let quote_token_account = cli
+        .quote_token_account
+        .as_deref()
+        .map(Pubkey::from_str)
+        .transpose()
+        .map_err(|e| anyhow!("Invalid quote token account: {e}"))?;
+    ob_client
+        .set_token_accounts(base_token_account, quote_token_account)
+        .await?;
+
+    if cli.blockhash_reuse_ms > 0 {
+        ob_client.set_blockhash_reuse(Duration::from_millis(cli.blockhash_reuse_ms));
+    }
+    if let Some(priority_fee) = cli.priority_fee.as_deref() {
+        ob_client.priority_fee = Some(PriorityFee::from_str(priority_fee)?);
+    }
+    if let Some(min_sol) = cli.min_sol {
+        ob_client.set_min_sol_balance((min_sol * 1_000_000_000.0) as u64);
+    }
+    if let Some(max_fee_lamports) = cli.max_fee_lamports {
+        ob_client.set_max_fee_lamports(max_fee_lamports);
+    }
+    ob_client.set_check_funds(cli.check_funds);
+    ob_client.event_queue_chunk_len = cli.event_queue_chunk;
+    if cli.no_reconnect {
+        ob_client.set_reconnect(false);
+    }
+    if cli.profile {
+        ob_client.enable_rpc_profiling();
+        let rpc = ob_client.rpc_client.clone();
+        tokio::spawn(async move {
+            loop {
+                sleep(PROFILE_LOG_INTERVAL).await;
+                log_rpc_profile(&rpc);
+            }
+        });
+    }
+    if let Some(skip_preflight) = cli.skip_preflight.as_deref() {
+        ob_client.set_skip_preflight(SkipPreflight::from_str(skip_preflight)?);
+    }
+    if let Some(cancel_policy) = cli.cancel_policy.as_deref() {
+        ob_client.set_cancel_policy(CancelPolicy::from_str(cancel_policy)?);
+    }
+    if let Some(reference_price) = cli.reference_price {
+        ob_client.set_reference_price(reference_price);
+    }
+    ob_client.max_ref_deviation_bps = cli.max_ref_deviation_bps;
+    if let Some(max_orders) = cli.max_orders_per_side {
+        ob_client.set_max_orders_per_side(max_orders);
+    }
+    ob_client.set_round_up_to_min_size(cli.round_up_min_size);
+    for (op, commitment) in parse_confirm_commitments(&cli.confirm_commitment)? {
+        ob_client.set_confirm_commitment(op, commitment);
+    }
+    if let Some(path) = cli.state_file.as_deref() {
+        let (resting, pruned) = ob_client.load_order_state(path).await?;
+        info!(
+            "[*] Tracking {} resting orders from {} ({} gone since the last run)",
+            resting, path, pruned
+        );
+    }
+
+    let export_fee_payer = match (cli.export.as_deref(), cli.fee_payer.as_deref()) {
+        (None, _) => None,
+        (Some(_), None) => Some(ob_client.owner_key),
+        (Some(_), Some(fee_payer)) => {
+            Some(Pubkey::from_str(fee_payer).map_err(|e| anyhow!("Invalid fee payer: {e}"))?)
+        }
+    };
+    let opts = RunOptions {
+        verbose: cli.verbose,
+        show_tx: cli.show_tx,
+        result_json: cli.result_json,
+        dump_tx: cli.dump_tx,
+        interactive: cli.interactive,
+        market: cli.market_id.to_string(),
+        export_fee_payer,
+    };
+
+    if !cli.flatten_on_exit {
+        let result = if cli.profile {
+            // Still print the profile of a loop stopped with Ctrl-C.
+            tokio::select! {
+                result = run_command(&mut ob_client, cli.command, &opts) => result,
+                signal = shutdown_signal() => signal.and(Err(anyhow!("Interrupted"))),
+            }
+        } else {
+            run_command(&mut ob_client, cli.command, &opts).await
+        };
+        log_rpc_profile(&ob_client.rpc_client);
+        return result;
+    }
+
+    tokio::select! {
+        result = run_command(&mut ob_client, cli.command, &opts) => {
+            log_rpc_profile(&ob_client.rpc_client);
+            return result;
+        }
+        signal = shutdown_signal() => signal?,
+    }
+
+    warn!("[*] Shutdown signal received, cancelling open orders and settling before exit");
+    let timeout = Duration::from_secs(cli.flatten_timeout_secs);
+    let flat = tokio::time::timeout(timeout, ob_client.flatten())
+        .await
+        .map_err(|_| {
+            anyhow!("Flatten did not finish within {timeout:?}, quotes may remain on the book")
+        })??;
+    for chunk in &flat.cancels {
+        match (chunk.confirmed, chunk.signature) {
+            (true, Some(signature)) => info!(
+                "[*] Cancelled {:?}, signature: {:?}",
+                chunk.order_ids, signature
+            ),
+            _ => error!("[*] Failed to cancel {:?}", chunk.order_ids),
+        }
+    }
+    if let Some(signature) = flat.settle_signature {
+        info!("[*] Settled funds, signature: {:?}", signature);
+    }
+    log_rpc_profile(&ob_client.rpc_client);
+    if !flat.is_flat() {
+        return Err(anyhow!("Some orders could not be cancelled before exit"));
+    }
+
+    Ok(())
+}
+
+/// Logs the latency of each RPC method called so far, when `--profile` is set.
+fn log_rpc_profile(rpc: &Rpc) {
+    for stats in rpc.profile_summary() {
+        info!(
+            "[*] RPC {} => calls: {}, p50: {:?}, p99: {:?}, total: {:?}",
+            stats.method, stats.count, stats.p50, stats.p99, stats.total
+        );
+    }
+}
+
+/// Global flags read by the subcommands.
+struct RunOptions {
+    verbose: u8,
+    show_tx: bool,
+    result_json: bool,
+    dump_tx: bool,
+    interactive: bool,
+    market: String,
+    /// Fee payer of the base64 messages printed for `--export`, `None` without it.
+    export_fee_payer: Option<Pubkey>,
+}
+
+async fn run_command(ob_client: &mut OBClient, command: Commands, opts: &RunOptions) -> Result<()> {
+    let interactive = opts.interactive;
+    let market = &opts.market;
+
+    match command {
+        Commands::Info => {
+            info!("[*] {}", ob_client.market_overview());
+            match ob_client.open_orders_balances().await {
+                Ok(balances) => info!("[*] {}", balances),
+                Err(err) => warn!("[*] Unable to read open orders balances: {err}"),
+            }
+            match ob_client.vault_balances().await {
+                Ok((base, quote)) => info!(
+                    "[*] Market vaults: base {}, quote {}",
+                    ob_client.market_info.format_size(base),
+                    quote
+                ),
+                Err(err) => warn!("[*] Unable to read the market vaults: {err}"),
+            }
+            match ob_client.accrued_fees().await {
+                Ok((base, quote)) => info!(
+                    "[*] Accrued fees: base {}, quote {}",
+                    ob_client.market_info.format_size(base),
+                    quote
+                ),
+                Err(err) => warn!("[*] Unable to read the accrued fees: {err}"),
+            }
+            match ob_client.net_position().await {
+                Ok(position) => info!(
+                    "[*] Net position: {} base",
+                    ob_client.market_info.format_size(position)
+                ),
+                Err(err) => warn!("[*] Unable to compute net position: {err}"),
+            }
+            let (bid_notional, ask_notional) = ob_client.my_resting_notional();
+            info!(
+                "[*] Resting notional: bids {}, asks {} quote",
+                bid_notional, ask_notional
+            );
+            if let Some(mid) = ob_client.order_book.top().mid() {
+                let max_buy = ob_client.max_order_size(Side::Bid, mid).await;
+                let max_sell = ob_client.max_order_size(Side::Ask, mid).await;
+                match (max_buy, max_sell) {
+                    (Ok(max_buy), Ok(max_sell)) => info!(
+                        "[*] Max buy / max sell at the mid {}: {} / {} quote",
+                        ob_client.market_info.format_price(mid),
+                        max_buy,
+                        max_sell
+                    ),
+                    (Err(err), _) | (_, Err(err)) => {
+                        warn!("[*] Unable to compute the max order sizes: {err}")
+                    }
+                }
+            }
+            for (client_order_id, order) in ob_client.order_state.orders() {
+                info!(
+                    "[*] Tracked order {} => {} {} @ {}, order id: {:?}",
+                    client_order_id,
+                    if order.is_bid { "bid" } else { "ask" },
+                    ob_client.market_info.format_size(order.size),
+                    ob_client.market_info.format_price(order.price),
+                    order.order_id
+                );
+            }
+            if opts.verbose > 0 {
+                info!("[*] OB_V1_Client:\n{:#?}", ob_client);
+            }
+        }
+
+        Commands::Position => {
+            let position = ob_client.net_position().await?;
+            info!(
+                "[*] Net position on market {}: {} base",
+                market,
+                ob_client.market_info.format_size(position)
+            );
+            let (bid_notional, ask_notional) = ob_client.my_resting_notional();
+            info!(
+                "[*] Resting notional: bids {}, asks {} quote",
+                bid_notional, ask_notional
+            );
         }
 
         Commands::EventQueue => {
             let stats = ob_client.fetch_event_queue_stats().await?;
-            let needs_crank = stats.count > 0;
+            let needs_crank = stats.needs_crank();
             info!(
                 "[*] Event queue stats => pending_events: {}, head: {}, seq_num: {}, account_flags: 0x{:x}, needs_crank: {}",
                 stats.count, stats.head, stats.seq_num, stats.account_flags, needs_crank
             );
         }
 
-        Commands::Place(arg) => {
-            let side = match arg.side.to_ascii_lowercase().as_str() {
-                "bid" => Side::Bid,
-                "ask" => Side::Ask,
-                _ => Side::Bid,
+        Commands::RequestQueue => match ob_client.request_queue_status().await? {
+            RequestQueueStats::Active {
+                account_flags,
+                head,
+                count,
+                next_seq_num,
+                capacity,
+            } => info!(
+                "[*] Request queue stats => pending_requests: {}, head: {}, next_seq_num: {}, \
+                 capacity: {}, account_flags: 0x{:x}",
+                count, head, next_seq_num, capacity, account_flags
+            ),
+            RequestQueueStats::NotApplicable { reason } => {
+                info!("[*] Request queue not applicable: {}", reason)
+            }
+        },
+
+        Commands::WatchOpenOrders => {
+            let mut snapshots = Box::pin(ob_client.subscribe_open_orders());
+            let watch = async {
+                let mut previous: Option<OoSnapshot> = None;
+                while let Some(snapshot) = snapshots.next().await {
+                    for order in previous.iter().flat_map(|previous| &previous.orders) {
+                        if !snapshot.orders.contains(order) {
+                            info!(
+                                "[*] Order {} (client order id {}) filled or cancelled",
+                                order.order_id, order.client_order_id
+                            );
+                        }
+                    }
+                    let balances = &snapshot.balances;
+                    info!(
+                        "[*] Slot {}: {} orders, free / locked base {} / {}, quote {} / {}",
+                        snapshot.slot,
+                        snapshot.orders.len(),
+                        balances.coin_free,
+                        balances.coin_locked(),
+                        balances.pc_free,
+                        balances.pc_locked()
+                    );
+                    previous = Some(snapshot);
+                }
+                Ok::<(), anyhow::Error>(())
             };
+            tokio::select! {
+                result = watch => result?,
+                signal = shutdown_signal() => signal?,
+            }
+        }
+
+        Commands::Watch(arg) => {
+            let market_info = ob_client.market_info.clone();
+            let price = |price: Option<f64>| match price {
+                Some(price) => market_info.format_price(price),
+                None => "-".to_string(),
+            };
+            let watch =
+                ob_client.watch_top_of_book(Duration::from_millis(arg.interval_ms), |top| {
+                    let spread = match (top.spread(), top.spread_bps()) {
+                        (Some(spread), Some(bps)) => {
+                            format!("{} ({:.1} bps)", market_info.format_price(spread), bps)
+                        }
+                        _ => "-".to_string(),
+                    };
+                    // Overwrite the previous line.
+                    print!(
+                        "\r\x1b[2Kbid {} x {} | ask {} x {} | mid {} | spread {}",
+                        price(top.best_bid),
+                        market_info.format_size(top.bid_size),
+                        price(top.best_ask),
+                        market_info.format_size(top.ask_size),
+                        price(top.mid()),
+                        spread
+                    );
+                    std::io::stdout().flush()?;
+                    Ok(())
+                });
+            tokio::select! {
+                result = watch => result?,
+                signal = shutdown_signal() => signal?,
+            }
+            println!();
+        }
+
+        Commands::Record(arg) => {
+            let mut writer = RecordWriter::new(
+                &arg.out,
+                ob_client.market_info.market_address,
+                Duration::from_secs(arg.rotate_secs),
+            )?;
+            info!(
+                "[*] Recording the book to {}, Ctrl-C to stop",
+                arg.out.display()
+            );
+            let mut records = 0u64;
+            let record =
+                ob_client.watch_book_records(Duration::from_millis(arg.interval_ms), |record| {
+                    writer.write(&record)?;
+                    records += 1;
+                    Ok(())
+                });
+            tokio::select! {
+                result = record => result?,
+                signal = shutdown_signal() => signal?,
+            }
+            if let Some(path) = writer.current_path() {
+                info!("[*] Wrote {} records, last to {}", records, path.display());
+            }
+        }
+
+        Commands::CrankStatus(arg) => {
+            info!(
+                "[*] Sampling the event queue over {} seconds...",
+                arg.interval_secs
+            );
+            let status = ob_client
+                .crank_status(Duration::from_secs(arg.interval_secs))
+                .await?;
+            info!(
+                "[*] Event queue => pending: {} -> {} of {}, produced: {} ({:.2}/s), consumed: {} ({:.2}/s), net: {:+.2}/s",
+                status.pending_before,
+                status.pending,
+                status.capacity,
+                status.produced,
+                status.inflow_per_sec(),
+                status.consumed,
+                status.consumed_per_sec(),
+                status.net_per_sec()
+            );
+            if let Some(limit) = arg.limit {
+                let crank_rate = limit as f64 * 1_000.0 / arg.crank_interval_ms.max(1) as f64;
+                info!(
+                    "[*] A crank consuming {} events every {} ms handles up to {:.2}/s, {}",
+                    limit,
+                    arg.crank_interval_ms,
+                    crank_rate,
+                    if crank_rate >= status.inflow_per_sec() {
+                        "enough for the current inflow"
+                    } else {
+                        "below the current inflow"
+                    }
+                );
+            }
+            match (status.time_to_clear(), status.time_to_overflow()) {
+                (Some(clear), _) => info!("[*] Keeping up, the queue clears in ~{:?}", clear),
+                (_, Some(overflow)) => {
+                    warn!("[*] Falling behind, the queue fills up in ~{:?}", overflow)
+                }
+                _ if status.pending == 0 => info!("[*] Keeping up, the queue is empty"),
+                _ => warn!("[*] Pending events are not moving, is a crank running?"),
+            }
+        }
 
+        Commands::Place(arg) => {
+            let side = arg.side;
+
+            if arg.execute
+                && !confirm(
+                    interactive,
+                    &format!(
+                        "About to place {} {} quote @ {} on market {}",
+                        side_name(side),
+                        arg.target_amount_quote,
+                        arg.price_target,
+                        market
+                    ),
+                )?
+            {
+                return Ok(());
+            }
+
+            ob_client.expiry_ts = arg.expiry_ts;
+            ob_client.match_limit = arg.match_limit;
+            ob_client.place_retries = arg.retries;
+            let offset = match arg.offset_bps {
+                Some(bps) => OffsetKind::Bps(bps),
+                None => OffsetKind::Usdc(arg.best_offset_usdc),
+            };
             if let Some(ord_ret_type) = ob_client
-                .place_limit_order(
+                .place_limit_order_with_client_id(
                     arg.target_amount_quote,
                     side,
-                    arg.best_offset_usdc,
+                    offset,
                     arg.execute,
                     arg.price_target,
+                    arg.client_order_id,
                 )
                 .await?
             {
-                handle_order_return(&mut ob_client, ord_ret_type).await?;
+                handle_order_return(ob_client, ord_ret_type, opts).await?;
             }
         }
 
-        Commands::Cancel(arg) => {
-            if arg.execute {
-                if let Some(signature) = execute_limited_cancel(
-                    &mut ob_client,
-                    MAX_CANCEL_ORDERS,
-                    MAX_CANCEL_ORDERS_PER_TX,
+        Commands::MarketOrder(arg) => {
+            let side = arg.side;
+
+            if arg.execute
+                && !confirm(
+                    interactive,
+                    &format!(
+                        "About to send a market {} for {} quote with {} bps slippage on market {}",
+                        side_name(side),
+                        arg.size_quote,
+                        arg.slippage_bps,
+                        market
+                    ),
+                )?
+            {
+                return Ok(());
+            }
+
+            ob_client.match_limit = arg.match_limit;
+            if let Some(ord_ret_type) = ob_client
+                .place_market_order(
+                    side,
+                    arg.size_quote,
+                    arg.slippage_bps,
+                    arg.allow_partial,
+                    arg.execute,
                 )
                 .await?
-                {
-                    info!("\n[*] Transaction successful, signature: {:?}", signature);
-                    show_tx(&mut ob_client, &signature).await?;
+            {
+                handle_order_return(ob_client, ord_ret_type, opts).await?;
+            }
+        }
+
+        Commands::PingFill(arg) => {
+            if !arg.spend_funds {
+                info!("[*] ping-fill trades real funds and pays fees, pass --spend-funds to run");
+                return Ok(());
+            }
+            if !confirm(
+                interactive,
+                &format!(
+                    "About to trade a {} of {} quote, then crank and settle, on market {}",
+                    side_name(arg.side),
+                    arg.size_quote,
+                    market
+                ),
+            )? {
+                return Ok(());
+            }
+
+            let report = ob_client
+                .ping_fill(arg.side, arg.size_quote, arg.slippage_bps, arg.crank_limit)
+                .await?;
+            info!("[*] Order: {}", report.signature);
+            info!("[*] Send: {:?}", report.send);
+            info!("[*] Confirm: {:?}", report.confirm);
+            match report.crank {
+                Some(crank) => info!("[*] Crank: {:?}", crank),
+                None => info!("[*] Crank: no events to consume"),
+            }
+            info!("[*] Settle: {:?}", report.settle);
+            info!("[*] Total: {:?}", report.total);
+            if !report.filled {
+                warn!("[*] The order did not fill, widen --slippage-bps to time a fill");
+            }
+        }
+
+        Commands::CancelRange(arg) => {
+            let side = arg.side;
+            if arg.execute
+                && !confirm(
+                    interactive,
+                    &format!(
+                        "About to cancel your orders priced {} to {} on market {}",
+                        arg.min, arg.max, market
+                    ),
+                )?
+            {
+                return Ok(());
+            }
+            let (orders, transactions) = ob_client
+                .cancel_in_price_range(arg.min, arg.max, side, arg.execute)
+                .await?;
+            if orders.is_empty() {
+                info!("[*] No orders resting between {} and {}", arg.min, arg.max);
+            }
+            for order in &orders {
+                info!(
+                    "[*] Cancelling order {} (client id {}) @ {}",
+                    order.order_id,
+                    order.client_order_id,
+                    ob_client.market_info.format_price(order.price)
+                );
+            }
+            for ord_ret_type in transactions {
+                handle_order_return(ob_client, ord_ret_type, opts).await?;
+            }
+        }
+
+        Commands::CancelClientIds(arg) => {
+            let (low, high) = match (arg.low, arg.high, arg.prefix, arg.prefix_bits) {
+                (Some(low), Some(high), _, _) => (low, high),
+                (_, _, Some(prefix), Some(bits)) => {
+                    if prefix.checked_shr(bits).unwrap_or(0) != 0 {
+                        return Err(anyhow!("Prefix {prefix:#x} does not fit in {bits} bits"));
+                    }
+                    let low = prefix.checked_shl(64 - bits).unwrap_or(0);
+                    let high = low | u64::MAX.checked_shr(bits).unwrap_or(0);
+                    (low, high)
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "Pass --low and --high, or --prefix and --prefix-bits"
+                    ))
+                }
+            };
+            if arg.execute
+                && !confirm(
+                    interactive,
+                    &format!(
+                        "About to cancel your orders with client ids {:#x} to {:#x} on market {}",
+                        low, high, market
+                    ),
+                )?
+            {
+                return Ok(());
+            }
+            let (orders, transactions) = ob_client
+                .cancel_orders_by_client_id_range(low, high, arg.execute)
+                .await?;
+            if orders.is_empty() {
+                info!(
+                    "[*] No orders resting with client ids {:#x} to {:#x}",
+                    low, high
+                );
+            }
+            for order in &orders {
+                info!(
+                    "[*] Cancelling order {} (client id {:#x}) @ {}",
+                    order.order_id,
+                    order.client_order_id,
+                    ob_client.market_info.format_price(order.price)
+                );
+            }
+            for ord_ret_type in transactions {
+                handle_order_return(ob_client, ord_ret_type, opts).await?;
+            }
+        }
+
+        Commands::Cancel(arg) if arg.all_accounts => {
+            if arg.execute
+                && !confirm(
+                    interactive,
+                    &format!(
+                        "About to cancel the orders of all your open orders accounts on market {}",
+                        market
+                    ),
+                )?
+            {
+                return Ok(());
+            }
+            let accounts = ob_client.cancel_all_accounts(arg.execute).await?;
+            if accounts.is_empty() {
+                info!("[*] No orders resting on market {}", market);
+            }
+            for (open_orders, transactions) in accounts {
+                info!("[*] Open orders account {}", open_orders);
+                for ord_ret_type in transactions {
+                    handle_order_return(ob_client, ord_ret_type, opts).await?;
+                }
+            }
+        }
+
+        Commands::Cancel(arg) if arg.from_book => {
+            if arg.execute
+                && !confirm(
+                    interactive,
+                    &format!(
+                        "About to cancel your orders on the book of market {}",
+                        market
+                    ),
+                )?
+            {
+                return Ok(());
+            }
+            let result = ob_client.cancel_orders_by_book_read(arg.execute).await?;
+            if result.orders.is_empty() {
+                info!("[*] No orders resting on market {}", market);
+            }
+            for order in &result.orders {
+                info!(
+                    "[*] Cancelling order {} (client id {}) @ {}",
+                    order.order_id,
+                    order.client_order_id,
+                    ob_client.market_info.format_price(order.price)
+                );
+            }
+            if !result.in_sync() {
+                warn!(
+                    "[*] Book and open orders account disagree: {} only on the book, {} only \
+                     in the account",
+                    result.book_only.len(),
+                    result.account_only.len()
+                );
+            }
+            for ord_ret_type in result.transactions {
+                handle_order_return(ob_client, ord_ret_type, opts).await?;
+            }
+        }
+
+        Commands::Cancel(arg) => {
+            ob_client.cancel_retries = arg.retries;
+            if arg.execute {
+                let open_orders =
+                    ob_client.open_orders.open_bids.len() + ob_client.open_orders.open_asks.len();
+                if !confirm(
+                    interactive,
+                    &format!(
+                        "About to cancel up to {} of your {} open orders on market {}",
+                        MAX_CANCEL_ORDERS, open_orders, market
+                    ),
+                )? {
+                    return Ok(());
+                }
+                if let Some(rounds) = arg.until_flat {
+                    let remaining = ob_client.ensure_flat(rounds).await?;
+                    if remaining > 0 {
+                        return Err(anyhow!(
+                            "{remaining} orders still resting after {rounds} cancel rounds"
+                        ));
+                    }
+                    info!("[*] No orders resting on market {}", market);
+                    return Ok(());
+                }
+                let result = ob_client
+                    .cancel_orders_chunked(MAX_CANCEL_ORDERS, MAX_CANCEL_ORDERS_PER_TX)
+                    .await?;
+                for chunk in &result.chunks {
+                    if chunk.confirmed {
+                        info!(
+                            "\n[*] Cancelled {:?}, signature: {:?}",
+                            chunk.order_ids, chunk.signature
+                        );
+                    } else {
+                        error!(
+                            "[*] Failed to cancel {:?} (client order ids {:?}) after {} attempts",
+                            chunk.order_ids, chunk.client_order_ids, chunk.attempts
+                        );
+                    }
+                    let signature = chunk.signature.unwrap_or_default();
+                    report_tx(ob_client, &signature, opts).await?;
+                }
+                match &result.still_resting {
+                    Some(ids) if ids.is_empty() => {
+                        info!("[*] No orders resting on market {}", market)
+                    }
+                    Some(ids) => info!("[*] Client order ids still resting: {:?}", ids),
+                    None => warn!("[*] Unable to tell which orders still rest"),
                 }
             } else if let Some(ord_ret_type) = ob_client.cancel_orders(false).await? {
-                handle_order_return(&mut ob_client, ord_ret_type).await?;
+                handle_order_return(ob_client, ord_ret_type, opts).await?;
             }
         }
 
         Commands::Settle(arg) => {
+            let destination = match arg.to_wallet {
+                Some(wallet) => format!("wallet {}", wallet),
+                None => "your wallet".to_string(),
+            };
+            if arg.execute
+                && !confirm(
+                    interactive,
+                    &format!(
+                        "About to settle your funds on market {} to {}",
+                        market, destination
+                    ),
+                )?
+            {
+                return Ok(());
+            }
+            if let Some(wallet) = arg.to_wallet {
+                let ord_ret_type = ob_client.settle_to_wallet(wallet, arg.execute).await?;
+                return handle_order_return(ob_client, ord_ret_type, opts).await;
+            }
+            if arg.create_ata {
+                ob_client.set_create_missing_atas(true);
+            }
             if let Some(ord_ret_type) = ob_client.settle_balance(arg.execute).await? {
-                handle_order_return(&mut ob_client, ord_ret_type).await?;
+                handle_order_return(ob_client, ord_ret_type, opts).await?;
             }
         }
 
         Commands::Match(arg) => {
-            let (_confirmed, signature) =
-                ob_client.match_orders_transaction(arg.limit).await?;
-            info!("\n[*] Transaction successful, signature: {:?}", signature);
-            show_tx(&mut ob_client, &signature).await?;
+            ob_client.match_retries = arg.retries;
+            ob_client.set_skip_empty_match(arg.skip_if_empty);
+            match ob_client.match_orders_transaction(arg.limit).await? {
+                Some((_confirmed, signature)) => {
+                    info!("\n[*] Transaction successful, signature: {:?}", signature);
+                    report_tx(ob_client, &signature, opts).await?;
+                }
+                None => info!("[*] No pending requests, nothing to match"),
+            }
         }
 
         Commands::CancelSettlePlace(arg) => {
+            if !confirm(
+                interactive,
+                &format!(
+                    "About to cancel, settle and place BID {} quote @ {} / ASK {} quote @ {} on market {}",
+                    arg.target_usdc_bid,
+                    arg.price_jlp_usdc_bid,
+                    arg.usdc_ask_target,
+                    arg.ask_price_jlp_usdc,
+                    market
+                ),
+            )? {
+                return Ok(());
+            }
+            ob_client.max_book_move_bps = arg.max_book_move_bps;
             let (_confirmed, signature) = ob_client
                 .cancel_settle_place(
                     arg.usdc_ask_target,
@@ -322,48 +1747,265 @@ async fn main() -> Result<()> {
                 )
                 .await?;
             info!("\n[*] Transaction successful, signature: {:?}", signature);
-            show_tx(&mut ob_client, &signature).await?;
+            report_tx(ob_client, &signature, opts).await?;
         }
 
         Commands::CancelSettlePlaceBid(arg) => {
+            if !confirm(
+                interactive,
+                &format!(
+                    "About to cancel, settle and place BID {} quote @ {} on market {}",
+                    arg.target_size_usdc_bid, arg.bid_price_jlp_usdc, market
+                ),
+            )? {
+                return Ok(());
+            }
             let (_confirmed, signature) = ob_client
-                .cancel_settle_place_bid(
-                    arg.target_size_usdc_bid,
-                    arg.bid_price_jlp_usdc,
-                )
+                .cancel_settle_place_bid(arg.target_size_usdc_bid, arg.bid_price_jlp_usdc)
                 .await?;
             info!("\n[*] Transaction successful, signature: {:?}", signature);
-            show_tx(&mut ob_client, &signature).await?;
+            report_tx(ob_client, &signature, opts).await?;
         }
 
         Commands::CancelSettlePlaceAsk(arg) => {
+            if !confirm(
+                interactive,
+                &format!(
+                    "About to cancel, settle and place ASK {} quote @ {} on market {}",
+                    arg.target_size_usdc_ask, arg.ask_price_jlp_usdc, market
+                ),
+            )? {
+                return Ok(());
+            }
             let (_confirmed, signature) = ob_client
-                .cancel_settle_place_ask(
-                    arg.target_size_usdc_ask,
-                    arg.ask_price_jlp_usdc,
+                .cancel_settle_place_ask(arg.target_size_usdc_ask, arg.ask_price_jlp_usdc)
+                .await?;
+            info!("\n[*] Transaction successful, signature: {:?}", signature);
+            report_tx(ob_client, &signature, opts).await?;
+        }
+
+        Commands::PlaceSkewed(arg) => {
+            if !confirm(
+                interactive,
+                &format!(
+                    "About to cancel, settle and quote {} quote per side around {} (inventory {}) on market {}",
+                    arg.size_quote, arg.mid, arg.inventory_base, market
+                ),
+            )? {
+                return Ok(());
+            }
+            let (_confirmed, signature) = ob_client
+                .place_skewed(
+                    arg.mid,
+                    arg.half_spread_bps,
+                    arg.size_quote,
+                    arg.inventory_base,
+                    arg.skew_factor,
                 )
                 .await?;
             info!("\n[*] Transaction successful, signature: {:?}", signature);
-            show_tx(&mut ob_client, &signature).await?;
+            report_tx(ob_client, &signature, opts).await?;
+        }
+
+        Commands::Ladder(arg) => {
+            let side = arg.side;
+            let distribution = LadderDistribution::from_str(&arg.distribution)?;
+
+            if arg.execute
+                && !confirm(
+                    interactive,
+                    &format!(
+                        "About to place {} {} quote over {} levels from {} every {} bps on market {}",
+                        side_name(side),
+                        arg.total_quote,
+                        arg.levels,
+                        arg.base_price,
+                        arg.step_bps,
+                        market
+                    ),
+                )?
+            {
+                return Ok(());
+            }
+
+            let ladder = ob_client
+                .place_laddered(
+                    side,
+                    arg.total_quote,
+                    arg.base_price,
+                    arg.levels,
+                    arg.step_bps,
+                    distribution,
+                    arg.execute,
+                )
+                .await?;
+            for order in &ladder.orders {
+                info!(
+                    "[*] Level => client order id: {}, price: {}, size: {}, notional: {}",
+                    order.client_order_id,
+                    ob_client.market_info.format_price(order.price),
+                    ob_client.market_info.format_size(order.size),
+                    order.notional
+                );
+            }
+            for ord_ret_type in ladder.transactions {
+                handle_order_return(ob_client, ord_ret_type, opts).await?;
+            }
+        }
+
+        Commands::PlaceBoth(arg) => {
+            if arg.execute
+                && !confirm(
+                    interactive,
+                    &format!(
+                        "About to place BID {} quote @ {} and ASK {} quote @ {} on market {}",
+                        arg.bid_size, arg.bid_price, arg.ask_size, arg.ask_price, market
+                    ),
+                )?
+            {
+                return Ok(());
+            }
+
+            let bid = PlaceSpec {
+                size_quote: arg.bid_size,
+                price: arg.bid_price,
+            };
+            let ask = PlaceSpec {
+                size_quote: arg.ask_size,
+                price: arg.ask_price,
+            };
+            let placed = ob_client.place_both(bid, ask, arg.execute).await?;
+            for order in [&placed.bid, &placed.ask] {
+                info!(
+                    "[*] {:?} => client order id: {}, price: {}, size: {}, notional: {}",
+                    order.side,
+                    order.client_order_id,
+                    ob_client.market_info.format_price(order.price),
+                    ob_client.market_info.format_size(order.size),
+                    order.notional
+                );
+            }
+            handle_order_return(ob_client, placed.transaction, opts).await?;
+        }
+
+        Commands::QuoteAround(arg) => {
+            if arg.execute
+                && !confirm(
+                    interactive,
+                    &format!(
+                        "About to quote {} quote per side {} bps apart around {} on market {}",
+                        arg.size_quote, arg.spread_bps, arg.fair, market
+                    ),
+                )?
+            {
+                return Ok(());
+            }
+
+            let placed = ob_client
+                .quote_around(arg.fair, arg.spread_bps, arg.size_quote, arg.execute)
+                .await?;
+            for order in [&placed.bid, &placed.ask] {
+                info!(
+                    "[*] {:?} => client order id: {}, price: {}, size: {}, notional: {}",
+                    order.side,
+                    order.client_order_id,
+                    ob_client.market_info.format_price(order.price),
+                    ob_client.market_info.format_size(order.size),
+                    order.notional
+                );
+            }
+            handle_order_return(ob_client, placed.transaction, opts).await?;
+        }
+
+        Commands::Requote(arg) => {
+            let parse = |quotes: &[String]| -> Result<Vec<PlaceSpec>> {
+                quotes
+                    .iter()
+                    .map(|quote| PlaceSpec::from_str(quote))
+                    .collect()
+            };
+            let bids = parse(&arg.bid)?;
+            let asks = parse(&arg.ask)?;
+
+            ob_client.load_bids_asks_info().await?;
+            let plan = ob_client.quote_diff(&bids, &asks);
+            for (label, orders) in [("Keep", &plan.keep), ("Cancel", &plan.cancel)] {
+                for (side, order) in orders {
+                    info!(
+                        "[*] {} {:?} {} @ {}, order id: {}",
+                        label,
+                        side,
+                        ob_client.market_info.format_size(order.quantity),
+                        ob_client.market_info.format_price(order.price),
+                        order.order_id
+                    );
+                }
+            }
+            for (side, spec) in &plan.place {
+                info!(
+                    "[*] Place {:?} {} quote @ {}",
+                    side, spec.size_quote, spec.price
+                );
+            }
+            if plan.is_empty() {
+                info!("[*] Resting orders already match the desired quotes");
+                return Ok(());
+            }
+
+            if arg.execute
+                && !confirm(
+                    interactive,
+                    &format!(
+                        "About to cancel {} and place {} orders on market {}",
+                        plan.cancel.len(),
+                        plan.place.len(),
+                        market
+                    ),
+                )?
+            {
+                return Ok(());
+            }
+
+            for ord_ret_type in ob_client.apply_quote_plan(&plan, arg.execute).await? {
+                handle_order_return(ob_client, ord_ret_type, opts).await?;
+            }
         }
 
         Commands::Consume(arg) => {
+            if arg.own_events {
+                match ob_client.consume_events_for_owner(arg.limit).await? {
+                    Some((_confirmed, signature, consumed)) => {
+                        info!("\n[*] Transaction successful, signature: {:?}", signature);
+                        info!("[*] Consumed {} events", consumed);
+                        report_tx(ob_client, &signature, opts).await?;
+                    }
+                    None => info!("[*] No pending events for your open orders account"),
+                }
+                return Ok(());
+            }
             let open_orders = if arg.open_orders.is_empty() {
                 let mut owners = ob_client
                     .collect_event_queue_open_orders(arg.limit as usize)
                     .await?;
-                if owners.is_empty() {
+                if owners.is_empty() && !arg.no_self_fallback {
                     owners.push(ob_client.open_orders.oo_key);
                 }
                 owners
             } else {
                 parse_open_orders(&arg.open_orders)?
             };
-            let (_confirmed, signature) = ob_client
+            if open_orders.is_empty() {
+                info!(
+                    "[*] No open orders accounts referenced in the event queue, nothing to consume"
+                );
+                return Ok(());
+            }
+            let (_confirmed, signature, consumed) = ob_client
                 .consume_events_instruction(open_orders, arg.limit)
                 .await?;
             info!("\n[*] Transaction successful, signature: {:?}", signature);
-            show_tx(&mut ob_client, &signature).await?;
+            info!("[*] Consumed {} events", consumed);
+            report_tx(ob_client, &signature, opts).await?;
         }
 
         Commands::ConsumePermissioned(arg) => {
@@ -371,30 +2013,35 @@ async fn main() -> Result<()> {
                 let mut owners = ob_client
                     .collect_event_queue_open_orders(arg.limit as usize)
                     .await?;
-                if owners.is_empty() {
+                if owners.is_empty() && !arg.no_self_fallback {
                     owners.push(ob_client.open_orders.oo_key);
                 }
                 owners
             } else {
                 parse_open_orders(&arg.open_orders)?
             };
-            let (_confirmed, signature) = ob_client
+            if open_orders.is_empty() {
+                info!(
+                    "[*] No open orders accounts referenced in the event queue, nothing to consume"
+                );
+                return Ok(());
+            }
+            let (_confirmed, signature, consumed) = ob_client
                 .consume_events_permissioned_instruction(open_orders, arg.limit)
                 .await?;
             info!("\n[*] Transaction successful, signature: {:?}", signature);
-            show_tx(&mut ob_client, &signature).await?;
+            info!("[*] Consumed {} events", consumed);
+            report_tx(ob_client, &signature, opts).await?;
         }
 
-        Commands::LoadOrders => {
-            match ob_client.load_orders_for_owner().await {
-                Ok(l) => {
-                    info!("\n[*] Found Program Accounts: {:#?}", l);
-                }
-                Err(e) => {
-                    eprintln!("[*] Error loading orders for owner: {e}");
-                }
+        Commands::LoadOrders => match ob_client.load_orders_for_owner().await {
+            Ok(l) => {
+                info!("\n[*] Found Program Accounts: {:#?}", l);
             }
-        }
+            Err(e) => {
+                eprintln!("[*] Error loading orders for owner: {e}");
+            }
+        },
 
         Commands::FindOpenOrders => {
             match ob_client
@@ -409,31 +2056,474 @@ async fn main() -> Result<()> {
                 }
             }
         }
+
+        Commands::Accounts => {
+            let summaries = ob_client
+                .all_open_orders_summaries(ob_client.market_info.program_id)
+                .await?;
+            if summaries.is_empty() {
+                info!(
+                    "[*] No open orders account found for {}",
+                    ob_client.owner_key
+                );
+            }
+            for summary in &summaries {
+                info!(
+                    "[*] {} on {}: {} orders, free base {} quote {}, total base {} quote {}",
+                    summary.open_orders,
+                    summary.market,
+                    summary.resting_orders,
+                    summary.balances.coin_free,
+                    summary.balances.pc_free,
+                    summary.balances.coin_total,
+                    summary.balances.pc_total
+                );
+            }
+        }
+
+        Commands::OrderId(arg) => {
+            let side = arg.side;
+            let order_id = match (arg.id.as_deref(), arg.price, arg.seq) {
+                (Some(id), _, _) => match id.strip_prefix("0x") {
+                    Some(hex) => u128::from_str_radix(hex, 16),
+                    None => id.parse(),
+                }
+                .map_err(|e| anyhow!("Invalid order id {id:?}: {e}"))?,
+                (None, Some(price), Some(seq)) => ob_client.order_id_for(price, side, seq),
+                _ => return Err(anyhow!("Pass --id, or --price and --seq")),
+            };
+            let price_lots = (order_id >> 64) as u64;
+            info!(
+                "[*] Order id {} ({:#034x}) => {:?}, price: {} ({} lots), seq: {}",
+                order_id,
+                order_id,
+                side,
+                ob_client
+                    .market_info
+                    .format_price(ob_client.price_from_order_id(order_id, side)),
+                price_lots,
+                ob_client.order_seq_from_order_id(order_id, side)
+            );
+            let book_side = match side {
+                Side::Bid => &ob_client.order_book.bids,
+                Side::Ask => &ob_client.order_book.asks,
+            };
+            match book_side.iter().find(|order| order.order_id == order_id) {
+                Some(order) => info!(
+                    "[*] Resting on the book: owner {}, client order id {}, size {}",
+                    order.owner,
+                    order.client_order_id,
+                    ob_client.market_info.format_size(order.quantity)
+                ),
+                None => info!("[*] No {:?} with this id rests on the book", side),
+            }
+        }
+
+        Commands::TickLadder(arg) => {
+            let market_info = &ob_client.market_info;
+            let mid = ob_client
+                .order_book
+                .top()
+                .mid()
+                .ok_or_else(|| anyhow!("The book needs a bid and an ask to have a mid"))?;
+            let ladder = market_info.tick_ladder(mid, arg.levels);
+            info!(
+                "[*] Tick size {}, prices nearest to the mid:",
+                market_info.format_price(market_info.tick_size())
+            );
+            for (lots, price) in ladder.above.iter().rev() {
+                info!("[*]   {} ({} lots)", market_info.format_price(*price), lots);
+            }
+            info!(
+                "[*]   {} mid{}",
+                market_info.format_price(mid),
+                if ladder.mid_on_tick {
+                    " (on a tick)"
+                } else {
+                    ""
+                }
+            );
+            for (lots, price) in &ladder.below {
+                info!("[*]   {} ({} lots)", market_info.format_price(*price), lots);
+            }
+        }
+
+        Commands::OoPool(arg) => {
+            if arg.execute
+                && !confirm(
+                    interactive,
+                    &format!(
+                        "About to create up to {} open orders accounts on market {}",
+                        arg.count, market
+                    ),
+                )?
+            {
+                return Ok(());
+            }
+            ob_client.open_orders_extra_lamports = arg.extra_lamports;
+            let (lamports, created) = ob_client
+                .ensure_open_orders_pool(arg.count, arg.execute)
+                .await?;
+            info!(
+                "[*] Each new open orders account locks {} SOL ({} lamports, {} over rent)",
+                lamports as f64 / 1_000_000_000.0,
+                lamports,
+                arg.extra_lamports
+            );
+            for (oo_key, ord_ret_type) in created {
+                info!("[*] New open orders account {}", oo_key);
+                handle_order_return(ob_client, ord_ret_type, opts).await?;
+            }
+            info!("[*] Open orders pool: {:?}", ob_client.open_orders_pool);
+        }
+
+        Commands::Dump(arg) => {
+            let dump = ob_client.dump_state().await?;
+            let json = serde_json::to_string_pretty(&dump)?;
+            match arg.out {
+                Some(path) => {
+                    std::fs::write(&path, json)
+                        .map_err(|e| anyhow!("Failed to write dump to {}: {}", path, e))?;
+                    info!("[*] Market state written to {}", path);
+                }
+                None => println!("{json}"),
+            }
+        }
+
+        Commands::Crank(arg) => run_crank(ob_client, &arg).await?,
+
+        Commands::DecodeTx(arg) => {
+            let signature = Signature::from_str(&arg.signature)
+                .map_err(|e| anyhow!("Invalid signature: {e}"))?;
+            let instructions = ob_client.decode_transaction(&signature).await?;
+            if instructions.is_empty() {
+                info!("[*] Transaction {} has no OpenBook instructions", signature);
+            }
+            for instruction in instructions {
+                info!("[*] {}", instruction);
+            }
+        }
+
+        Commands::History(arg) => {
+            let page = ob_client
+                .signatures_for_open_orders(arg.limit, arg.before, arg.until)
+                .await?;
+            if page.is_empty() {
+                info!(
+                    "[*] No transactions for open orders account {}",
+                    ob_client.open_orders.oo_key
+                );
+            }
+            for entry in &page {
+                info!(
+                    "[*] {} slot {}, block time {}, {}",
+                    entry.signature,
+                    entry.slot,
+                    entry
+                        .block_time
+                        .map_or_else(|| "unknown".to_string(), |time| time.to_string()),
+                    entry.err.as_deref().unwrap_or("ok")
+                );
+                if arg.decode {
+                    match ob_client.decode_transaction(&entry.signature).await {
+                        Ok(instructions) => {
+                            for instruction in instructions {
+                                info!("[*]     {}", instruction);
+                            }
+                        }
+                        Err(err) => warn!("[*]     Unable to decode the transaction: {err}"),
+                    }
+                }
+            }
+            if let (Some(last), true) = (page.last(), page.len() == arg.limit) {
+                info!("[*] Next page: --before {}", last.signature);
+            }
+        }
+
+        Commands::CrankPlan(arg) => {
+            let plan = ob_client.crank_plan(arg.limit).await?;
+            info!(
+                "[*] {} pending events (seq {}) drain in {} passes of at most {} events",
+                plan.pending,
+                plan.seq_num,
+                plan.passes.len(),
+                arg.limit
+            );
+            for (index, pass) in plan.passes.iter().enumerate() {
+                info!(
+                    "[*] Pass {}: {} events, {} open orders accounts",
+                    index + 1,
+                    pass.events,
+                    pass.open_orders.len()
+                );
+            }
+            if arg.execute {
+                let results = ob_client.execute_crank_plan(&plan).await?;
+                let consumed: u64 = results.iter().map(|(_, _, consumed)| consumed).sum();
+                info!(
+                    "[*] Consumed {} of {} planned events in {} transactions",
+                    consumed,
+                    plan.events(),
+                    results.len()
+                );
+                for (_, signature, _) in &results {
+                    report_tx(ob_client, signature, opts).await?;
+                }
+            }
+        }
+
+        Commands::CrankMarkets(arg) => {
+            let markets = ob_client
+                .markets_with_owner_events(
+                    ob_client.market_info.program_id,
+                    ob_client.owner_key,
+                    arg.limit,
+                )
+                .await?;
+            if markets.is_empty() {
+                info!("[*] No market has pending events for your open orders accounts");
+            }
+            for market in &markets {
+                info!(
+                    "[*] Market {}: {} pending events, {} yours",
+                    market.market, market.pending, market.owner_events
+                );
+                if arg.execute {
+                    let (_confirmed, signature) = ob_client.crank_market(market, arg.limit).await?;
+                    info!("\n[*] Transaction successful, signature: {:?}", signature);
+                    report_tx(ob_client, &signature, opts).await?;
+                }
+            }
+        }
+
+        Commands::VerifyOrder(arg) => {
+            let order = ob_client
+                .find_resting_order(arg.client_id)
+                .await?
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Order with client id {} is not resting on the book (filled, cancelled or rejected)",
+                        arg.client_id
+                    )
+                })?;
+            info!(
+                "[*] Order {} resting => order id: {}, price: {}, size: {}",
+                arg.client_id, order.order_id, order.price, order.quantity
+            );
+
+            if let Some(price) = arg.price {
+                let price_lots = (price * ob_client.market_info.price_factor()) as u64;
+                if price_lots != order.price_lots {
+                    return Err(anyhow!(
+                        "Order rests at {} ({} lots), expected {} ({} lots)",
+                        order.price,
+                        order.price_lots,
+                        price,
+                        price_lots
+                    ));
+                }
+            }
+            if let Some(size) = arg.size {
+                if (order.quantity - size).abs() > ob_client.market_info.base_lots_to_ui(1) / 2.0 {
+                    warn!(
+                        "[*] Order rests with size {}, expected {} (partially filled?)",
+                        order.quantity, size
+                    );
+                }
+            }
+        }
     }
 
     Ok(())
 }
 
+/// Resolves on the first SIGINT or SIGTERM.
+async fn shutdown_signal() -> Result<()> {
+    #[cfg(unix)]
+    {
+        let mut sigterm = signal(SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result?,
+            _ = sigterm.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await?;
+
+    Ok(())
+}
+
+async fn run_crank(ob_client: &OBClient, arg: &Crank) -> Result<()> {
+    let interval = Duration::from_millis(arg.interval_ms);
+    let alert_after = Duration::from_secs(arg.alert_after_secs);
+    let mut backlog_since: Option<Instant> = None;
+
+    loop {
+        let stats = match ob_client.fetch_event_queue_stats().await {
+            Ok(stats) => stats,
+            Err(err) => {
+                error!("[*] Failed to fetch event queue: {err}");
+                sleep(interval).await;
+                continue;
+            }
+        };
+
+        if let Some(threshold) = arg.alert_queue_depth {
+            if stats.count > threshold {
+                let since = *backlog_since.get_or_insert_with(Instant::now);
+                if since.elapsed() >= alert_after {
+                    warn!(
+                        "[*] Event queue depth {} above {} for {:?}, the crank is not keeping up",
+                        stats.count,
+                        threshold,
+                        since.elapsed()
+                    );
+                    if arg.exit_on_alert {
+                        return Err(anyhow!(
+                            "Event queue depth stayed above {threshold} for {alert_after:?}"
+                        ));
+                    }
+                    backlog_since = Some(Instant::now());
+                }
+            } else {
+                backlog_since = None;
+            }
+        }
+
+        if stats.needs_crank() {
+            let open_orders = ob_client
+                .collect_event_queue_open_orders(arg.limit as usize)
+                .await
+                .unwrap_or_else(|err| {
+                    error!("[*] Failed to read event queue owners: {err}");
+                    Vec::new()
+                });
+            if !open_orders.is_empty() && arg.audit_events {
+                match ob_client
+                    .consume_events_audited(open_orders, arg.limit)
+                    .await
+                {
+                    Ok((confirmed, signature, events)) => {
+                        info!(
+                            "[*] Consumed {} of {} pending events, signature: {}, confirmed: {}",
+                            events.len(),
+                            stats.count,
+                            signature,
+                            confirmed
+                        );
+                        for event in &events {
+                            let mut line = serde_json::to_value(event)?;
+                            line["signature"] = signature.to_string().into();
+                            println!("{line}");
+                        }
+                    }
+                    Err(err) => error!("[*] Consume events failed: {err}"),
+                }
+            } else if !open_orders.is_empty() {
+                match ob_client
+                    .consume_events_instruction(open_orders, arg.limit)
+                    .await
+                {
+                    Ok((confirmed, signature, consumed)) => info!(
+                        "[*] Consumed {} of {} pending events, signature: {}, confirmed: {}",
+                        consumed, stats.count, signature, confirmed
+                    ),
+                    Err(err) => error!("[*] Consume events failed: {err}"),
+                }
+            }
+        }
+
+        sleep(interval).await;
+    }
+}
+
+/// Asks the user to confirm `action` on stdin when running interactively.
+fn confirm(interactive: bool, action: &str) -> Result<bool> {
+    if !interactive {
+        return Ok(true);
+    }
+
+    print!("{action}. Proceed? [y/N] ");
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+
+    let proceed = matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes");
+    if !proceed {
+        info!("[*] Aborted");
+    }
+    Ok(proceed)
+}
+
 async fn handle_order_return(
     ob_client: &mut OBClient,
     ord_ret_type: OrderReturnType,
+    opts: &RunOptions,
 ) -> Result<()> {
     match ord_ret_type {
         OrderReturnType::Instructions(insts) => {
-            info!("\n[*] Got Instructions: {:?}", insts);
+            if opts.dump_tx {
+                info!(
+                    "[*] Transaction budget: {}",
+                    ob_client.transaction_budget(&insts).await?
+                );
+            }
+            match opts.export_fee_payer {
+                Some(fee_payer) => println!("{}", message_base64(&insts, &fee_payer)),
+                None => info!("\n[*] Got Instructions: {:?}", insts),
+            }
         }
         OrderReturnType::Signature(signature) => {
             info!("\n[*] Transaction successful, signature: {:?}", signature);
-            show_tx(ob_client, &signature).await?;
+            report_tx(ob_client, &signature, opts).await?;
+        }
+        OrderReturnType::Placed(signature, order) => {
+            let market = &ob_client.market_info;
+            info!(
+                "\n[*] Placed {:?} => client order id: {}, price: {} ({} lots), size: {} ({} lots), notional: {}, max quote: {} native, max_ts: {}",
+                order.side,
+                order.client_order_id,
+                market.format_price(order.price),
+                order.price_lots,
+                market.format_size(order.size),
+                order.base_lots,
+                order.notional,
+                order.max_native_quote,
+                order.max_ts
+            );
+            info!("[*] Transaction successful, signature: {:?}", signature);
+            report_tx(ob_client, &signature, opts).await?;
         }
     }
     Ok(())
 }
 
-async fn show_tx(ob_client: &mut OBClient, signature: &Signature) -> Result<()> {
-    // wait for crank
-    sleep(Duration::from_millis(CRANK_DELAY_MS)).await;
+async fn report_tx(
+    ob_client: &mut OBClient,
+    signature: &Signature,
+    opts: &RunOptions,
+) -> Result<()> {
+    let result = ob_client.transaction_result(signature).await?;
+    if opts.result_json {
+        println!("{}", serde_json::to_string(&result)?);
+    }
+    if *signature == Signature::default() {
+        error!("[*] Transaction was not sent");
+        return Ok(());
+    }
+    info!(
+        "[*] Transaction {} confirmed: {}",
+        signature, result.confirmed
+    );
+
+    if opts.show_tx {
+        show_tx(ob_client, signature).await?;
+    }
+
+    Ok(())
+}
 
+async fn show_tx(ob_client: &mut OBClient, signature: &Signature) -> Result<()> {
     match ob_client.rpc_client.fetch_transaction(signature).await {
         Ok(confirmed_tx) => {
             println_transaction(
@@ -449,46 +2539,44 @@ async fn show_tx(ob_client: &mut OBClient, signature: &Signature) -> Result<()>
             );
         }
         Err(err) => {
-            error!(
-                "[*] Unable to get confirmed transaction details: {}",
-                err
-            );
+            error!("[*] Unable to get confirmed transaction details: {}", err);
         }
     }
 
     Ok(())
 }
 
-async fn execute_limited_cancel(
-    ob_client: &mut OBClient,
-    max_instructions: usize,
-    max_instructions_per_tx: usize,
-) -> Result<Option<Signature>> {
-    let ord_ret_type = match ob_client.cancel_orders(false).await? {
-        Some(ret) => ret,
-        None => return Ok(None),
-    };
+/// Parses a `--side` value, erroring on anything but `bid` or `ask` so that a typo never places
+/// an order on the wrong side.
+fn parse_side(input: &str) -> Result<Side, String> {
+    Side::from_name(input).map_err(|e| format!("{e}, e.g. --side bid"))
+}
 
-    let OrderReturnType::Instructions(mut insts) = ord_ret_type else {
-        return Ok(None);
-    };
+/// Parses a base58 public key argument, such as `--market-id`.
+fn parse_pubkey(input: &str) -> Result<Pubkey, String> {
+    Pubkey::from_str(input).map_err(|e| {
+        format!("{e}, expected a base58 address, e.g. 8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6")
+    })
+}
 
-    if insts.is_empty() {
-        return Ok(None);
+fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::Bid => "BID",
+        Side::Ask => "ASK",
     }
+}
 
-    insts.truncate(max_instructions);
-
-    let mut last_sig = None;
-    for chunk in insts.chunks(max_instructions_per_tx.max(1)) {
-        let (_, signature) = ob_client
-            .rpc_client
-            .send_and_confirm((*ob_client.owner).insecure_clone(), chunk.to_vec())
-            .await?;
-        last_sig = Some(signature);
+fn parse_confirm_commitments(inputs: &[String]) -> Result<Vec<(ComputeOp, CommitmentConfig)>> {
+    let mut commitments = Vec::new();
+    for input in inputs {
+        let (op, level) = input
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Invalid confirm commitment '{input}', expected OP=LEVEL"))?;
+        let commitment = CommitmentConfig::from_str(level)
+            .map_err(|e| anyhow!("Invalid commitment '{level}': {e}"))?;
+        commitments.push((ComputeOp::from_str(op)?, commitment));
     }
-
-    Ok(last_sig)
+    Ok(commitments)
 }
 
 fn parse_open_orders(inputs: &[String]) -> Result<Vec<Pubkey>> {
@@ -500,4 +2588,3 @@ fn parse_open_orders(inputs: &[String]) -> Result<Vec<Pubkey>> {
     }
     Ok(keys)
 }
-
@@ -1,48 +1,129 @@
 //! This module implements a thread safe client to interact with a remote Solana node.
 
 use std::fmt;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use backon::ExponentialBuilder;
 use backon::Retryable;
+use futures::channel::mpsc::{self, UnboundedSender};
+use futures::{Stream, StreamExt};
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
 use solana_client::rpc_config::RpcSendTransactionConfig;
 use solana_client::rpc_request::RpcError;
 use solana_client::{
     client_error::ClientError,
-    nonblocking::rpc_client::RpcClient,
-    rpc_client::GetConfirmedSignaturesForAddress2Config,
-    rpc_config::{RpcAccountInfoConfig, RpcTransactionConfig},
-    rpc_response::RpcConfirmedTransactionStatusWithSignature,
+    nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+    rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClientConfig},
+    rpc_config::{
+        RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSignatureSubscribeConfig,
+        RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig, RpcTransactionConfig,
+    },
+    rpc_filter::{Memcmp, RpcFilterType},
+    rpc_response::{
+        RpcConfirmedTransactionStatusWithSignature, RpcSignatureResult,
+        RpcSimulateTransactionResult,
+    },
 };
 use solana_rpc_client_api::client_error::ErrorKind;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
 use solana_sdk::instruction::Instruction;
-use solana_sdk::signature::Signer;
+use solana_sdk::message::Message;
 use solana_sdk::signer::keypair::Keypair;
-use solana_sdk::transaction::Transaction;
+use solana_sdk::transaction::{Transaction, TransactionError};
 use solana_sdk::{account::Account, pubkey::Pubkey, signature::Signature};
 use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
 
+use crate::rpc_sender::{ManagedSender, Profiler, RateLimiter, Reconnect, RpcCallStats};
+use crate::signer::{sign_transaction, TransactionSigner};
+#[cfg(feature = "v1")]
+use crate::v1::ob_client::OPEN_ORDERS_ACCOUNT_LEN;
+
 #[cfg(feature = "v2")]
 use anchor_lang::{AccountDeserialize, Discriminator};
 
 #[cfg(feature = "v2")]
 use openbookdex_v2::state::OpenOrdersAccount;
 
-#[cfg(feature = "v2")]
-use solana_client::{
-    rpc_config::RpcProgramAccountsConfig,
-    rpc_filter::{Memcmp, RpcFilterType},
-};
+/// How long `send_and_confirm` waits for a signature to reach the client's commitment.
+pub const DEFAULT_CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
 
-#[cfg(feature = "v2")]
-use solana_account_decoder::UiAccountEncoding;
+/// Number of blocks a blockhash stays valid for after it is fetched.
+const BLOCKHASH_VALID_BLOCKS: u64 = 150;
+
+/// Blocks before its last valid block height at which a reused blockhash is refreshed, so a
+/// transaction signed with it still has time to land.
+const BLOCKHASH_EXPIRY_MARGIN: u64 = 40;
+
+/// Approximate time between two blocks, used to estimate the block height without polling it.
+const BLOCK_TIME: Duration = Duration::from_millis(400);
+
+/// Reads `fetch_account_data_chunked` starts over when the chunks come from different slots.
+const CHUNKED_READ_ATTEMPTS: usize = 5;
+
+/// Delay between two `getSignatureStatuses` polls.
+const SIGNATURE_STATUS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// First and longest delay before `subscribe_account` reopens a dropped subscription; the delay
+/// doubles after each failed attempt.
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(1);
+const MAX_RESUBSCRIBE_DELAY: Duration = Duration::from_secs(30);
+
+/// How a transaction sent by [`Rpc::send_and_confirm_transaction`] ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SendOutcome {
+    /// The transaction reached the requested commitment without error.
+    Confirmed,
+    /// The transaction landed and failed on-chain.
+    Failed(TransactionError),
+    /// The node refused the transaction, e.g. because its preflight simulation failed, so it
+    /// was never forwarded.
+    Rejected(String),
+    /// The transaction was sent but its status is still unknown: it was not seen before the
+    /// confirmation timed out, or the status could not be read. It may still land.
+    Unknown,
+}
 
 /// Wrapper type for RpcClient providing additional functionality and enabling Debug trait implementation.
 ///
 /// This struct holds an `Arc` of `RpcClient` to ensure thread safety and efficient resource sharing.
 #[derive(Clone)]
-pub struct Rpc(Arc<RpcClient>);
+pub struct Rpc {
+    client: Arc<RpcClient>,
+    limiter: RateLimiter,
+    reconnect: Reconnect,
+    profiler: Profiler,
+    blockhash: Arc<Mutex<BlockhashCache>>,
+    /// Whether requests go through a `ManagedSender`, i.e. the client was built with `with_url`.
+    managed: bool,
+}
+
+/// Blockhash shared by the transactions of a batch, see [`Rpc::set_blockhash_reuse`].
+#[derive(Debug, Default)]
+struct BlockhashCache {
+    /// How long a fetched blockhash may be reused, zero to fetch one per transaction.
+    window: Duration,
+    /// The last blockhash, its last valid block height and when it was fetched.
+    cached: Option<(Hash, u64, Instant)>,
+}
+
+impl BlockhashCache {
+    /// Returns the cached blockhash if it is inside the reuse window and not close to expiry.
+    fn usable(&self) -> Option<Hash> {
+        let (hash, last_valid_block_height, fetched_at) = self.cached?;
+        let elapsed = fetched_at.elapsed();
+        if elapsed >= self.window {
+            return None;
+        }
+        // The blockhash was fetched about `BLOCKHASH_VALID_BLOCKS` before it expires.
+        let elapsed_blocks = (elapsed.as_millis() / BLOCK_TIME.as_millis()) as u64;
+        let estimated_height =
+            last_valid_block_height.saturating_sub(BLOCKHASH_VALID_BLOCKS) + elapsed_blocks;
+        (estimated_height + BLOCKHASH_EXPIRY_MARGIN < last_valid_block_height).then_some(hash)
+    }
+}
 
 impl Rpc {
     /// Constructs a new Rpc wrapper around the provided RpcClient instance.
@@ -72,12 +153,168 @@ impl Rpc {
     /// }
     /// ```
     pub fn new(rpc_client: RpcClient) -> Self {
-        Rpc(Arc::new(rpc_client))
+        Rpc {
+            client: Arc::new(rpc_client),
+            limiter: RateLimiter::default(),
+            reconnect: Reconnect::default(),
+            profiler: Profiler::default(),
+            blockhash: Arc::default(),
+            managed: false,
+        }
+    }
+
+    /// Constructs a new Rpc client for the given URL whose requests all go through a shared rate limiter.
+    ///
+    /// The limiter is disabled until `set_rate_limit` is called.
+    ///
+    /// # Parameters
+    ///
+    /// - `rpc_url`: The URL of the Solana JSON-RPC endpoint.
+    /// - `commitment`: The default commitment used by the client.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::rpc::Rpc;
+    ///
+    /// let rpc_client = Rpc::with_url(
+    ///     "https://api.mainnet-beta.solana.com".to_string(),
+    ///     CommitmentConfig::confirmed(),
+    /// );
+    /// rpc_client.set_rate_limit(10).unwrap();
+    /// ```
+    pub fn with_url(rpc_url: String, commitment: CommitmentConfig) -> Self {
+        let limiter = RateLimiter::default();
+        let reconnect = Reconnect::default();
+        let profiler = Profiler::default();
+        let sender = ManagedSender::new(
+            rpc_url,
+            limiter.clone(),
+            reconnect.clone(),
+            profiler.clone(),
+        );
+        let client = RpcClient::new_sender(sender, RpcClientConfig::with_commitment(commitment));
+        Rpc {
+            client: Arc::new(client),
+            limiter,
+            reconnect,
+            profiler,
+            blockhash: Arc::default(),
+            managed: true,
+        }
+    }
+
+    /// Limits the number of requests sent per second by this client and all of its clones.
+    ///
+    /// Requests over the limit wait for their turn instead of failing. Passing `0` removes the limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the client was built with `new`: its requests do not go through the
+    /// limiter, so only clients built with `with_url` can be rate limited.
+    pub fn set_rate_limit(&self, requests_per_second: u32) -> Result<()> {
+        if !self.managed {
+            return Err(anyhow::anyhow!(
+                "Rate limiting needs an Rpc client built with Rpc::with_url"
+            ));
+        }
+        self.limiter.set_rate(requests_per_second);
+        Ok(())
+    }
+
+    /// Enables or disables reconnecting after connection-level errors, for this client and all
+    /// of its clones.
+    ///
+    /// When enabled, the default, a request that fails because the connection was refused or
+    /// reset rebuilds the HTTP transport and is retried once, so a long-running process
+    /// survives a provider restart. This only applies to clients built with `with_url`.
+    pub fn set_reconnect(&self, enabled: bool) {
+        self.reconnect.set(enabled);
+    }
+
+    /// Starts timing every request sent by this client and all of its clones.
+    ///
+    /// Each duration is logged at debug level and kept for `profile_summary`. This only applies
+    /// to clients built with `with_url`.
+    pub fn enable_profiling(&self) {
+        self.profiler.enable();
+    }
+
+    /// Returns the p50/p99 latency of each RPC method called since profiling was enabled,
+    /// slowest total first. Empty if profiling is disabled.
+    pub fn profile_summary(&self) -> Vec<RpcCallStats> {
+        self.profiler.summary()
+    }
+
+    /// Reuses a fetched blockhash for the transactions sent within `window`, by this client and
+    /// all of its clones.
+    ///
+    /// Batches (chunked cancels, ladders, requote cycles) then skip one `getLatestBlockhash`
+    /// per transaction. The blockhash is refreshed once the window elapses, once it gets within
+    /// `BLOCKHASH_EXPIRY_MARGIN` blocks of its last valid block height, and after any
+    /// transaction that is not confirmed so retries are signed with a fresh one. Passing
+    /// `Duration::ZERO` fetches a blockhash per transaction again.
+    ///
+    /// Identical instructions sent twice within the window make the same transaction, which the
+    /// cluster only processes once, so loops resending the same instruction should not use it.
+    pub fn set_blockhash_reuse(&self, window: Duration) {
+        let mut cache = self.blockhash.lock().unwrap();
+        cache.window = window;
+        cache.cached = None;
+    }
+
+    /// Returns the blockhash to sign the next transaction with, reusing the cached one when
+    /// allowed by `set_blockhash_reuse`.
+    pub async fn latest_blockhash(&self) -> Result<Hash, ClientError> {
+        if let Some(hash) = self.blockhash.lock().unwrap().usable() {
+            return Ok(hash);
+        }
+
+        let (hash, last_valid_block_height) = self
+            .inner()
+            .get_latest_blockhash_with_commitment(self.inner().commitment())
+            .await?;
+        let mut cache = self.blockhash.lock().unwrap();
+        if !cache.window.is_zero() {
+            tracing::debug!(
+                "reusing blockhash {} until block height {}",
+                hash,
+                last_valid_block_height - BLOCKHASH_EXPIRY_MARGIN
+            );
+            cache.cached = Some((hash, last_valid_block_height, Instant::now()));
+        }
+        Ok(hash)
+    }
+
+    /// Drops the cached blockhash so the next transaction fetches a fresh one.
+    pub fn invalidate_blockhash(&self) {
+        self.blockhash.lock().unwrap().cached = None;
+    }
+
+    /// Fetches a fresh blockhash, bypassing the reuse cache, with its last valid block height.
+    pub async fn blockhash_with_expiry(&self) -> Result<(Hash, u64), ClientError> {
+        self.inner()
+            .get_latest_blockhash_with_commitment(self.inner().commitment())
+            .await
+    }
+
+    /// Waits until the block height passes `last_valid_block_height`, after which a
+    /// transaction signed with a blockhash valid until then can no longer land.
+    pub async fn wait_blockhash_expired(&self, last_valid_block_height: u64) -> anyhow::Result<()> {
+        loop {
+            let height = self.inner().get_block_height().await?;
+            if height > last_valid_block_height {
+                return Ok(());
+            }
+            let blocks_left = (last_valid_block_height - height + 1).min(25) as u32;
+            tokio::time::sleep(BLOCK_TIME * blocks_left).await;
+        }
     }
 
     /// Returns a reference to the inner RpcClient instance wrapped by this wrapper.
     pub fn inner(&self) -> &RpcClient {
-        &self.0
+        &self.client
     }
 
     /// Retrieves a transaction with the specified signature.
@@ -167,13 +404,33 @@ impl Rpc {
         pubkey: &Pubkey,
         before: Option<Signature>,
         until: Option<Signature>,
+    ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>, ClientError> {
+        self.fetch_signatures_page(pubkey, None, before, until)
+            .await
+    }
+
+    /// Retrieves one page of the transaction signatures of an address, newest first, like
+    /// [`Rpc::fetch_signatures_for_address`].
+    ///
+    /// # Parameters
+    ///
+    /// - `pubkey`: The public key of the address.
+    /// - `limit`: Optional. The most signatures to return, the node's maximum (1000) if `None`.
+    /// - `before`: Optional. Start after this signature, e.g. the last one of the previous page.
+    /// - `until`: Optional. Stop at this signature, excluded.
+    pub async fn fetch_signatures_page(
+        &self,
+        pubkey: &Pubkey,
+        limit: Option<usize>,
+        before: Option<Signature>,
+        until: Option<Signature>,
     ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>, ClientError> {
         (|| async {
             let config = GetConfirmedSignaturesForAddress2Config {
                 before,
                 until,
+                limit,
                 commitment: Some(self.inner().commitment()),
-                ..GetConfirmedSignaturesForAddress2Config::default()
             };
             self.inner()
                 .get_signatures_for_address_with_config(pubkey, config)
@@ -235,6 +492,555 @@ impl Rpc {
         .value)
     }
 
+    /// Fetches `length` bytes of an account's data starting at `offset`, using `dataSlice`.
+    ///
+    /// # Parameters
+    ///
+    /// - `pubkey`: The account to read.
+    /// - `offset`: The first byte to return.
+    /// - `length`: The number of bytes to return.
+    ///
+    /// # Returns
+    ///
+    /// The requested bytes, fewer if the account ends before `offset + length`.
+    pub async fn fetch_account_data_slice(
+        &self,
+        pubkey: &Pubkey,
+        offset: usize,
+        length: usize,
+    ) -> anyhow::Result<Vec<u8>> {
+        Ok(self
+            .fetch_account_slice_at(pubkey, offset, length, None)
+            .await?
+            .1)
+    }
+
+    /// Fetches a slice like `fetch_account_data_slice`, from a node at least at
+    /// `min_context_slot`, and returns it with the slot it was read at.
+    async fn fetch_account_slice_at(
+        &self,
+        pubkey: &Pubkey,
+        offset: usize,
+        length: usize,
+        min_context_slot: Option<u64>,
+    ) -> anyhow::Result<(u64, Vec<u8>)> {
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: Some(UiDataSliceConfig { offset, length }),
+            commitment: Some(self.inner().commitment()),
+            min_context_slot,
+        };
+        let response = (|| async {
+            self.inner()
+                .get_account_with_config(pubkey, config.clone())
+                .await
+        })
+        .retry(&ExponentialBuilder::default())
+        .await?;
+        let account = response
+            .value
+            .ok_or_else(|| anyhow::anyhow!("Account {} not found", pubkey))?;
+
+        Ok((response.context.slot, account.data))
+    }
+
+    /// Fetches an account's data `chunk_len` bytes at a time and stitches the slices together.
+    ///
+    /// For RPCs that cap the size of a response below the size of the account. Every chunk
+    /// after the first is requested at the slot of the first one; if a node answers from
+    /// another slot, the account may have changed in between, so the read starts over.
+    ///
+    /// # Parameters
+    ///
+    /// - `pubkey`: The account to read.
+    /// - `chunk_len`: The number of bytes requested per call, at least 1.
+    ///
+    /// # Returns
+    ///
+    /// The whole account data, as of a single slot.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a request fails, or if the chunks still span several slots after
+    /// `CHUNKED_READ_ATTEMPTS` reads.
+    pub async fn fetch_account_data_chunked(
+        &self,
+        pubkey: &Pubkey,
+        chunk_len: usize,
+    ) -> anyhow::Result<Vec<u8>> {
+        let chunk_len = chunk_len.max(1);
+        'read: for attempt in 1..=CHUNKED_READ_ATTEMPTS {
+            let (slot, mut data) = self
+                .fetch_account_slice_at(pubkey, 0, chunk_len, None)
+                .await?;
+            let mut done = data.len() < chunk_len;
+            while !done {
+                let (chunk_slot, chunk) = self
+                    .fetch_account_slice_at(pubkey, data.len(), chunk_len, Some(slot))
+                    .await?;
+                if chunk_slot != slot {
+                    tracing::debug!(
+                        "Chunks of {} read at slots {} and {}, attempt {}",
+                        pubkey,
+                        slot,
+                        chunk_slot,
+                        attempt
+                    );
+                    continue 'read;
+                }
+                done = chunk.len() < chunk_len;
+                data.extend_from_slice(&chunk);
+            }
+            return Ok(data);
+        }
+        Err(anyhow::anyhow!(
+            "Account {} kept changing while read in chunks, {} attempts",
+            pubkey,
+            CHUNKED_READ_ATTEMPTS
+        ))
+    }
+
+    /// Polls `getSignatureStatuses` until a signature reaches the given commitment.
+    ///
+    /// This is much lighter than fetching the full transaction: only the status of the
+    /// signature is requested on each poll.
+    ///
+    /// # Parameters
+    ///
+    /// - `signature`: The signature of the transaction to confirm.
+    /// - `commitment`: The commitment level the transaction must reach.
+    /// - `timeout`: How long to keep polling before giving up.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the transaction reached the commitment without error, `false` if it failed
+    /// on-chain or the timeout elapsed, or an error if the RPC request itself failed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::signature::Signature;
+    /// use openbook::rpc_client::RpcClient;
+    /// use openbook::rpc::Rpc;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let rpc_url = std::env::var("RPC_URL").expect("RPC_URL is not set");
+    ///
+    ///     let connection = RpcClient::new(rpc_url);
+    ///     let rpc_client = Rpc::new(connection);
+    ///
+    ///     let confirmed = rpc_client
+    ///         .confirm_signature(
+    ///             &Signature::default(),
+    ///             CommitmentConfig::confirmed(),
+    ///             Duration::from_secs(1),
+    ///         )
+    ///         .await?;
+    ///     println!("Confirmed: {:?}", confirmed);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn confirm_signature(
+        &self,
+        signature: &Signature,
+        commitment: CommitmentConfig,
+        timeout: Duration,
+    ) -> Result<bool, ClientError> {
+        Ok(self
+            .signature_outcome(signature, commitment, timeout)
+            .await?
+            == SendOutcome::Confirmed)
+    }
+
+    /// Polls a signature like `confirm_signature`, telling a transaction that failed on-chain
+    /// apart from one whose status is still unknown when `timeout` elapses.
+    async fn signature_outcome(
+        &self,
+        signature: &Signature,
+        commitment: CommitmentConfig,
+        timeout: Duration,
+    ) -> Result<SendOutcome, ClientError> {
+        let start = Instant::now();
+        loop {
+            let statuses = self
+                .inner()
+                .get_signature_statuses(&[*signature])
+                .await?
+                .value;
+            if let Some(Some(status)) = statuses.first() {
+                if let Some(err) = &status.err {
+                    tracing::error!("transaction {} failed: {:?}", signature, err);
+                    return Ok(SendOutcome::Failed(err.clone()));
+                }
+                if status.satisfies_commitment(commitment) {
+                    return Ok(SendOutcome::Confirmed);
+                }
+            }
+            if start.elapsed() >= timeout {
+                tracing::warn!(
+                    "transaction {} not confirmed after {:?}",
+                    signature,
+                    timeout
+                );
+                return Ok(SendOutcome::Unknown);
+            }
+            tokio::time::sleep(SIGNATURE_STATUS_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Keeps the latest state of `pubkeys` and calls `on_tick` with it every `interval`, until
+    /// `on_tick` returns an error.
+    ///
+    /// The accounts are followed with `accountSubscribe` websockets so each tick sees the last
+    /// pushed update. If the websocket cannot be opened, or a subscription fails or closes,
+    /// the accounts are polled with `getMultipleAccounts` every `interval` instead.
+    ///
+    /// # Parameters
+    ///
+    /// - `ws_url`: The websocket endpoint of the node.
+    /// - `pubkeys`: The accounts to watch.
+    /// - `interval`: Time between two calls of `on_tick`.
+    /// - `on_tick`: Receives the accounts, in the order of `pubkeys`, `None` if missing.
+    pub async fn watch_accounts<F>(
+        &self,
+        ws_url: &str,
+        pubkeys: &[Pubkey],
+        interval: Duration,
+        mut on_tick: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&[Option<Account>]) -> Result<()>,
+    {
+        let mut latest = self.fetch_multiple_accounts(pubkeys).await?;
+        let mut ticker = tokio::time::interval(interval);
+
+        match PubsubClient::new(ws_url).await {
+            Ok(pubsub) => {
+                let config = RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    commitment: Some(self.inner().commitment()),
+                    ..RpcAccountInfoConfig::default()
+                };
+                let mut streams = Vec::with_capacity(pubkeys.len());
+                for (index, pubkey) in pubkeys.iter().enumerate() {
+                    match pubsub.account_subscribe(pubkey, Some(config.clone())).await {
+                        Ok((stream, _unsubscribe)) => {
+                            streams.push(stream.map(move |update| (index, update)))
+                        }
+                        Err(err) => {
+                            tracing::warn!("accountSubscribe failed, polling instead: {}", err);
+                            streams.clear();
+                            break;
+                        }
+                    }
+                }
+
+                if !streams.is_empty() {
+                    let mut updates = futures::stream::select_all(streams);
+                    loop {
+                        tokio::select! {
+                            update = updates.next() => match update {
+                                Some((index, update)) => latest[index] = update.value.decode(),
+                                None => {
+                                    tracing::warn!("account subscription closed, polling instead");
+                                    break;
+                                }
+                            },
+                            _ = ticker.tick() => on_tick(&latest)?,
+                        }
+                    }
+                }
+            }
+            Err(err) => {
+                tracing::warn!("websocket {} unavailable, polling instead: {}", ws_url, err);
+            }
+        }
+
+        loop {
+            ticker.tick().await;
+            latest = self.fetch_multiple_accounts(pubkeys).await?;
+            on_tick(&latest)?;
+        }
+    }
+
+    /// Streams the slot and state of `pubkey` each time its data changes.
+    ///
+    /// A background task follows the account with an `accountSubscribe` websocket. When the
+    /// websocket cannot be opened, or the subscription fails or closes, it reconnects after a
+    /// delay doubling up to 30 seconds, and reads the account with `getAccountInfo` first so a
+    /// change made while disconnected is not missed. Updates whose data equals the last one
+    /// sent, e.g. a lamports-only change, are skipped.
+    ///
+    /// The task stops at the first update after the stream is dropped. It is spawned on the
+    /// current tokio runtime.
+    ///
+    /// # Parameters
+    ///
+    /// - `ws_url`: The websocket endpoint of the node.
+    /// - `pubkey`: The account to follow.
+    pub fn subscribe_account(
+        &self,
+        ws_url: &str,
+        pubkey: Pubkey,
+    ) -> impl Stream<Item = (u64, Account)> {
+        let (sender, receiver) = mpsc::unbounded();
+        let rpc = self.clone();
+        let ws_url = ws_url.to_string();
+
+        tokio::spawn(async move {
+            let mut last_data = None;
+            let mut delay = RESUBSCRIBE_DELAY;
+            loop {
+                match rpc
+                    .inner()
+                    .get_account_with_commitment(&pubkey, rpc.inner().commitment())
+                    .await
+                {
+                    Ok(response) => {
+                        if let Some(account) = response.value {
+                            if !forward_account(
+                                &sender,
+                                &mut last_data,
+                                response.context.slot,
+                                account,
+                            ) {
+                                return;
+                            }
+                        }
+                    }
+                    Err(err) => tracing::warn!("failed to read account {}: {}", pubkey, err),
+                }
+
+                match PubsubClient::new(&ws_url).await {
+                    Ok(pubsub) => {
+                        let config = RpcAccountInfoConfig {
+                            encoding: Some(UiAccountEncoding::Base64),
+                            commitment: Some(rpc.inner().commitment()),
+                            ..RpcAccountInfoConfig::default()
+                        };
+                        match pubsub.account_subscribe(&pubkey, Some(config)).await {
+                            Ok((mut updates, _unsubscribe)) => {
+                                delay = RESUBSCRIBE_DELAY;
+                                while let Some(update) = updates.next().await {
+                                    let Some(account) = update.value.decode() else {
+                                        continue;
+                                    };
+                                    if !forward_account(
+                                        &sender,
+                                        &mut last_data,
+                                        update.context.slot,
+                                        account,
+                                    ) {
+                                        return;
+                                    }
+                                }
+                                tracing::warn!("account subscription to {} closed", pubkey);
+                            }
+                            Err(err) => tracing::warn!("accountSubscribe failed: {}", err),
+                        }
+                    }
+                    Err(err) => tracing::warn!("websocket {} unavailable: {}", ws_url, err),
+                }
+
+                if sender.is_closed() {
+                    return;
+                }
+                tracing::warn!("resubscribing to {} in {:?}", pubkey, delay);
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RESUBSCRIBE_DELAY);
+            }
+        });
+
+        receiver
+    }
+
+    /// Waits for a signature to reach the given commitment using a `signatureSubscribe` websocket.
+    ///
+    /// The node pushes a notification as soon as the transaction reaches the commitment, so no
+    /// time is lost between polls. If the websocket cannot be opened or the subscription is
+    /// dropped, this falls back to [`Rpc::confirm_signature`].
+    ///
+    /// # Parameters
+    ///
+    /// - `ws_url`: The websocket endpoint of the node (e.g. `wss://api.mainnet-beta.solana.com`).
+    /// - `signature`: The signature of the transaction to confirm.
+    /// - `commitment`: The commitment level the transaction must reach.
+    /// - `timeout`: How long to wait for the notification before giving up.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the transaction reached the commitment without error, `false` if it failed
+    /// on-chain or the timeout elapsed, or an error if the RPC request itself failed.
+    pub async fn confirm_via_signature_subscribe(
+        &self,
+        ws_url: &str,
+        signature: &Signature,
+        commitment: CommitmentConfig,
+        timeout: Duration,
+    ) -> Result<bool, ClientError> {
+        let pubsub = match PubsubClient::new(ws_url).await {
+            Ok(pubsub) => pubsub,
+            Err(err) => {
+                tracing::warn!("websocket {} unavailable, polling instead: {}", ws_url, err);
+                return self.confirm_signature(signature, commitment, timeout).await;
+            }
+        };
+        let config = RpcSignatureSubscribeConfig {
+            commitment: Some(commitment),
+            enable_received_notification: Some(false),
+        };
+        let (mut notifications, unsubscribe) =
+            match pubsub.signature_subscribe(signature, Some(config)).await {
+                Ok(subscription) => subscription,
+                Err(err) => {
+                    tracing::warn!("signatureSubscribe failed, polling instead: {}", err);
+                    return self.confirm_signature(signature, commitment, timeout).await;
+                }
+            };
+
+        // The transaction may have reached the commitment before the subscription was registered.
+        let statuses = self
+            .inner()
+            .get_signature_statuses(&[*signature])
+            .await?
+            .value;
+        let landed = match statuses.first() {
+            Some(Some(status)) if status.err.is_some() => Some(false),
+            Some(Some(status)) if status.satisfies_commitment(commitment) => Some(true),
+            _ => None,
+        };
+
+        let confirmed = match landed {
+            Some(confirmed) => Some(confirmed),
+            None => match tokio::time::timeout(timeout, notifications.next()).await {
+                Ok(Some(response)) => match response.value {
+                    RpcSignatureResult::ProcessedSignature(result) => {
+                        if let Some(err) = result.err {
+                            tracing::error!("transaction {} failed: {:?}", signature, err);
+                        }
+                        Some(result.err.is_none())
+                    }
+                    RpcSignatureResult::ReceivedSignature(_) => None,
+                },
+                Ok(None) => None,
+                Err(_) => {
+                    tracing::warn!(
+                        "transaction {} not confirmed after {:?}",
+                        signature,
+                        timeout
+                    );
+                    Some(false)
+                }
+            },
+        };
+        drop(notifications);
+        unsubscribe().await;
+
+        match confirmed {
+            Some(confirmed) => Ok(confirmed),
+            None => {
+                tracing::warn!("signature subscription closed, polling instead");
+                self.confirm_signature(signature, commitment, timeout).await
+            }
+        }
+    }
+
+    /// Lists the OpenBook v1 open orders accounts of `owner` on `market`.
+    ///
+    /// # Parameters
+    ///
+    /// - `program`: The DEX program owning the accounts.
+    /// - `market`: The market the accounts were initialized for.
+    /// - `owner`: The wallet owning the accounts.
+    ///
+    /// # Returns
+    ///
+    /// The addresses of the matching accounts, in no particular order.
+    #[cfg(feature = "v1")]
+    pub async fn fetch_v1_open_orders_accounts(
+        &self,
+        program: Pubkey,
+        market: Pubkey,
+        owner: Pubkey,
+    ) -> anyhow::Result<Vec<Pubkey>> {
+        // Open orders layout: 5 padding bytes, the account flags, then the market and owner.
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::DataSize(OPEN_ORDERS_ACCOUNT_LEN as u64),
+                RpcFilterType::Memcmp(Memcmp::new_raw_bytes(13, market.to_bytes().to_vec())),
+                RpcFilterType::Memcmp(Memcmp::new_raw_bytes(45, owner.to_bytes().to_vec())),
+            ]),
+            ..RpcProgramAccountsConfig::default()
+        };
+        Ok(self
+            .inner()
+            .get_program_accounts_with_config(&program, config)
+            .await?
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect())
+    }
+
+    /// Lists the OpenBook v1 open orders accounts of `owner` on every market of `program`.
+    ///
+    /// # Parameters
+    ///
+    /// - `program`: The DEX program owning the accounts.
+    /// - `owner`: The wallet owning the accounts.
+    ///
+    /// # Returns
+    ///
+    /// Each account address with the market it was initialized for, in no particular order.
+    #[cfg(feature = "v1")]
+    pub async fn fetch_v1_open_orders_markets(
+        &self,
+        program: Pubkey,
+        owner: Pubkey,
+    ) -> anyhow::Result<Vec<(Pubkey, Pubkey)>> {
+        Ok(self
+            .fetch_v1_open_orders_of_owner(program, owner)
+            .await?
+            .into_iter()
+            .filter_map(|(key, account)| {
+                let market = <[u8; 32]>::try_from(account.data.get(13..45)?).ok()?;
+                Some((key, Pubkey::from(market)))
+            })
+            .collect())
+    }
+
+    /// Fetches the OpenBook v1 open orders accounts of `owner` on every market of `program`.
+    ///
+    /// # Parameters
+    ///
+    /// - `program`: The DEX program owning the accounts.
+    /// - `owner`: The wallet owning the accounts.
+    ///
+    /// # Returns
+    ///
+    /// Each account address with its raw account, in no particular order.
+    #[cfg(feature = "v1")]
+    pub async fn fetch_v1_open_orders_of_owner(
+        &self,
+        program: Pubkey,
+        owner: Pubkey,
+    ) -> anyhow::Result<Vec<(Pubkey, Account)>> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::DataSize(OPEN_ORDERS_ACCOUNT_LEN as u64),
+                RpcFilterType::Memcmp(Memcmp::new_raw_bytes(45, owner.to_bytes().to_vec())),
+            ]),
+            ..RpcProgramAccountsConfig::default()
+        };
+        Ok(self
+            .inner()
+            .get_program_accounts_with_config(&program, config)
+            .await?)
+    }
+
     #[cfg(feature = "v2")]
     pub async fn fetch_anchor_account<T: AccountDeserialize>(
         &self,
@@ -300,31 +1106,128 @@ impl Rpc {
             .collect()
     }
 
+    /// Simulates `instructions` paid by `payer`, returning the state of `accounts` after the
+    /// simulation.
+    ///
+    /// The node replaces the blockhash and skips signature checks, so the transaction is left
+    /// unsigned and the result only depends on the accounts. An account missing after the
+    /// simulation is `None` in `RpcSimulateTransactionResult::accounts`.
+    pub async fn simulate_with_accounts(
+        &self,
+        payer: &Pubkey,
+        instructions: &[Instruction],
+        accounts: &[Pubkey],
+    ) -> anyhow::Result<RpcSimulateTransactionResult> {
+        let txn = Transaction::new_unsigned(Message::new_with_blockhash(
+            instructions,
+            Some(payer),
+            &Hash::default(),
+        ));
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(self.inner().commitment()),
+            encoding: Some(UiTransactionEncoding::Base64),
+            accounts: Some(RpcSimulateTransactionAccountsConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                addresses: accounts.iter().map(Pubkey::to_string).collect(),
+            }),
+            ..RpcSimulateTransactionConfig::default()
+        };
+
+        Ok(self
+            .inner()
+            .simulate_transaction_with_config(&txn, config)
+            .await?
+            .value)
+    }
+
     pub async fn send_and_confirm(
         &self,
         owner: Keypair,
         instructions: Vec<Instruction>,
     ) -> anyhow::Result<(bool, Signature)> {
-        let confirmed;
+        self.send_and_confirm_with_preflight(owner, instructions, false)
+            .await
+    }
+
+    /// Signs, sends and confirms `instructions` like `send_and_confirm`, optionally with
+    /// `skipPreflight`.
+    ///
+    /// Without preflight the node forwards the transaction without simulating it, so errors
+    /// are not returned by `sendTransaction`. The signature is still confirmed and a failed or
+    /// dropped transaction comes back as unconfirmed.
+    pub async fn send_and_confirm_with_preflight(
+        &self,
+        owner: Keypair,
+        instructions: Vec<Instruction>,
+        skip_preflight: bool,
+    ) -> anyhow::Result<(bool, Signature)> {
+        self.send_and_confirm_with_commitment(
+            owner,
+            instructions,
+            skip_preflight,
+            self.inner().commitment(),
+        )
+        .await
+    }
+
+    /// Signs, sends and confirms `instructions` like `send_and_confirm_with_preflight`, waiting
+    /// for `commitment` instead of the client's commitment.
+    ///
+    /// Preflight still simulates at the client's commitment; only the confirmation waits for
+    /// `commitment`, e.g. `finalized` before moving settled funds elsewhere.
+    pub async fn send_and_confirm_with_commitment(
+        &self,
+        owner: Keypair,
+        instructions: Vec<Instruction>,
+        skip_preflight: bool,
+        commitment: CommitmentConfig,
+    ) -> anyhow::Result<(bool, Signature)> {
+        self.send_and_confirm_with_signer(&owner, instructions, skip_preflight, commitment)
+            .await
+    }
+
+    /// Signs `instructions` with any `TransactionSigner`, e.g. a remote HSM or KMS, then sends
+    /// and confirms them like `send_and_confirm_with_commitment`.
+    ///
+    /// A signer that fails, or returns a signature that does not verify, is an error and
+    /// nothing is sent.
+    pub async fn send_and_confirm_with_signer(
+        &self,
+        signer: &dyn TransactionSigner,
+        instructions: Vec<Instruction>,
+        skip_preflight: bool,
+        commitment: CommitmentConfig,
+    ) -> anyhow::Result<(bool, Signature)> {
+        let recent_hash = self.latest_blockhash().await?;
+        let txn = sign_transaction(signer, &instructions, recent_hash).await?;
+        let (outcome, signature) = self
+            .send_and_confirm_transaction(&txn, skip_preflight, commitment)
+            .await?;
+        Ok((outcome == SendOutcome::Confirmed, signature))
+    }
+
+    /// Sends and confirms an already signed `txn` like `send_and_confirm_with_signer`, telling
+    /// how it ended.
+    ///
+    /// The returned signature is `Signature::default()` if the node did not accept the
+    /// transaction; it can still be read from `txn`.
+    pub async fn send_and_confirm_transaction(
+        &self,
+        txn: &Transaction,
+        skip_preflight: bool,
+        commitment: CommitmentConfig,
+    ) -> anyhow::Result<(SendOutcome, Signature)> {
+        let outcome;
         let mut sig = Signature::default();
-        let recent_hash = self
-            .inner()
-            .get_latest_blockhash_with_commitment(self.inner().commitment())
-            .await?
-            .0;
-        let txn = Transaction::new_signed_with_payer(
-            &instructions,
-            Some(&owner.pubkey()),
-            &[&owner],
-            recent_hash,
-        );
 
         match self
             .inner()
             .send_transaction_with_config(
-                &txn,
+                txn,
                 RpcSendTransactionConfig {
-                    skip_preflight: false,
+                    skip_preflight,
                     max_retries: None,
                     preflight_commitment: Some(self.inner().commitment().commitment),
                     encoding: None,
@@ -334,15 +1237,14 @@ impl Rpc {
             .await
         {
             Ok(signature) => {
-                match (|| async { self.inner().confirm_transaction(&signature).await })
-                    .retry(&ExponentialBuilder::default())
+                sig = signature;
+                match self
+                    .signature_outcome(&signature, commitment, DEFAULT_CONFIRM_TIMEOUT)
                     .await
                 {
-                    Ok(_ret) => {
-                        // Hack: We have received a signature. We assume it is confirmed due to the Solana network/Crank delay to get confirmation.
-                        confirmed = true;
-                        sig = signature;
-                        tracing::debug!("transaction confirmed: {:?}", signature);
+                    Ok(ret) => {
+                        tracing::debug!("transaction {:?} outcome: {:?}", signature, ret);
+                        outcome = ret;
                     }
                     Err(err) => {
                         match err.kind() {
@@ -379,9 +1281,9 @@ impl Rpc {
                         }
                         tracing::error!(
                             "Error occurred while processing instructions: {:?}",
-                            instructions
+                            txn.message.instructions
                         );
-                        confirmed = false;
+                        outcome = SendOutcome::Unknown;
                     }
                 }
             }
@@ -420,14 +1322,75 @@ impl Rpc {
                 }
                 tracing::error!(
                     "Error occurred while processing instructions: {:?}",
-                    instructions
+                    txn.message.instructions
                 );
-                confirmed = false;
+                // An error answered by the node means it refused the transaction; a failed
+                // request may still have reached it.
+                outcome = match err.kind() {
+                    ErrorKind::RpcError(RpcError::RpcResponseError { .. }) => {
+                        SendOutcome::Rejected(err.to_string())
+                    }
+                    _ => SendOutcome::Unknown,
+                };
             }
         };
 
-        Ok((confirmed, sig))
+        if outcome != SendOutcome::Confirmed {
+            if skip_preflight && sig != Signature::default() {
+                tracing::error!(
+                    "transaction {} was sent without preflight and did not confirm",
+                    sig
+                );
+            }
+            self.invalidate_blockhash();
+        }
+
+        Ok((outcome, sig))
+    }
+
+    /// Signs and sends `instructions` without waiting for confirmation.
+    ///
+    /// Returns as soon as the node accepted the transaction, so the caller can time the send
+    /// and the confirmation apart with `confirm_signature`.
+    pub async fn send_transaction(
+        &self,
+        signer: &dyn TransactionSigner,
+        instructions: &[Instruction],
+        skip_preflight: bool,
+    ) -> anyhow::Result<Signature> {
+        let recent_hash = self.latest_blockhash().await?;
+        let txn = sign_transaction(signer, instructions, recent_hash).await?;
+
+        Ok(self
+            .inner()
+            .send_transaction_with_config(
+                &txn,
+                RpcSendTransactionConfig {
+                    skip_preflight,
+                    max_retries: None,
+                    preflight_commitment: Some(self.inner().commitment().commitment),
+                    encoding: None,
+                    min_context_slot: None,
+                },
+            )
+            .await?)
+    }
+}
+
+/// Sends `account` on `sender` unless its data equals `last_data`.
+///
+/// Returns `false` once the receiving stream was dropped.
+fn forward_account(
+    sender: &UnboundedSender<(u64, Account)>,
+    last_data: &mut Option<Vec<u8>>,
+    slot: u64,
+    account: Account,
+) -> bool {
+    if last_data.as_ref() == Some(&account.data) {
+        return true;
     }
+    *last_data = Some(account.data.clone());
+    sender.unbounded_send((slot, account)).is_ok()
 }
 
 /// Implement the Debug trait for the wrapper type `Rpc`.
@@ -439,6 +1402,7 @@ impl fmt::Debug for Rpc {
         // Include relevant information about RpcClient
         f.debug_struct("RpcClient")
             .field("commitment", &self.inner().commitment())
+            .field("rate_limit", &self.limiter.rate())
             .finish()
     }
 }
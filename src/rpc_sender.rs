@@ -0,0 +1,356 @@
+//! This module implements the `RpcSender` used by `Rpc` clients built from a URL.
+//!
+//! Every JSON-RPC request issued through the wrapped `RpcClient` goes through [`ManagedSender`],
+//! which lets the crate pace requests, wait out rate limiting and recover dropped connections
+//! without touching each call site.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use solana_client::{
+    client_error::{ClientError, ErrorKind, Result as ClientResult},
+    http_sender::HttpSender,
+    rpc_request::RpcRequest,
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+
+/// Token bucket shared by every clone of an `Rpc` client.
+///
+/// The bucket holds up to one second worth of requests. When it is empty, callers wait for
+/// the next token instead of failing.
+#[derive(Clone, Debug, Default)]
+pub struct RateLimiter(Arc<Mutex<Bucket>>);
+
+#[derive(Debug)]
+struct Bucket {
+    rate: Option<f64>,
+    tokens: f64,
+    last_refill: Instant,
+    paused_until: Option<Instant>,
+}
+
+impl Default for Bucket {
+    fn default() -> Self {
+        Self {
+            rate: None,
+            tokens: 0.0,
+            last_refill: Instant::now(),
+            paused_until: None,
+        }
+    }
+}
+
+impl Bucket {
+    /// Takes a token if one is available, otherwise returns how long to wait for the next one.
+    fn try_take(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        if let Some(until) = self.paused_until {
+            if now < until {
+                return Some(until - now);
+            }
+            self.paused_until = None;
+        }
+        let rate = self.rate?;
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.last_refill = now;
+        self.tokens = (self.tokens + elapsed * rate).min(rate.max(1.0));
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - self.tokens) / rate))
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Sets the maximum number of requests per second, `0` disables the limit.
+    pub fn set_rate(&self, requests_per_second: u32) {
+        let mut bucket = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if requests_per_second == 0 {
+            bucket.rate = None;
+        } else {
+            let rate = requests_per_second as f64;
+            bucket.rate = Some(rate);
+            bucket.tokens = rate;
+            bucket.last_refill = Instant::now();
+        }
+    }
+
+    /// Returns the configured number of requests per second, if limited.
+    pub fn rate(&self) -> Option<f64> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).rate
+    }
+
+    /// Holds every request back for `delay`, e.g. when the provider answered HTTP 429. Applies
+    /// even without a configured rate.
+    pub fn pause(&self, delay: Duration) {
+        let mut bucket = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let until = Instant::now() + delay;
+        bucket.paused_until = Some(
+            bucket
+                .paused_until
+                .map_or(until, |paused| paused.max(until)),
+        );
+    }
+
+    /// Waits until a request may be sent.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = self.0.lock().unwrap_or_else(|e| e.into_inner()).try_take();
+            match wait {
+                None => return,
+                Some(delay) => tokio::time::sleep(delay).await,
+            }
+        }
+    }
+}
+
+/// Switch shared by every clone of an `Rpc` client, see [`ManagedSender`]. On by default.
+#[derive(Clone, Debug)]
+pub struct Reconnect(Arc<AtomicBool>);
+
+impl Default for Reconnect {
+    fn default() -> Self {
+        Self(Arc::new(AtomicBool::new(true)))
+    }
+}
+
+impl Reconnect {
+    /// Enables or disables reconnecting.
+    pub fn set(&self, enabled: bool) {
+        self.0.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Returns whether reconnecting is enabled.
+    pub fn enabled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Durations of the requests sent by every clone of an `Rpc` client, grouped by method.
+///
+/// Disabled by default; while disabled nothing is recorded or logged. Durations are counted in
+/// a fixed-size histogram per method, so a long-running loop does not grow its memory.
+#[derive(Clone, Debug, Default)]
+pub struct Profiler(Arc<Mutex<Option<BTreeMap<String, Histogram>>>>);
+
+/// Latency summary of one RPC method, see [`Profiler::summary`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcCallStats {
+    /// The JSON-RPC method, e.g. `getAccountInfo`.
+    pub method: String,
+    /// Number of requests sent.
+    pub count: usize,
+    /// Median duration, within the resolution of the histogram.
+    pub p50: Duration,
+    /// 99th percentile duration, within the resolution of the histogram.
+    pub p99: Duration,
+    /// Time spent in all the requests.
+    pub total: Duration,
+}
+
+/// Histogram buckets per doubling of the duration, i.e. a resolution of about 9%.
+const HISTOGRAM_STEPS_PER_OCTAVE: f64 = 8.0;
+
+/// Number of buckets, covering 1 µs to about 2 minutes; slower requests go in the last one.
+const HISTOGRAM_BUCKETS: usize = 27 * HISTOGRAM_STEPS_PER_OCTAVE as usize;
+
+/// Request durations of one method, bucketed on a log scale.
+#[derive(Clone, Debug)]
+struct Histogram {
+    buckets: [u64; HISTOGRAM_BUCKETS],
+    count: usize,
+    total: Duration,
+    max: Duration,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: [0; HISTOGRAM_BUCKETS],
+            count: 0,
+            total: Duration::ZERO,
+            max: Duration::ZERO,
+        }
+    }
+}
+
+impl Histogram {
+    fn record(&mut self, elapsed: Duration) {
+        let micros = elapsed.as_micros().max(1) as f64;
+        let bucket = (micros.log2() * HISTOGRAM_STEPS_PER_OCTAVE) as usize;
+        self.buckets[bucket.min(HISTOGRAM_BUCKETS - 1)] += 1;
+        self.count += 1;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+    }
+
+    /// Nearest-rank percentile, as the upper bound of its bucket capped at the slowest request.
+    fn percentile(&self, p: f64) -> Duration {
+        let rank = ((p * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let upper = 2f64.powf((bucket + 1) as f64 / HISTOGRAM_STEPS_PER_OCTAVE);
+                return Duration::from_micros(upper as u64).min(self.max);
+            }
+        }
+        self.max
+    }
+}
+
+impl Profiler {
+    /// Starts recording request durations.
+    pub fn enable(&self) {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_or_insert_with(BTreeMap::new);
+    }
+
+    /// Returns whether request durations are recorded.
+    pub fn enabled(&self) -> bool {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).is_some()
+    }
+
+    /// Records that a `method` request took `elapsed`, if enabled.
+    pub fn record(&self, method: &str, elapsed: Duration) {
+        if let Some(calls) = self.0.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            calls.entry(method.to_string()).or_default().record(elapsed);
+        }
+    }
+
+    /// Returns the latency of every method called so far, slowest total first.
+    pub fn summary(&self) -> Vec<RpcCallStats> {
+        let calls = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let mut stats: Vec<RpcCallStats> = calls
+            .iter()
+            .flatten()
+            .map(|(method, histogram)| RpcCallStats {
+                method: method.clone(),
+                count: histogram.count,
+                p50: histogram.percentile(0.5),
+                p99: histogram.percentile(0.99),
+                total: histogram.total,
+            })
+            .collect();
+        stats.sort_by(|a, b| b.total.cmp(&a.total));
+        stats
+    }
+}
+
+/// Returns `true` for errors where the request never got an answer because the connection
+/// failed (refused, reset, TLS failure), as opposed to an RPC or timeout error.
+fn is_connection_error(err: &ClientError) -> bool {
+    match err.kind() {
+        ErrorKind::Io(_) => true,
+        ErrorKind::Reqwest(err) => err.is_connect() || (err.is_request() && !err.is_timeout()),
+        _ => false,
+    }
+}
+
+/// `RpcSender` wrapping the default HTTP transport with client-side rate limiting.
+///
+/// The wrapped `HttpSender` retries a request answered with HTTP 429 up to 5 times, after the
+/// delay of its `Retry-After` header or 500 ms without one. Those waits only hold back the
+/// throttled request, so once it returns the shared rate limiter is paused for as long as the
+/// sender was rate limited, and the other requests back off too instead of adding to the
+/// throttling.
+///
+/// When a request fails at the connection level and reconnecting is enabled, the HTTP sender,
+/// and with it the connection pool, is rebuilt and the request is retried once.
+pub struct ManagedSender {
+    url: String,
+    http: RwLock<Arc<HttpSender>>,
+    limiter: RateLimiter,
+    reconnect: Reconnect,
+    profiler: Profiler,
+}
+
+impl ManagedSender {
+    /// Creates a sender for `url` sharing the given rate limiter, reconnect switch and profiler.
+    pub fn new(
+        url: String,
+        limiter: RateLimiter,
+        reconnect: Reconnect,
+        profiler: Profiler,
+    ) -> Self {
+        Self {
+            http: RwLock::new(Arc::new(HttpSender::new(url.clone()))),
+            url,
+            limiter,
+            reconnect,
+            profiler,
+        }
+    }
+
+    fn http(&self) -> Arc<HttpSender> {
+        self.http.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Sends `request` once through the current HTTP sender, pausing the rate limiter if it
+    /// was throttled meanwhile.
+    async fn send_once(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> ClientResult<serde_json::Value> {
+        self.limiter.acquire().await;
+        let http = self.http();
+        let rate_limited_before = http.get_transport_stats().rate_limited_time;
+        // Rate limiter waits are not part of the measured time.
+        let start = Instant::now();
+        let result = http.send(request, params).await;
+        if self.profiler.enabled() {
+            let elapsed = start.elapsed();
+            tracing::debug!("{} took {:?}", request, elapsed);
+            self.profiler.record(&request.to_string(), elapsed);
+        }
+        let rate_limited = http
+            .get_transport_stats()
+            .rate_limited_time
+            .saturating_sub(rate_limited_before);
+        if !rate_limited.is_zero() {
+            tracing::warn!(
+                "{} rate limited by {} for {:?}, holding other requests back as long",
+                request,
+                self.url,
+                rate_limited
+            );
+            self.limiter.pause(rate_limited);
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl RpcSender for ManagedSender {
+    async fn send(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> ClientResult<serde_json::Value> {
+        match self.send_once(request, params.clone()).await {
+            Err(err) if self.reconnect.enabled() && is_connection_error(&err) => {
+                tracing::warn!("{} failed ({}), reconnecting to {}", request, err, self.url);
+                *self.http.write().unwrap_or_else(|e| e.into_inner()) =
+                    Arc::new(HttpSender::new(self.url.clone()));
+                self.send_once(request, params).await
+            }
+            result => result,
+        }
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.http().get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+}
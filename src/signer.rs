@@ -0,0 +1,194 @@
+//! This module abstracts the signing of transactions, so keys may live outside the process.
+//!
+//! A local [`Keypair`] is a [`TransactionSigner`]. Remote signers, such as an HSM or a cloud
+//! KMS holding an ed25519 key, implement the trait by sending the serialized message to the
+//! device and returning the signature it produced; the private key is never exposed.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    message::Message,
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    signer::keypair::Keypair,
+    transaction::Transaction,
+};
+
+/// Signs transaction messages for a single fee payer.
+///
+/// # Examples
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use openbook::pubkey::Pubkey;
+/// use openbook::signature::Signature;
+/// use openbook::signer::TransactionSigner;
+///
+/// /// Forwards messages to a signing service holding the key.
+/// struct RemoteSigner {
+///     pubkey: Pubkey,
+/// }
+///
+/// #[async_trait]
+/// impl TransactionSigner for RemoteSigner {
+///     fn pubkey(&self) -> Pubkey {
+///         self.pubkey
+///     }
+///
+///     async fn sign(&self, message: &[u8]) -> anyhow::Result<Signature> {
+///         // Send `message` to the service and parse the 64 byte signature it returns.
+///         let _ = message;
+///         Err(anyhow::anyhow!("not connected"))
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait TransactionSigner: Send + Sync {
+    /// Returns the public key signatures are made for, the fee payer of every transaction.
+    fn pubkey(&self) -> Pubkey;
+
+    /// Signs the serialized transaction `message` with ed25519.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signer cannot produce a signature, e.g. the remote device is
+    /// unreachable or refused the request.
+    async fn sign(&self, message: &[u8]) -> Result<Signature>;
+}
+
+#[async_trait]
+impl TransactionSigner for Keypair {
+    fn pubkey(&self) -> Pubkey {
+        Signer::pubkey(self)
+    }
+
+    async fn sign(&self, message: &[u8]) -> Result<Signature> {
+        Ok(self.sign_message(message))
+    }
+}
+
+/// Builds a transaction of `instructions` paid by `signer` and signs it.
+///
+/// Only the fee payer may sign, so instructions that need another signer are refused. The
+/// signature is verified before the transaction is returned, so a remote signer holding the
+/// wrong key is caught before anything is sent.
+///
+/// # Errors
+///
+/// Returns an error if another signature is required, if `signer` fails, or if its signature
+/// does not verify against its public key.
+pub async fn sign_transaction(
+    signer: &dyn TransactionSigner,
+    instructions: &[Instruction],
+    recent_blockhash: Hash,
+) -> Result<Transaction> {
+    let payer = signer.pubkey();
+    let message = Message::new_with_blockhash(instructions, Some(&payer), &recent_blockhash);
+    let required = message.header.num_required_signatures;
+    if required != 1 {
+        return Err(anyhow!(
+            "Transaction needs {required} signatures, only the fee payer {payer} can sign"
+        ));
+    }
+
+    let message_bytes = message.serialize();
+    let signature = signer.sign(&message_bytes).await?;
+    if !signature.verify(payer.as_ref(), &message_bytes) {
+        return Err(anyhow!(
+            "Signature {signature} does not verify against the signer's public key {payer}"
+        ));
+    }
+
+    Ok(Transaction {
+        signatures: vec![signature],
+        message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v1::{market::Market, ob_client::OBClient};
+    use solana_sdk::instruction::AccountMeta;
+    use std::sync::Arc;
+
+    /// Claims one key but signs with another, like a remote signer holding the wrong key.
+    struct WrongKeySigner {
+        pubkey: Pubkey,
+        key: Keypair,
+    }
+
+    #[async_trait]
+    impl TransactionSigner for WrongKeySigner {
+        fn pubkey(&self) -> Pubkey {
+            self.pubkey
+        }
+
+        async fn sign(&self, message: &[u8]) -> Result<Signature> {
+            Ok(self.key.sign_message(message))
+        }
+    }
+
+    fn memo(signers: &[Pubkey]) -> Instruction {
+        let accounts = signers
+            .iter()
+            .map(|signer| AccountMeta::new_readonly(*signer, true))
+            .collect();
+        Instruction::new_with_bytes(Pubkey::new_unique(), b"memo", accounts)
+    }
+
+    #[tokio::test]
+    async fn signs_for_the_fee_payer() {
+        let signer = Keypair::new();
+        let payer = Signer::pubkey(&signer);
+
+        let txn = sign_transaction(&signer, &[memo(&[payer])], Hash::new_unique())
+            .await
+            .unwrap();
+
+        assert_eq!(txn.signatures.len(), 1);
+        txn.verify().unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_a_signature_that_does_not_verify() {
+        let signer = WrongKeySigner {
+            pubkey: Pubkey::new_unique(),
+            key: Keypair::new(),
+        };
+
+        let err = sign_transaction(&signer, &[memo(&[])], Hash::new_unique())
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("does not verify"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn rejects_a_message_needing_another_signature() {
+        let signer = Keypair::new();
+        let other = Pubkey::new_unique();
+
+        let err = sign_transaction(&signer, &[memo(&[other])], Hash::new_unique())
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("needs 2 signatures"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn rejects_a_signer_for_another_wallet() {
+        let mut ob_client =
+            OBClient::offline(Market::default(), Keypair::new(), Pubkey::new_unique()).unwrap();
+
+        let err = ob_client
+            .set_transaction_signer(Arc::new(Keypair::new()))
+            .unwrap_err();
+        assert!(err.to_string().contains("does not match"), "{err}");
+
+        let owner = ob_client.owner.insecure_clone();
+        ob_client.set_transaction_signer(Arc::new(owner)).unwrap();
+    }
+}
@@ -31,7 +31,10 @@ use crate::rpc::Rpc;
 use crate::rpc_client::RpcClient;
 use crate::utils::read_keypair;
 #[cfg(feature = "v1")]
-use crate::v1::{ob_client::OBClient as OBClientV1, orders::OrderReturnType};
+use crate::v1::{
+    ob_client::OBClient as OBClientV1,
+    orders::{NamedParam, OrderReturnType},
+};
 #[cfg(feature = "v2")]
 use crate::v2::market::CreateMarketArgs;
 #[cfg(feature = "v2")]
@@ -613,10 +616,12 @@ async fn run_app<B: Backend>(
                             SelectedTab::Tab3 => {}
                             SelectedTab::Tab4 => match app.ob_client.clone().unwrap() {
                                 SdkClient::OBClientV1(ob_client) => {
-                                    let side = match app.side_input.value() {
-                                        "bid" => OBV1Side::Bid,
-                                        "ask" => OBV1Side::Ask,
-                                        _ => OBV1Side::Bid,
+                                    let side = match OBV1Side::from_name(app.side_input.value()) {
+                                        Ok(side) => side,
+                                        Err(err) => {
+                                            app.transaction_status = err.to_string();
+                                            continue;
+                                        }
                                     };
                                     let price =
                                         app.target_price_input.value().parse::<f64>().unwrap();
@@ -625,7 +630,8 @@ async fn run_app<B: Backend>(
                                         .place_limit_order(5.0, side, 5.0, true, price)
                                         .await?;
                                     match result {
-                                        Some(OrderReturnType::Signature(signature)) => {
+                                        Some(OrderReturnType::Signature(signature))
+                                        | Some(OrderReturnType::Placed(signature, _)) => {
                                             app.transaction_status = format!(
                                                 "Transaction successful, signature: {:?}",
                                                 signature
@@ -1038,7 +1044,11 @@ fn ui(frame: &mut Frame, app: &mut App, version: SdkVersion) {
             }
         })
         .scroll((0, scroll as u16))
-        .block(Block::default().borders(Borders::ALL).title("🛍️ Bid or Ask"));
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("🛍️ Bid or Ask"),
+        );
 
     let scroll = app.target_price_input.visual_scroll(width as usize);
     let target_price_input = Paragraph::new(app.target_price_input.value())
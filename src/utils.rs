@@ -1,7 +1,12 @@
 //! This module contains utility functions related openbook.
 
 use crate::{bs58, keypair::Keypair};
-use solana_sdk::{account::Account, account_info::AccountInfo, pubkey::Pubkey};
+use base64::Engine;
+use serde::Serializer;
+use solana_sdk::{
+    account::Account, account_info::AccountInfo, instruction::Instruction, message::Message,
+    pubkey::Pubkey, signature::Signature,
+};
 use std::{fs, time::SystemTime, time::UNIX_EPOCH};
 
 /// Converts a slice of `u64` values into a fixed-size byte array.
@@ -86,6 +91,51 @@ pub fn get_unix_secs() -> u64 {
         .as_secs()
 }
 
+/// Reads the token amount of an SPL token account from its raw data.
+///
+/// The amount follows the mint and the owner, at bytes 64 to 72, in both the token program and
+/// Token-2022 layouts.
+///
+/// # Returns
+///
+/// The amount in native units, `None` if `data` is too short to be a token account.
+///
+/// # Examples
+///
+/// ```rust
+/// use openbook::utils::token_account_amount;
+///
+/// let mut data = vec![0u8; 165];
+/// data[64..72].copy_from_slice(&42u64.to_le_bytes());
+/// assert_eq!(token_account_amount(&data), Some(42));
+/// ```
+pub fn token_account_amount(data: &[u8]) -> Option<u64> {
+    let bytes = data.get(64..72)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// Compiles `instructions` into a legacy `Message` paid by `fee_payer`, encoded in base64.
+///
+/// This is the format multisig tools such as Squads import to propose a transaction: the
+/// message is unsigned and its blockhash is left empty, to be filled in when the proposal is
+/// executed. Every signer of the instructions, e.g. the owner of an open orders account, must
+/// be an account the multisig can sign for.
+///
+/// # Examples
+///
+/// ```rust
+/// use openbook::pubkey::Pubkey;
+/// use openbook::utils::message_base64;
+///
+/// let vault = Pubkey::new_unique();
+/// let encoded = message_base64(&[], &vault);
+/// assert!(!encoded.is_empty());
+/// ```
+pub fn message_base64(instructions: &[Instruction], fee_payer: &Pubkey) -> String {
+    let message = Message::new(instructions, Some(fee_payer));
+    base64::engine::general_purpose::STANDARD.encode(message.serialize())
+}
+
 /// Creates an `AccountInfo` instance from an `Account`.
 ///
 /// # Arguments
@@ -152,3 +202,35 @@ pub fn create_account_info_from_account<'a>(
         account.rent_epoch,
     )
 }
+
+/// Serializes a `Pubkey` as its base58 string.
+///
+/// Meant to be used with `#[serde(serialize_with = "serialize_pubkey")]` so that JSON output
+/// shows addresses instead of raw byte arrays.
+///
+/// # Examples
+///
+/// ```rust
+/// use openbook::pubkey::Pubkey;
+/// use openbook::utils::serialize_pubkey;
+///
+/// #[derive(serde::Serialize)]
+/// struct Account {
+///     #[serde(serialize_with = "serialize_pubkey")]
+///     key: Pubkey,
+/// }
+///
+/// let json = serde_json::to_string(&Account { key: Pubkey::default() }).unwrap();
+/// assert_eq!(json, r#"{"key":"11111111111111111111111111111111"}"#);
+/// ```
+pub fn serialize_pubkey<S: Serializer>(pubkey: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&pubkey.to_string())
+}
+
+/// Serializes a `Signature` as its base58 string, for use with `#[serde(serialize_with)]`.
+pub fn serialize_signature<S: Serializer>(
+    signature: &Signature,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&signature.to_string())
+}
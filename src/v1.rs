@@ -1,4 +1,8 @@
+pub mod book;
+pub mod decode;
+pub mod event_queue;
 pub mod market;
 pub mod ob_client;
 pub mod orders;
+pub mod record;
 pub mod traits;
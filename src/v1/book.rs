@@ -0,0 +1,210 @@
+//! This module contains the decoded order book snapshot of an OpenBook v1 market.
+
+use crate::{
+    utils::{serialize_pubkey, u64_slice_to_pubkey},
+    v1::market::Market,
+};
+use openbook_dex::{critbit::Slab, matching::Side};
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+/// A single resting order read from the bids or asks slab.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct BookOrder {
+    /// The on-book order id.
+    pub order_id: u128,
+    /// The client order id supplied when the order was placed.
+    pub client_order_id: u64,
+    /// The open orders account owning the order.
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub owner: Pubkey,
+    /// The limit price in quote lots per base lot.
+    pub price_lots: u64,
+    /// The remaining size in base lots.
+    pub quantity_lots: u64,
+    /// The limit price in UI units.
+    pub price: f64,
+    /// The remaining size in base UI units.
+    pub quantity: f64,
+}
+
+/// Snapshot of both sides of the order book, best price first.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OrderBook {
+    /// Resting bids, sorted by descending price.
+    pub bids: Vec<BookOrder>,
+    /// Resting asks, sorted by ascending price.
+    pub asks: Vec<BookOrder>,
+}
+
+/// Best prices of the book and the size resting at them, see `OrderBook::top`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct TopOfBook {
+    /// Highest bid price, if any.
+    pub best_bid: Option<f64>,
+    /// Base size resting at the best bid.
+    pub bid_size: f64,
+    /// Lowest ask price, if any.
+    pub best_ask: Option<f64>,
+    /// Base size resting at the best ask.
+    pub ask_size: f64,
+}
+
+impl TopOfBook {
+    /// Returns the midpoint between the best bid and ask, if both sides are quoted.
+    pub fn mid(&self) -> Option<f64> {
+        Some((self.best_bid? + self.best_ask?) / 2.0)
+    }
+
+    /// Returns the best ask minus the best bid, if both sides are quoted.
+    pub fn spread(&self) -> Option<f64> {
+        Some(self.best_ask? - self.best_bid?)
+    }
+
+    /// Returns the spread in bps of the mid, if both sides are quoted.
+    pub fn spread_bps(&self) -> Option<f64> {
+        Some(self.spread()? / self.mid()? * 10_000.0)
+    }
+}
+
+/// Expected outcome of crossing the book with a market order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct FillEstimate {
+    /// Best opposing price at the time of the snapshot.
+    pub best_price: f64,
+    /// Volume-weighted average fill price.
+    pub avg_price: f64,
+    /// Price of the last level touched by the order.
+    pub worst_price: f64,
+    /// Base amount that would be filled, in UI units.
+    pub filled_base: f64,
+    /// Quote amount that would be spent or received, in UI units.
+    pub filled_quote: f64,
+    /// Quote amount left unfilled because the book ran out of depth.
+    pub unfilled_quote: f64,
+    /// Distance between the average fill price and the best price, in bps.
+    pub slippage_bps: f64,
+}
+
+impl OrderBook {
+    /// Returns the highest resting bid, if any.
+    pub fn best_bid(&self) -> Option<&BookOrder> {
+        self.bids.first()
+    }
+
+    /// Returns the lowest resting ask, if any.
+    pub fn best_ask(&self) -> Option<&BookOrder> {
+        self.asks.first()
+    }
+
+    /// Returns the midpoint between the best bid and the best ask, if both sides are quoted.
+    pub fn mid(&self) -> Option<f64> {
+        Some((self.best_bid()?.price + self.best_ask()?.price) / 2.0)
+    }
+
+    /// Returns the best bid and ask with the total size resting at each.
+    pub fn top(&self) -> TopOfBook {
+        let best = |orders: &[BookOrder]| {
+            let best = orders.first()?;
+            let size = orders
+                .iter()
+                .take_while(|order| order.price_lots == best.price_lots)
+                .map(|order| order.quantity)
+                .sum();
+            Some((best.price, size))
+        };
+        let (best_bid, bid_size) = best(&self.bids).unzip();
+        let (best_ask, ask_size) = best(&self.asks).unzip();
+        TopOfBook {
+            best_bid,
+            bid_size: bid_size.unwrap_or_default(),
+            best_ask,
+            ask_size: ask_size.unwrap_or_default(),
+        }
+    }
+
+    /// Returns the resting orders on the given side.
+    pub fn side(&self, side: Side) -> &[BookOrder] {
+        match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+
+    /// Walks the opposing side of the book to estimate the fill of a market order.
+    ///
+    /// # Arguments
+    ///
+    /// * `side` - The side of the market order (`Bid` takes asks, `Ask` takes bids).
+    /// * `size_quote` - The order size in quote UI units.
+    ///
+    /// # Returns
+    ///
+    /// A `FillEstimate` computed from this snapshot. Fees are not included.
+    pub fn estimate_fill(&self, side: Side, size_quote: f64) -> FillEstimate {
+        let levels = match side {
+            Side::Bid => &self.asks,
+            Side::Ask => &self.bids,
+        };
+
+        let mut estimate = FillEstimate {
+            best_price: levels.first().map(|o| o.price).unwrap_or_default(),
+            ..FillEstimate::default()
+        };
+
+        let mut remaining = size_quote;
+        for order in levels {
+            if remaining <= 0.0 {
+                break;
+            }
+            let level_quote = order.price * order.quantity;
+            let take_quote = remaining.min(level_quote);
+            if take_quote <= 0.0 {
+                continue;
+            }
+            estimate.filled_quote += take_quote;
+            estimate.filled_base += take_quote / order.price;
+            estimate.worst_price = order.price;
+            remaining -= take_quote;
+        }
+
+        estimate.unfilled_quote = remaining.max(0.0);
+        if estimate.filled_base > 0.0 {
+            estimate.avg_price = estimate.filled_quote / estimate.filled_base;
+        }
+        if estimate.best_price > 0.0 && estimate.avg_price > 0.0 {
+            estimate.slippage_bps =
+                (estimate.avg_price - estimate.best_price).abs() / estimate.best_price * 10_000.0;
+        }
+
+        estimate
+    }
+}
+
+/// Drains a bids or asks slab into a list of `BookOrder`s, best price first.
+///
+/// The slab is consumed, so callers should pass a slab loaded from a copy of the account data.
+pub fn drain_slab(slab: &mut Slab, side: Side, market: &Market) -> Vec<BookOrder> {
+    let mut orders = Vec::new();
+    loop {
+        let node = match side {
+            Side::Bid => slab.remove_max(),
+            Side::Ask => slab.remove_min(),
+        };
+        let Some(node) = node else {
+            break;
+        };
+        let price_lots = node.price().get();
+        let quantity_lots = node.quantity();
+        orders.push(BookOrder {
+            order_id: node.order_id(),
+            client_order_id: node.client_order_id(),
+            owner: Pubkey::from(u64_slice_to_pubkey(node.owner())),
+            price_lots,
+            quantity_lots,
+            price: market.price_lots_to_ui(price_lots),
+            quantity: market.base_lots_to_ui(quantity_lots),
+        });
+    }
+    orders
+}
@@ -0,0 +1,175 @@
+//! This module decodes the OpenBook v1 instructions of a transaction into readable summaries.
+//!
+//! Only top level instructions are decoded; instructions invoked through CPI by another program
+//! are not part of the message.
+
+use crate::v1::market::Market;
+use openbook_dex::instruction::MarketInstruction;
+use solana_sdk::{pubkey::Pubkey, transaction::VersionedTransaction};
+use std::fmt::{Display, Formatter};
+
+/// One OpenBook instruction of a transaction.
+#[derive(Debug, Clone)]
+pub struct DecodedInstruction {
+    /// Position of the instruction in the transaction message.
+    pub index: usize,
+    /// The market the instruction targets, if its account list names one.
+    pub market: Option<Pubkey>,
+    /// The accounts passed to the instruction, `Pubkey::default()` if unresolved.
+    pub accounts: Vec<Pubkey>,
+    /// The decoded instruction, `None` if its data is not a known instruction.
+    pub instruction: Option<MarketInstruction>,
+    /// A one line summary, with UI prices and sizes when the market was known.
+    pub summary: String,
+}
+
+impl Display for DecodedInstruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{} {}", self.index, self.summary)?;
+        if let Some(market) = self.market {
+            write!(f, " (market {})", market)?;
+        }
+        Ok(())
+    }
+}
+
+/// Decodes the instructions of `transaction` sent to `program_id`.
+///
+/// # Arguments
+///
+/// * `transaction` - The transaction to decode.
+/// * `loaded_addresses` - The writable then readonly addresses loaded from lookup tables, as
+///   reported in the transaction meta. Empty for legacy transactions.
+/// * `program_id` - The DEX program whose instructions are decoded.
+/// * `market` - A market whose instructions are shown in UI units as well as lots.
+///
+/// # Returns
+///
+/// The DEX instructions in message order; others are skipped.
+pub fn decode_instructions(
+    transaction: &VersionedTransaction,
+    loaded_addresses: &[Pubkey],
+    program_id: &Pubkey,
+    market: Option<&Market>,
+) -> Vec<DecodedInstruction> {
+    let keys: Vec<Pubkey> = transaction
+        .message
+        .static_account_keys()
+        .iter()
+        .chain(loaded_addresses)
+        .copied()
+        .collect();
+    let key = |index: u8| keys.get(index as usize).copied().unwrap_or_default();
+
+    transaction
+        .message
+        .instructions()
+        .iter()
+        .enumerate()
+        .filter(|(_, ix)| key(ix.program_id_index) == *program_id)
+        .map(|(index, ix)| {
+            let accounts: Vec<Pubkey> = ix.accounts.iter().map(|&i| key(i)).collect();
+            let instruction = MarketInstruction::unpack(&ix.data);
+            let market_key = instruction
+                .as_ref()
+                .and_then(|instruction| market_account_index(instruction, accounts.len()))
+                .and_then(|i| accounts.get(i).copied());
+            let ui_market = market.filter(|market| Some(market.market_address) == market_key);
+            let summary = match &instruction {
+                Some(instruction) => summarize(instruction, ui_market),
+                None => format!("Unknown instruction ({} bytes)", ix.data.len()),
+            };
+            DecodedInstruction {
+                index,
+                market: market_key,
+                accounts,
+                instruction,
+                summary,
+            }
+        })
+        .collect()
+}
+
+/// Returns the position of the market in the account list of `instruction`.
+fn market_account_index(instruction: &MarketInstruction, accounts: usize) -> Option<usize> {
+    match instruction {
+        MarketInstruction::NewOrderV3(_)
+        | MarketInstruction::SendTake(_)
+        | MarketInstruction::CancelOrderV2(_)
+        | MarketInstruction::CancelOrderByClientIdV2(_)
+        | MarketInstruction::CancelOrdersByClientIds(_)
+        | MarketInstruction::SettleFunds
+        | MarketInstruction::MatchOrders(_)
+        | MarketInstruction::Prune(_) => Some(0),
+        // The open orders accounts come first, then the market and three more accounts.
+        MarketInstruction::ConsumeEvents(_) => accounts.checked_sub(4),
+        MarketInstruction::ConsumeEventsPermissioned(_) => accounts.checked_sub(3),
+        MarketInstruction::InitOpenOrders => Some(2),
+        MarketInstruction::CloseOpenOrders => Some(3),
+        _ => None,
+    }
+}
+
+/// Describes `instruction`, converting lots to UI units when `market` is given.
+fn summarize(instruction: &MarketInstruction, market: Option<&Market>) -> String {
+    let price = |lots: u64| match market {
+        Some(market) => format!(
+            "{} ({} lots)",
+            market.format_price(market.price_lots_to_ui(lots)),
+            lots
+        ),
+        None => format!("{} lots", lots),
+    };
+    let size = |lots: u64| match market {
+        Some(market) => format!(
+            "{} ({} lots)",
+            market.format_size(market.base_lots_to_ui(lots)),
+            lots
+        ),
+        None => format!("{} lots", lots),
+    };
+
+    match instruction {
+        MarketInstruction::NewOrderV3(order) => format!(
+            "NewOrder {:?} {:?}: price {}, size {}, max quote {} native, client order id {}, limit {}, max_ts {}",
+            order.side,
+            order.order_type,
+            price(order.limit_price.get()),
+            size(order.max_coin_qty.get()),
+            order.max_native_pc_qty_including_fees,
+            order.client_order_id,
+            order.limit,
+            order.max_ts
+        ),
+        MarketInstruction::SendTake(take) => format!(
+            "SendTake {:?}: price {}, size {}, max quote {} native, min size {} lots, min quote {} native, limit {}",
+            take.side,
+            price(take.limit_price.get()),
+            size(take.max_coin_qty.get()),
+            take.max_native_pc_qty_including_fees,
+            take.min_coin_qty,
+            take.min_native_pc_qty,
+            take.limit
+        ),
+        MarketInstruction::CancelOrderV2(cancel) => {
+            format!("CancelOrder {:?}: order id {}", cancel.side, cancel.order_id)
+        }
+        MarketInstruction::CancelOrderByClientIdV2(client_order_id) => {
+            format!("CancelOrderByClientId: client order id {}", client_order_id)
+        }
+        MarketInstruction::CancelOrdersByClientIds(client_order_ids) => format!(
+            "CancelOrdersByClientIds: client order ids {:?}",
+            client_order_ids
+                .iter()
+                .filter(|&&id| id != 0)
+                .collect::<Vec<_>>()
+        ),
+        MarketInstruction::SettleFunds => "SettleFunds".to_string(),
+        MarketInstruction::MatchOrders(limit) => format!("MatchOrders: limit {}", limit),
+        MarketInstruction::ConsumeEvents(limit) => format!("ConsumeEvents: limit {}", limit),
+        MarketInstruction::ConsumeEventsPermissioned(limit) => {
+            format!("ConsumeEventsPermissioned: limit {}", limit)
+        }
+        other => format!("{:?}", other),
+    }
+}
@@ -0,0 +1,592 @@
+//! This module contains a streaming decoder for the OpenBook v1 event queue account.
+//!
+//! The event queue is a ring buffer that can hold tens of thousands of events on busy markets.
+//! Instead of deserializing it into a `Vec`, [`EventQueueView`] borrows the raw account data and
+//! [`EventQueueIter`] yields pending events one at a time.
+//!
+//! The request queue shares the header layout of the event queue, so [`RequestQueueStats`] is
+//! decoded here as well.
+
+use crate::utils::{serialize_pubkey, u64_slice_to_pubkey};
+use anyhow::{anyhow, Result};
+use openbook_dex::matching::Side;
+use openbook_dex::state::{
+    AccountFlag, Event, EventQueueHeader, EventView, Request, RequestQueueHeader,
+    ACCOUNT_HEAD_PADDING, ACCOUNT_TAIL_PADDING,
+};
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use std::convert::TryInto;
+use std::time::Duration;
+
+/// Summary of the market's event queue state.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct EventQueueStats {
+    /// Raw account flag bits stored on the queue.
+    pub account_flags: u64,
+    /// Current head pointer inside the ring buffer.
+    pub head: u64,
+    /// Number of pending events waiting to be consumed.
+    pub count: u64,
+    /// Monotonic event id.
+    pub seq_num: u64,
+}
+
+impl EventQueueStats {
+    /// Number of bytes at the start of an event queue account needed to decode the stats: the
+    /// head padding and the queue header.
+    pub const HEADER_LEN: usize =
+        ACCOUNT_HEAD_PADDING.len() + std::mem::size_of::<EventQueueHeader>();
+
+    /// Decodes the queue header from the start of an event queue account.
+    ///
+    /// Unlike [`EventQueueView::from_account_data`], only the first [`Self::HEADER_LEN`] bytes
+    /// are needed, so the header can be read with a small `dataSlice`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `data` is shorter than `HEADER_LEN`.
+    pub fn from_account_prefix(data: &[u8]) -> Result<Self> {
+        let header_bytes = data
+            .get(ACCOUNT_HEAD_PADDING.len()..Self::HEADER_LEN)
+            .ok_or_else(|| anyhow!("Event queue header too small ({} bytes)", data.len()))?;
+
+        Ok(Self {
+            account_flags: read_u64_field(header_bytes, 0)?,
+            head: read_u64_field(header_bytes, 8)?,
+            count: read_u64_field(header_bytes, 16)?,
+            seq_num: read_u64_field(header_bytes, 24)?,
+        })
+    }
+
+    /// Returns `true` if events are pending and a consume events call would make progress.
+    pub fn needs_crank(&self) -> bool {
+        self.count > 0
+    }
+}
+
+/// State of the market's request queue, see `OBClient::request_queue_status`.
+///
+/// The program pushes each new order to the request queue and matches it within the same
+/// instruction, so requests pending between transactions point at a stuck or older program.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RequestQueueStats {
+    /// The request queue header.
+    Active {
+        /// Raw account flag bits stored on the queue.
+        account_flags: u64,
+        /// Current head pointer inside the ring buffer.
+        head: u64,
+        /// Number of pending requests.
+        count: u64,
+        /// Sequence number given to the next request.
+        next_seq_num: u64,
+        /// Number of request slots in the queue.
+        capacity: u64,
+    },
+    /// The market has no request queue the program uses, and why.
+    NotApplicable {
+        /// What was found instead of a request queue.
+        reason: String,
+    },
+}
+
+impl RequestQueueStats {
+    /// Decodes the header of a request queue account.
+    ///
+    /// # Returns
+    ///
+    /// `NotApplicable` if the account is too small for a queue header or is not flagged as an
+    /// initialized request queue.
+    pub fn from_account_data(data: &[u8]) -> Self {
+        let header_len = std::mem::size_of::<RequestQueueHeader>();
+        let head_pad = ACCOUNT_HEAD_PADDING.len();
+        let tail_pad = ACCOUNT_TAIL_PADDING.len();
+        let header = match data.get(head_pad..head_pad + header_len) {
+            Some(header) if data.len() >= head_pad + header_len + tail_pad => header,
+            _ => {
+                return Self::NotApplicable {
+                    reason: format!(
+                        "account too small for a request queue ({} bytes)",
+                        data.len()
+                    ),
+                }
+            }
+        };
+        let field = |offset: usize| read_u64_field(header, offset).unwrap_or_default();
+        let account_flags = field(0);
+        let expected = AccountFlag::Initialized as u64 | AccountFlag::RequestQueue as u64;
+        if account_flags & expected != expected {
+            return Self::NotApplicable {
+                reason: format!("account flags 0x{account_flags:x} do not mark a request queue"),
+            };
+        }
+        let ring_len = data.len() - head_pad - header_len - tail_pad;
+
+        Self::Active {
+            account_flags,
+            head: field(8),
+            count: field(16),
+            next_seq_num: field(24),
+            capacity: (ring_len / std::mem::size_of::<Request>()) as u64,
+        }
+    }
+
+    /// Number of pending requests, `None` if the queue is not applicable.
+    pub fn pending(&self) -> Option<u64> {
+        match self {
+            Self::Active { count, .. } => Some(*count),
+            Self::NotApplicable { .. } => None,
+        }
+    }
+}
+
+/// Growth of the event queue between two samples, see `OBClient::crank_status`.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct CrankStatus {
+    /// Time between the two samples.
+    pub elapsed: Duration,
+    /// Pending events at the first sample.
+    pub pending_before: u64,
+    /// Pending events at the second sample.
+    pub pending: u64,
+    /// Events pushed to the queue between the samples, from the `seq_num` delta.
+    pub produced: u64,
+    /// Events consumed by all crankers between the samples.
+    pub consumed: u64,
+    /// Number of event slots in the queue.
+    pub capacity: u64,
+}
+
+impl CrankStatus {
+    /// Builds the status from two samples of the queue header taken `elapsed` apart.
+    pub fn from_samples(
+        before: &EventQueueStats,
+        after: &EventQueueStats,
+        elapsed: Duration,
+        capacity: u64,
+    ) -> Self {
+        let produced = after.seq_num.wrapping_sub(before.seq_num);
+        let consumed = (before.count + produced).saturating_sub(after.count);
+        Self {
+            elapsed,
+            pending_before: before.count,
+            pending: after.count,
+            produced,
+            consumed,
+            capacity,
+        }
+    }
+
+    fn per_sec(&self, events: f64) -> f64 {
+        events / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// Events pushed per second.
+    pub fn inflow_per_sec(&self) -> f64 {
+        self.per_sec(self.produced as f64)
+    }
+
+    /// Events consumed per second.
+    pub fn consumed_per_sec(&self) -> f64 {
+        self.per_sec(self.consumed as f64)
+    }
+
+    /// Growth of the pending count per second, negative while the queue drains.
+    pub fn net_per_sec(&self) -> f64 {
+        self.per_sec(self.pending as f64 - self.pending_before as f64)
+    }
+
+    /// Returns `true` if the pending count did not grow between the samples.
+    pub fn keeping_up(&self) -> bool {
+        self.pending <= self.pending_before
+    }
+
+    /// Time until the queue is empty at the current drain rate, `None` unless it is draining.
+    pub fn time_to_clear(&self) -> Option<Duration> {
+        let net = self.net_per_sec();
+        (net < 0.0).then(|| Duration::from_secs_f64(self.pending as f64 / -net))
+    }
+
+    /// Time until the queue is full at the current growth rate, `None` unless it is growing.
+    pub fn time_to_overflow(&self) -> Option<Duration> {
+        let net = self.net_per_sec();
+        let free = self.capacity.saturating_sub(self.pending) as f64;
+        (net > 0.0).then(|| Duration::from_secs_f64(free / net))
+    }
+}
+
+/// Pending events of one owner on one market, see `OBClient::markets_with_owner_events`.
+#[derive(Debug, Clone)]
+pub struct OwnerMarketEvents {
+    /// The market address.
+    pub market: Pubkey,
+    /// The market's event queue.
+    pub event_queue: Pubkey,
+    /// The market's base vault, passed as the fee receivable account when cranking.
+    pub coin_vault: Pubkey,
+    /// The market's quote vault, passed as the fee receivable account when cranking.
+    pub pc_vault: Pubkey,
+    /// The owner's open orders accounts on the market.
+    pub open_orders: Vec<Pubkey>,
+    /// Number of pending events in the queue, from every owner.
+    pub pending: u64,
+    /// Number of pending events that belong to the owner's open orders accounts.
+    pub owner_events: u64,
+    /// Number of events to crank: through the owner's last event, or up to the first event
+    /// whose account would not fit in the transaction.
+    pub crank_events: u16,
+    /// The open orders accounts to crank with, at most as many as fit in one consume events
+    /// transaction, sorted as the program expects.
+    pub crank_accounts: Vec<Pubkey>,
+}
+
+/// One consume events transaction of a [`CrankPlan`].
+#[derive(Debug, Clone)]
+pub struct CrankPass {
+    /// Number of events the pass consumes, used as its `limit`.
+    pub events: u16,
+    /// The open orders accounts of those events, sorted as the program expects.
+    pub open_orders: Vec<Pubkey>,
+}
+
+/// The consume events transactions needed to drain the event queue, see
+/// `OBClient::crank_plan`.
+#[derive(Debug, Clone)]
+pub struct CrankPlan {
+    /// Number of pending events when the queue was read.
+    pub pending: u64,
+    /// The queue's `seq_num` when it was read; events pushed since are not planned.
+    pub seq_num: u64,
+    /// The passes, in queue order. Each one expects the previous ones to have landed.
+    pub passes: Vec<CrankPass>,
+}
+
+impl CrankPlan {
+    /// Total number of events the passes consume.
+    pub fn events(&self) -> u64 {
+        self.passes.iter().map(|pass| pass.events as u64).sum()
+    }
+}
+
+/// One pending event decoded for an audit record of what a crank consumed, see
+/// `OBClient::consume_events_audited`.
+///
+/// Amounts are signed from the point of view of the open orders account: a fill credits what it
+/// received and debits what it paid. An out event moves nothing, its amount is what it unlocked.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct AuditEvent {
+    /// Sequence number of the event in the queue.
+    pub seq_num: u64,
+    /// `true` for a fill, `false` for an out event (an order leaving the book).
+    pub fill: bool,
+    /// Whether the fill was on the maker side; `false` for out events.
+    pub maker: bool,
+    /// Whether the order is a bid.
+    pub is_bid: bool,
+    /// The open orders account the event is credited to.
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub open_orders: Pubkey,
+    /// The on-book order id.
+    pub order_id: u128,
+    /// The client order id, `0` if none was given.
+    pub client_order_id: u64,
+    /// Base moved, in UI units.
+    pub base: f64,
+    /// Quote moved, in UI units.
+    pub quote: f64,
+    /// Fee paid by a taker, or rebate received by a maker as a negative amount, in quote UI
+    /// units. `0` for out events.
+    pub fee: f64,
+}
+
+/// Borrowed view over the raw bytes of an event queue account.
+#[derive(Debug, Clone, Copy)]
+pub struct EventQueueView<'a> {
+    /// Decoded queue header.
+    pub stats: EventQueueStats,
+    events: &'a [u8],
+    capacity: usize,
+}
+
+impl<'a> EventQueueView<'a> {
+    /// Parses the header of an event queue account without copying the event ring.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The raw account data, including the head and tail paddings.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the account is too small to contain a queue header.
+    pub fn from_account_data(data: &'a [u8]) -> Result<Self> {
+        let header_len = std::mem::size_of::<EventQueueHeader>();
+        let head_pad = ACCOUNT_HEAD_PADDING.len();
+        let tail_pad = ACCOUNT_TAIL_PADDING.len();
+        if data.len() < head_pad + header_len + tail_pad {
+            return Err(anyhow!(
+                "Event queue account too small ({} bytes)",
+                data.len()
+            ));
+        }
+        let usable = &data[head_pad..data.len() - tail_pad];
+        let stats = EventQueueStats::from_account_prefix(data)?;
+
+        let events = &usable[header_len..];
+        let capacity = events.len() / std::mem::size_of::<Event>();
+
+        Ok(Self {
+            stats,
+            events,
+            capacity,
+        })
+    }
+
+    /// Number of event slots in the ring buffer.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Finds the pending events of `open_orders` among the first `limit` events.
+    ///
+    /// `ConsumeEvents` stops at the first event whose owner is not passed in, so the accounts of
+    /// every event ahead of the last matching one are returned as well, sorted by their u64
+    /// words since the program looks them up with a binary search. At most `max_accounts` are
+    /// returned: if the events ahead of the last matching one touch more, the crank stops before
+    /// the event that would exceed the cap, as a pass of [`EventQueueView::crank_plan`] does.
+    ///
+    /// # Returns
+    ///
+    /// The number of matching events, the number of events to crank, and the accounts to crank
+    /// with. The last two are empty if no event matches.
+    pub fn owner_events(
+        &self,
+        open_orders: &[Pubkey],
+        limit: usize,
+        max_accounts: usize,
+    ) -> (u64, usize, Vec<Pubkey>) {
+        let max_accounts = max_accounts.max(1);
+        let mut owners: Vec<[u64; 4]> = Vec::new();
+        let mut capped_at = None;
+        let mut through = 0;
+        let mut events = 0;
+        let mut matched = 0;
+        for (index, event) in self.iter().take(limit).enumerate() {
+            let owner = event.owner;
+            if capped_at.is_none() && !owners.contains(&owner) {
+                if owners.len() == max_accounts {
+                    capped_at = Some(index);
+                } else {
+                    owners.push(owner);
+                }
+            }
+            if open_orders.contains(&Pubkey::from(u64_slice_to_pubkey(owner))) {
+                matched += 1;
+                through = owners.len();
+                events = capped_at.unwrap_or(index + 1);
+            }
+        }
+        owners.truncate(through);
+        owners.sort_unstable();
+
+        let accounts = owners
+            .into_iter()
+            .map(|owner| Pubkey::from(u64_slice_to_pubkey(owner)))
+            .collect();
+        (matched, events, accounts)
+    }
+
+    /// Splits the pending events into consume passes of at most `per_tx_limit` events and
+    /// `max_accounts` distinct open orders accounts.
+    ///
+    /// A pass ends before the event that would exceed either cap, since `ConsumeEvents` stops
+    /// at the first event whose owner is not passed in. Every pending event ends up in exactly
+    /// one pass.
+    pub fn crank_plan(&self, per_tx_limit: u16, max_accounts: usize) -> CrankPlan {
+        let per_tx_limit = per_tx_limit.max(1);
+        let max_accounts = max_accounts.max(1);
+        let mut passes = Vec::new();
+        let mut owners: Vec<[u64; 4]> = Vec::new();
+        let mut events: u16 = 0;
+        for event in self.iter() {
+            let owner = event.owner;
+            let new_owner = !owners.contains(&owner);
+            if events == per_tx_limit || (new_owner && owners.len() == max_accounts) {
+                passes.push(crank_pass(std::mem::take(&mut owners), events));
+                events = 0;
+            }
+            if !owners.contains(&owner) {
+                owners.push(owner);
+            }
+            events += 1;
+        }
+        if events > 0 {
+            passes.push(crank_pass(owners, events));
+        }
+
+        CrankPlan {
+            pending: self.stats.count,
+            seq_num: self.stats.seq_num,
+            passes,
+        }
+    }
+
+    /// Decodes the first `limit` pending events into audit records, converting amounts with
+    /// the market's `coin_decimals` and `pc_decimals`.
+    pub fn audit_events(
+        &self,
+        limit: usize,
+        coin_decimals: u8,
+        pc_decimals: u8,
+    ) -> Vec<AuditEvent> {
+        let base = |native: u64| native as f64 / 10f64.powi(coin_decimals as i32);
+        let quote = |native: u64| native as f64 / 10f64.powi(pc_decimals as i32);
+        // The head event was pushed `count` events before the last one.
+        let head_seq_num = self.stats.seq_num.wrapping_sub(self.stats.count);
+
+        self.iter()
+            .take(limit)
+            .enumerate()
+            .filter_map(|(index, event)| {
+                let seq_num = head_seq_num.wrapping_add(index as u64);
+                Some(match event.as_view().ok()? {
+                    EventView::Fill {
+                        side,
+                        maker,
+                        native_qty_paid,
+                        native_qty_received,
+                        native_fee_or_rebate,
+                        order_id,
+                        owner,
+                        client_order_id,
+                        ..
+                    } => {
+                        let (base_moved, quote_moved) = match side {
+                            Side::Bid => (base(native_qty_received), -quote(native_qty_paid)),
+                            Side::Ask => (-base(native_qty_paid), quote(native_qty_received)),
+                        };
+                        let fee = quote(native_fee_or_rebate);
+                        AuditEvent {
+                            seq_num,
+                            fill: true,
+                            maker,
+                            is_bid: side == Side::Bid,
+                            open_orders: Pubkey::from(u64_slice_to_pubkey(owner)),
+                            order_id,
+                            client_order_id: client_order_id.map_or(0, |id| id.get()),
+                            base: base_moved,
+                            quote: quote_moved,
+                            fee: if maker { -fee } else { fee },
+                        }
+                    }
+                    EventView::Out {
+                        side,
+                        native_qty_unlocked,
+                        order_id,
+                        owner,
+                        client_order_id,
+                        ..
+                    } => {
+                        let (base_unlocked, quote_unlocked) = match side {
+                            Side::Bid => (0.0, quote(native_qty_unlocked)),
+                            Side::Ask => (base(native_qty_unlocked), 0.0),
+                        };
+                        AuditEvent {
+                            seq_num,
+                            fill: false,
+                            maker: false,
+                            is_bid: side == Side::Bid,
+                            open_orders: Pubkey::from(u64_slice_to_pubkey(owner)),
+                            order_id,
+                            client_order_id: client_order_id.map_or(0, |id| id.get()),
+                            base: base_unlocked,
+                            quote: quote_unlocked,
+                            fee: 0.0,
+                        }
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Returns an iterator over the pending events, starting at the queue head.
+    pub fn iter(&self) -> EventQueueIter<'a> {
+        let pending = if self.capacity == 0 {
+            0
+        } else {
+            (self.stats.count as usize).min(self.capacity)
+        };
+        EventQueueIter {
+            events: self.events,
+            capacity: self.capacity,
+            head: self.stats.head as usize,
+            offset: 0,
+            pending,
+        }
+    }
+}
+
+/// Iterator yielding the pending events of an [`EventQueueView`] one record at a time.
+#[derive(Debug, Clone)]
+pub struct EventQueueIter<'a> {
+    events: &'a [u8],
+    capacity: usize,
+    head: usize,
+    offset: usize,
+    pending: usize,
+}
+
+impl Iterator for EventQueueIter<'_> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.pending {
+            return None;
+        }
+        let event_size = std::mem::size_of::<Event>();
+        let idx = (self.head + self.offset) % self.capacity;
+        let start = idx * event_size;
+        let bytes = self.events.get(start..start + event_size)?;
+        self.offset += 1;
+        event_from_bytes(bytes).ok()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.pending - self.offset;
+        (0, Some(remaining))
+    }
+}
+
+/// Builds a pass of `events` events owned by `owners`, sorted by their u64 words since the
+/// program looks them up with a binary search.
+fn crank_pass(mut owners: Vec<[u64; 4]>, events: u16) -> CrankPass {
+    owners.sort_unstable();
+    CrankPass {
+        events,
+        open_orders: owners
+            .into_iter()
+            .map(|owner| Pubkey::from(u64_slice_to_pubkey(owner)))
+            .collect(),
+    }
+}
+
+pub(crate) fn read_u64_field(bytes: &[u8], offset: usize) -> Result<u64> {
+    let end = offset + 8;
+    let chunk = bytes
+        .get(offset..end)
+        .ok_or_else(|| anyhow!("Missing bytes in event queue header"))?;
+    let arr: [u8; 8] = chunk
+        .try_into()
+        .map_err(|_| anyhow!("Failed to parse event queue header"))?;
+    Ok(u64::from_le_bytes(arr))
+}
+
+fn event_from_bytes(bytes: &[u8]) -> Result<Event> {
+    if bytes.len() < std::mem::size_of::<Event>() {
+        return Err(anyhow!("Incomplete event record"));
+    }
+    // SAFETY: Event is #[repr(packed)] and Copy, so read_unaligned is safe.
+    let event = unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const Event) };
+    Ok(event)
+}
@@ -1,12 +1,16 @@
 //! This module contains structs and functions related to the openbook market.
 use crate::{
     rpc::Rpc,
-    utils::{create_account_info_from_account, u64_slice_to_pubkey},
-    v1::traits::MarketInfo,
+    utils::{create_account_info_from_account, serialize_pubkey, u64_slice_to_pubkey},
+    v1::{
+        ob_client::{BASE_DECIMALS_ENV, QUOTE_DECIMALS_ENV},
+        traits::MarketInfo,
+    },
 };
-use anyhow::{Error, Result};
+use anyhow::{anyhow, Error, Result};
 use borsh::{BorshDeserialize, BorshSerialize};
 use openbook_dex::state::{gen_vault_signer_key, MarketState};
+use serde::Serialize;
 use solana_sdk::{
     pubkey::Pubkey,
     sysvar::slot_history::{AccountInfo, ProgramError},
@@ -14,13 +18,21 @@ use solana_sdk::{
 
 use std::fmt::{Debug, Formatter};
 
+/// Offset of the `decimals` byte in an SPL token mint account.
+pub(crate) const MINT_DECIMALS_OFFSET: usize = 44;
+
+/// Most decimal places shown for a price, enough for any tick of a 9/6 decimals market.
+const MAX_PRICE_DECIMALS: usize = 12;
+
 /// Struct representing a market with associated state and information.
-#[derive(Clone, Default, BorshSerialize, BorshDeserialize)]
+#[derive(Clone, Default, BorshSerialize, BorshDeserialize, Serialize)]
 pub struct Market {
     /// The public key of the program associated with the market.
+    #[serde(serialize_with = "serialize_pubkey")]
     pub program_id: Pubkey,
 
     /// The public key of the market.
+    #[serde(serialize_with = "serialize_pubkey")]
     pub market_address: Pubkey,
 
     /// The number of decimal places for the base currency (coin) in the market.
@@ -39,33 +51,46 @@ pub struct Market {
     pub pc_lot_size: u64,
 
     /// The public key of the market quote mint.
+    #[serde(serialize_with = "serialize_pubkey")]
     pub quote_mint: Pubkey,
 
     /// The public key of the market base mint.
+    #[serde(serialize_with = "serialize_pubkey")]
     pub base_mint: Pubkey,
 
     /// The public key of the vault holding base currency (coin) tokens.
+    #[serde(serialize_with = "serialize_pubkey")]
     pub coin_vault: Pubkey,
 
     /// The public key of the vault holding quote currency (pc) tokens.
+    #[serde(serialize_with = "serialize_pubkey")]
     pub pc_vault: Pubkey,
 
     /// The public key of the vault signer key associated with the market.
+    #[serde(serialize_with = "serialize_pubkey")]
     pub vault_signer_key: Pubkey,
 
+    /// The nonce the vault signer key is derived with, read from the market account.
+    pub vault_signer_nonce: u64,
+
     /// The public key of the event queue associated with the market.
+    #[serde(serialize_with = "serialize_pubkey")]
     pub event_queue: Pubkey,
 
     /// The public key of the request queue associated with the market.
+    #[serde(serialize_with = "serialize_pubkey")]
     pub request_queue: Pubkey,
 
     /// The public key of the bids associated with the market.
+    #[serde(serialize_with = "serialize_pubkey")]
     pub bids_address: Pubkey,
 
     /// The public key of the asks associated with the market.
+    #[serde(serialize_with = "serialize_pubkey")]
     pub asks_address: Pubkey,
 
     /// The public key of the events authority used for consume transactions.
+    #[serde(serialize_with = "serialize_pubkey")]
     pub events_authority: Pubkey,
 }
 
@@ -84,6 +109,11 @@ impl Debug for Market {
         writeln!(f, "        coin_vault: {:?}", self.coin_vault)?;
         writeln!(f, "        pc_vault: {:?}", self.pc_vault)?;
         writeln!(f, "        vault_signer_key: {:?}", self.vault_signer_key)?;
+        writeln!(
+            f,
+            "        vault_signer_nonce: {:?}",
+            self.vault_signer_nonce
+        )?;
         writeln!(f, "        event_queue: {:?}", self.event_queue)?;
         writeln!(f, "        request_queue: {:?}", self.request_queue)?;
         writeln!(f, "        bids_address: {:?}", self.bids_address)?;
@@ -93,6 +123,247 @@ impl Debug for Market {
     }
 }
 
+/// The valid prices nearest to a mid price, see `Market::tick_ladder`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TickLadder {
+    /// The mid price the ladder is built around, in UI units.
+    pub mid: f64,
+    /// Whether the mid itself is a valid price.
+    pub mid_on_tick: bool,
+    /// Valid prices above the mid as (price lots, UI price), nearest first.
+    pub above: Vec<(u64, f64)>,
+    /// Valid prices below the mid as (price lots, UI price), nearest first. Shorter than asked
+    /// when the ladder reaches one lot, the lowest price the program accepts.
+    pub below: Vec<(u64, f64)>,
+}
+
+impl Market {
+    /// Returns the factor converting a UI price into quote lots per base lot.
+    pub fn price_factor(&self) -> f64 {
+        let base_d_factor = 10f64.powi(self.coin_decimals as i32);
+        let quote_d_factor = 10f64.powi(self.pc_decimals as i32);
+        quote_d_factor * self.coin_lot_size as f64 / base_d_factor / self.pc_lot_size as f64
+    }
+
+    /// Converts a price expressed in quote lots per base lot into UI units.
+    pub fn price_lots_to_ui(&self, price_lots: u64) -> f64 {
+        price_lots as f64 / self.price_factor()
+    }
+
+    /// Converts a size expressed in base lots into base UI units.
+    pub fn base_lots_to_ui(&self, base_lots: u64) -> f64 {
+        base_lots as f64 * self.coin_lot_size as f64 / 10f64.powi(self.coin_decimals as i32)
+    }
+
+    /// Returns the smallest price increment, one quote lot per base lot, in UI units.
+    pub fn tick_size(&self) -> f64 {
+        self.price_lots_to_ui(1)
+    }
+
+    /// Returns the smallest order size, one base lot, in base UI units.
+    pub fn min_order_size(&self) -> f64 {
+        self.base_lots_to_ui(1)
+    }
+
+    /// Returns the number of decimal places needed to show any price on the tick grid.
+    pub fn price_decimals(&self) -> usize {
+        ui_decimals(self.tick_size(), MAX_PRICE_DECIMALS)
+    }
+
+    /// Returns the number of decimal places needed to show any size on the lot grid.
+    pub fn size_decimals(&self) -> usize {
+        ui_decimals(self.min_order_size(), self.coin_decimals as usize)
+    }
+
+    /// Formats a UI price with the market's price decimals.
+    pub fn format_price(&self, price: f64) -> String {
+        format!("{:.*}", self.price_decimals(), price)
+    }
+
+    /// Formats a base UI size with the market's size decimals.
+    pub fn format_size(&self, size: f64) -> String {
+        format!("{:.*}", self.size_decimals(), size)
+    }
+
+    /// Lists the `levels` valid prices strictly above and below `mid`, one tick apart.
+    ///
+    /// A mid within floating point noise of a tick counts as on the tick, so it is left out
+    /// of both sides rather than showing up as the nearest level.
+    pub fn tick_ladder(&self, mid: f64, levels: usize) -> TickLadder {
+        let mid_lots = mid * self.price_factor();
+        let mid_on_tick = (mid_lots - mid_lots.round()).abs() < 1e-6;
+        let (first_above, first_below) = if mid_on_tick {
+            let lots = mid_lots.round() as u64;
+            (lots + 1, lots.checked_sub(1))
+        } else {
+            let lots = mid_lots.floor() as u64;
+            (lots + 1, Some(lots))
+        };
+        let level = |lots: u64| (lots, self.price_lots_to_ui(lots));
+        TickLadder {
+            mid,
+            mid_on_tick,
+            above: (first_above..).take(levels).map(level).collect(),
+            below: first_below
+                .map(|first| (1..=first).rev().take(levels).map(level).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Initializes a market like [`MarketInfo::new`], using `decimals` as the base and quote
+    /// decimals instead of reading them from the mint accounts.
+    ///
+    /// With `decimals` set the mints are never fetched, so a market whose mints the RPC will not
+    /// return can still be loaded. `None` behaves like [`MarketInfo::new`].
+    #[allow(clippy::too_many_arguments)]
+    pub async fn with_decimals(
+        rpc_client: Rpc,
+        program_id: Pubkey,
+        market_id: Pubkey,
+        base_mint: Pubkey,
+        quote_mint: Pubkey,
+        events_authority: Pubkey,
+        load: bool,
+        decimals: Option<(u8, u8)>,
+    ) -> Result<Self, Error> {
+        let mut market = Self {
+            program_id,
+            coin_decimals: 9,
+            pc_decimals: 6,
+            coin_lot_size: 1_000_000,
+            pc_lot_size: 1,
+            quote_mint,
+            base_mint,
+            events_authority,
+            market_address: market_id,
+            bids_address: Default::default(),
+            asks_address: Default::default(),
+            coin_vault: Default::default(),
+            pc_vault: Default::default(),
+            vault_signer_key: Default::default(),
+            vault_signer_nonce: 0,
+            event_queue: Default::default(),
+            request_queue: Default::default(),
+            account_flags: 0,
+        };
+
+        if load {
+            market.load_with_decimals(&rpc_client, decimals).await?;
+        }
+
+        market.init_vault_signer_key().await?;
+
+        Ok(market)
+    }
+
+    /// Loads the market like [`MarketInfo::load`], using `decimals` instead of the mints' if set.
+    pub async fn load_with_decimals(
+        &mut self,
+        rpc_client: &Rpc,
+        decimals: Option<(u8, u8)>,
+    ) -> Result<(), Error> {
+        let mut account = rpc_client.inner().get_account(&self.market_address).await?;
+        let owner = account.owner;
+        let program_id_binding = self.program_id;
+        let market_account_binding = self.market_address;
+        let account_info;
+        {
+            account_info = create_account_info_from_account(
+                &mut account,
+                &market_account_binding,
+                &program_id_binding,
+                false,
+                false,
+            );
+        }
+        if self.program_id != owner {
+            return Err(ProgramError::InvalidArgument.into());
+        }
+
+        self.load_market_state_info(&account_info).await?;
+        self.load_mint_decimals(rpc_client, decimals).await?;
+
+        Ok(())
+    }
+
+    /// Reads the base and quote decimals from their mint accounts, unless overridden by
+    /// `decimals` or else by the `BASE_DECIMALS` / `QUOTE_DECIMALS` environment variables.
+    async fn load_mint_decimals(
+        &mut self,
+        rpc_client: &Rpc,
+        decimals: Option<(u8, u8)>,
+    ) -> Result<(), Error> {
+        let overrides = match decimals {
+            Some((base, quote)) => [Some(base), Some(quote)],
+            None => [
+                env_decimals(BASE_DECIMALS_ENV)?,
+                env_decimals(QUOTE_DECIMALS_ENV)?,
+            ],
+        };
+        let mints: Vec<Pubkey> = [self.base_mint, self.quote_mint]
+            .into_iter()
+            .zip(overrides)
+            .filter(|(_, decimals)| decimals.is_none())
+            .map(|(mint, _)| mint)
+            .collect();
+        let accounts = if mints.is_empty() {
+            Vec::new()
+        } else {
+            rpc_client
+                .fetch_multiple_accounts(&mints)
+                .await
+                .map_err(|e| anyhow!("Unable to fetch the market's mints: {e}. {DECIMALS_HINT}"))?
+        };
+
+        let mut fetched = mints.iter().zip(accounts);
+        let mut decimals = [self.coin_decimals, self.pc_decimals];
+        for (decimals, overridden) in decimals.iter_mut().zip(overrides) {
+            *decimals = match overridden {
+                Some(overridden) => overridden,
+                None => {
+                    let (mint, account) = fetched
+                        .next()
+                        .ok_or_else(|| anyhow!("Missing mint account. {DECIMALS_HINT}"))?;
+                    account
+                        .as_ref()
+                        .and_then(|account| account.data.get(MINT_DECIMALS_OFFSET).copied())
+                        .ok_or_else(|| {
+                            anyhow!("Unable to read the decimals of mint {mint}. {DECIMALS_HINT}")
+                        })?
+                }
+            };
+        }
+        [self.coin_decimals, self.pc_decimals] = decimals;
+
+        Ok(())
+    }
+}
+
+/// Points to the decimals overrides when the mints cannot be read.
+const DECIMALS_HINT: &str =
+    "Pass --base-decimals / --quote-decimals (BASE_DECIMALS / QUOTE_DECIMALS env vars) to set them";
+
+/// Reads a decimals override from the environment variable `name`, if set.
+fn env_decimals(name: &str) -> Result<Option<u8>> {
+    match std::env::var(name) {
+        Ok(value) => value
+            .parse()
+            .map(Some)
+            .map_err(|e| anyhow!("Invalid {name} {value:?}: {e}")),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Returns the fewest decimal places, up to `max`, that represent `value` exactly.
+fn ui_decimals(value: f64, max: usize) -> usize {
+    (0..max)
+        .find(|&decimals| {
+            let scaled = value * 10f64.powi(decimals as i32);
+            (scaled - scaled.round()).abs() <= scaled.abs() * 1e-9
+        })
+        .unwrap_or(max)
+}
+
 impl MarketInfo for Market {
     /// Initializes a new instance of the `Market` struct.
     ///
@@ -174,33 +445,17 @@ impl MarketInfo for Market {
         events_authority: Pubkey,
         load: bool,
     ) -> Result<Self, Error> {
-        let mut market = Self {
+        Self::with_decimals(
+            rpc_client,
             program_id,
-            coin_decimals: 9,
-            pc_decimals: 6,
-            coin_lot_size: 1_000_000,
-            pc_lot_size: 1,
-            quote_mint,
+            market_id,
             base_mint,
+            quote_mint,
             events_authority,
-            market_address: market_id,
-            bids_address: Default::default(),
-            asks_address: Default::default(),
-            coin_vault: Default::default(),
-            pc_vault: Default::default(),
-            vault_signer_key: Default::default(),
-            event_queue: Default::default(),
-            request_queue: Default::default(),
-            account_flags: 0,
-        };
-
-        if load {
-            market.load(&rpc_client).await?;
-        }
-
-        market.init_vault_signer_key().await?;
-
-        Ok(market)
+            load,
+            None,
+        )
+        .await
     }
 
     /// Loads market information from the provided RPC client.
@@ -261,27 +516,7 @@ impl MarketInfo for Market {
     /// }
     /// ```
     async fn load(&mut self, rpc_client: &Rpc) -> Result<(), Error> {
-        let mut account = rpc_client.inner().get_account(&self.market_address).await?;
-        let owner = account.owner;
-        let program_id_binding = self.program_id;
-        let market_account_binding = self.market_address;
-        let account_info;
-        {
-            account_info = create_account_info_from_account(
-                &mut account,
-                &market_account_binding,
-                &program_id_binding,
-                false,
-                false,
-            );
-        }
-        if self.program_id != owner {
-            return Err(ProgramError::InvalidArgument.into());
-        }
-
-        self.load_market_state_info(&account_info).await?;
-
-        Ok(())
+        self.load_with_decimals(rpc_client, None).await
     }
 
     /// Loads the market state information from the provided account information.
@@ -385,11 +620,21 @@ impl MarketInfo for Market {
         self.coin_lot_size = market_state.coin_lot_size;
         self.pc_lot_size = market_state.pc_lot_size;
 
+        self.vault_signer_nonce = market_state.vault_signer_nonce;
+        self.vault_signer_key = gen_vault_signer_key(
+            self.vault_signer_nonce,
+            &self.market_address,
+            &self.program_id,
+        )?;
+
         Ok(())
     }
 
     /// Initializes the vault signer key.
     ///
+    /// A loaded market already derived it from its `vault_signer_nonce`, which is kept. Otherwise
+    /// the first nonce yielding a valid key is searched for, like the market creation does.
+    ///
     /// # Returns
     ///
     /// Returns `Ok(())` if initialization is successful, or an error otherwise.
@@ -439,9 +684,13 @@ impl MarketInfo for Market {
     /// }
     /// ```
     async fn init_vault_signer_key(&mut self) -> Result<(), Error> {
+        if self.vault_signer_key != Pubkey::default() {
+            return Ok(());
+        }
         for i in 0..100 {
             if let Ok(pk) = gen_vault_signer_key(i, &self.market_address, &self.program_id) {
                 self.vault_signer_key = pk;
+                self.vault_signer_nonce = i;
                 return Ok(());
             }
         }
@@ -1,60 +1,177 @@
 use crate::v1::{
-    market::Market,
-    orders::{OpenOrders, OpenOrdersCacheEntry, OrderReturnType},
+    book::{drain_slab, BookOrder, FillEstimate, OrderBook, TopOfBook},
+    decode::{decode_instructions, DecodedInstruction},
+    event_queue::EventQueueView,
+    market::{Market, MINT_DECIMALS_OFFSET},
+    orders::{
+        decode_order_id, encode_order_id, find_resting_order_id, BookCancelResult,
+        CancelChunkResult, ChunkedCancelResult, FlattenResult, LadderResult, OoBalances,
+        OoSnapshot, OoSummary, OpenOrders, OpenOrdersCacheEntry, OrderOutcome, OrderReturnType,
+        OrderState, PingFillReport, PlaceBothResult, PlaceSpec, QuotePlan, SubmittedOrder,
+    },
+    record::{BookRecord, RECORD_SCHEMA_VERSION},
 };
 use crate::{
-    rpc::Rpc,
-    rpc_client::RpcClient,
-    utils::{create_account_info_from_account, get_unix_secs, read_keypair, u64_slice_to_pubkey},
+    rpc::{Rpc, SendOutcome, DEFAULT_CONFIRM_TIMEOUT},
+    rpc_sender::RpcCallStats,
+    signer::{sign_transaction, TransactionSigner},
+    utils::{
+        create_account_info_from_account, get_unix_secs, read_keypair, serialize_pubkey,
+        serialize_signature, token_account_amount, u64_slice_to_pubkey,
+    },
     v1::traits::{MarketInfo, OpenOrdersT},
 };
 
 use anyhow::{anyhow, Error, Result};
+use futures::{Stream, StreamExt};
 use openbook_dex::{
     critbit::Slab,
-    instruction::SelfTradeBehavior,
+    fees::FeeTier,
+    instruction::{MarketInstruction, SelfTradeBehavior},
     matching::{OrderType, Side},
-    state::{
-        EventQueueHeader, Market as MarketAuth, MarketState, ACCOUNT_HEAD_PADDING,
-        ACCOUNT_TAIL_PADDING,
-    },
+    state::{EventView, Market as MarketAuth, MarketState, MarketStateV2},
 };
 use rand::random;
+use serde::Serialize;
 use solana_sdk::{
+    account::Account,
     commitment_config::CommitmentConfig,
-    compute_budget::ComputeBudgetInstruction,
+    compute_budget::{self, ComputeBudgetInstruction},
+    instruction::Instruction,
+    message::Message,
+    packet::PACKET_DATA_SIZE,
+    program_pack::Pack,
     pubkey::Pubkey,
     signature::{Signature, Signer},
     signer::keypair::Keypair,
+    system_program,
     sysvar::{rent, slot_history::ProgramError},
 };
-use spl_associated_token_account::get_associated_token_address;
+use solana_transaction_status::UiLoadedAddresses;
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
 use std::{
     cell::RefMut,
     collections::{HashMap, HashSet},
-    convert::TryInto,
     fmt::{Debug, Formatter},
     num::NonZeroU64,
     str::FromStr,
     sync::Arc,
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use tracing::debug;
+use tracing::{debug, error, info, warn};
+
+pub use crate::v1::event_queue::{
+    AuditEvent, CrankPass, CrankPlan, CrankStatus, EventQueueStats, OwnerMarketEvents,
+    RequestQueueStats,
+};
 
 pub static SPL_TOKEN_ID: &'static str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
-pub static SRM_PROGRAM_ID: &'static str = "srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX";
+/// The OpenBook v1 program, the default target of the client.
+pub static OPENBOOK_V1_PROGRAM_ID: &'static str = "srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX";
+/// Same as `OPENBOOK_V1_PROGRAM_ID`, the name comes from the `srmq` prefix of the address.
+pub use OPENBOOK_V1_PROGRAM_ID as SRM_PROGRAM_ID;
+/// The legacy Serum v3 program, only targeted when the program id is set explicitly.
 pub static SERUM_V3_PROGRAM_ID: &'static str = "9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin";
+/// The OpenBook v2 program, whose markets this client cannot decode.
+pub static OPENBOOK_V2_PROGRAM_ID: &'static str = "opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb";
 pub static PROGRAM_ID_ENV: &'static str = "PROGRAM_ID";
+pub static RATE_LIMIT_ENV: &'static str = "RPC_RATE_LIMIT";
+/// Decimals of the base mint, used instead of reading the mint account when set.
+pub static BASE_DECIMALS_ENV: &'static str = "BASE_DECIMALS";
+/// Decimals of the quote mint, used instead of reading the mint account when set.
+pub static QUOTE_DECIMALS_ENV: &'static str = "QUOTE_DECIMALS";
+pub static WS_URL_ENV: &'static str = "WS_URL";
+
+/// Number of times `match_orders_transaction` resends an unconfirmed match by default.
+pub const DEFAULT_MATCH_RETRIES: u32 = 3;
+
+/// Slippage tolerance used by the `MarketOrder` command when none is given, in bps.
+pub const DEFAULT_MARKET_SLIPPAGE_BPS: f64 = 50.0;
+
+/// Lifetime of an order's `max_ts` when no `expiry_ts` is set, in seconds.
+pub const DEFAULT_ORDER_TTL_SECS: u64 = 30;
+
+/// Most resting orders a new order matches against by default: no cap, matching only stops at
+/// the transaction's compute limit.
+pub const DEFAULT_MATCH_LIMIT: u16 = u16::MAX;
+
+/// Deviation from the reference price, in bps, above which an order is refused by default.
+pub const DEFAULT_MAX_REF_DEVIATION_BPS: f64 = 100.0;
+
+/// Highest compute unit limit a transaction may request.
+const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Default number of times `cancel_orders_chunked` resends an unconfirmed chunk with a fresh
+/// blockhash.
+pub const DEFAULT_CANCEL_RETRIES: u32 = 1;
 
 // Limit how many cancel instructions we build so we do not exceed Solana's 1232-byte raw transaction size cap.
 const MAX_CANCEL_ORDERS: usize = 5;
 
+/// Client order ids carried by one `CancelOrdersByClientIds` instruction.
+const MAX_CLIENT_IDS_PER_CANCEL: usize = 8;
+
+/// Lowest compute unit price, in micro-lamports, used when no recent fee is reported.
+pub const PRIORITY_FEE_FLOOR: u64 = 1;
+
+/// Base fee charged per transaction signature, in lamports.
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+/// How many orders `place_laddered` sends per transaction, to stay within the compute limit.
+const MAX_PLACE_ORDERS: usize = 5;
+
+/// Most signatures `getSignaturesForAddress` returns per request.
+pub const MAX_SIGNATURES_PER_PAGE: usize = 1000;
+
+/// Size of a v1 market account: the 5 padding bytes, the `MarketState` and 7 trailing bytes.
+const MARKET_ACCOUNT_LEN: usize = std::mem::size_of::<MarketState>() + 12;
+
+/// Size of a permissioned v1 market account, holding a `MarketStateV2` with its authorities.
+const MARKET_V2_ACCOUNT_LEN: usize = std::mem::size_of::<MarketStateV2>() + 12;
+
+/// Offsets of the base and quote mints in a v1 market account.
+const MARKET_COIN_MINT_OFFSET: usize = 53;
+const MARKET_PC_MINT_OFFSET: usize = 85;
+
+/// Offsets of the base vault, quote vault and event queue in a v1 market account.
+const MARKET_COIN_VAULT_OFFSET: usize = 117;
+const MARKET_PC_VAULT_OFFSET: usize = 165;
+const MARKET_EVENT_QUEUE_OFFSET: usize = 253;
+
+/// Offsets of `coin_fees_accrued` and `pc_fees_accrued` in a v1 market account, each after its
+/// vault and deposits.
+const MARKET_COIN_FEES_ACCRUED_OFFSET: usize = 157;
+const MARKET_PC_FEES_ACCRUED_OFFSET: usize = 205;
+
+/// Open orders accounts passed to one consume events instruction, to stay within the 1232-byte
+/// transaction size cap.
+const MAX_CRANK_ACCOUNTS: usize = 24;
+
+/// Most accounts `getMultipleAccounts` returns per request.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Size of a v1 open orders account: the `OpenOrders` state and the 12 bytes of padding around
+/// it. Rent exemption for it is about 0.0234 SOL.
+pub const OPEN_ORDERS_ACCOUNT_LEN: usize =
+    std::mem::size_of::<openbook_dex::state::OpenOrders>() + 12;
+
 /// OpenBook v1 Client to interact with the OpenBook market and perform actions.
 #[derive(Clone)]
 pub struct OBClient {
     /// The keypair of the owner used for signing transactions related to the market.
+    ///
+    /// In read-only mode, or when a `transaction_signer` is set, this is a throwaway keypair
+    /// that is never used to sign.
     pub owner: Arc<Keypair>,
+    /// External signer used instead of `owner`, see [`OBClient::set_transaction_signer`].
+    pub transaction_signer: Option<Arc<dyn TransactionSigner>>,
+    /// The public key of the wallet whose accounts are read and traded.
+    pub owner_key: Pubkey,
+    /// Whether the client was built without a signer, see [`OBClient::new_readonly`].
+    pub read_only: bool,
     /// The RPC client for interacting with the Solana blockchain.
     pub rpc_client: Rpc,
     /// The public key of the associated account holding the quote tokens.
@@ -67,25 +184,608 @@ pub struct OBClient {
     pub market_info: Market,
     /// A HashMap containing open orders cache entries associated with their public keys.
     pub open_orders_cache: HashMap<Pubkey, OpenOrdersCacheEntry>,
+    /// Snapshot of the market's bids and asks taken on the last book load.
+    pub order_book: OrderBook,
+    /// How many times an unconfirmed match transaction is resent with a fresh blockhash.
+    pub match_retries: u32,
+    /// Whether `match_orders_transaction` skips the transaction when no request is pending, see
+    /// [`OBClient::set_skip_empty_match`].
+    pub skip_empty_match: bool,
+    /// How many times `place_limit_order` resends an order whose status is unknown, with the
+    /// same client order id, once its blockhash expired without it landing. `0` never resends.
+    pub place_retries: u32,
+    /// How many times `cancel_orders_chunked` resends an unconfirmed chunk with a fresh
+    /// blockhash.
+    pub cancel_retries: u32,
+    /// Maximum move of the book mid, in bps, tolerated by `cancel_settle_place` between the
+    /// last book snapshot and the re-read done right before placing. `None` skips the check.
+    pub max_book_move_bps: Option<f64>,
+    /// Per-instruction compute unit limits overriding `ComputeOp::default_units`.
+    pub cu_limits: HashMap<ComputeOp, u32>,
+    /// Commitment awaited after sending each kind of instruction, see
+    /// [`OBClient::set_confirm_commitment`]. Kinds not listed wait for the client's commitment.
+    pub confirm_commitments: HashMap<ComputeOp, CommitmentConfig>,
+    /// Unix timestamp sent as the `max_ts` of limit orders, after which the program rejects
+    /// them. `None` uses `DEFAULT_ORDER_TTL_SECS` from the time the order is built.
+    pub expiry_ts: Option<i64>,
+    /// Most resting orders a new order may match against in one instruction, the `limit` of
+    /// `NewOrderV3`. A taker order hitting the cap stops matching; a limit order then rests the
+    /// rest, a market order cancels it.
+    pub match_limit: u16,
+    /// Compute unit price added to every transaction. `None` only prices the cancel, settle and
+    /// place combos, at the highest recent fee.
+    pub priority_fee: Option<PriorityFee>,
+    /// Lamports the fee payer must hold for a transaction to be sent, see
+    /// [`OBClient::set_min_sol_balance`]. `None` sends regardless of the balance.
+    pub min_sol_balance: Option<u64>,
+    /// Most lamports a transaction may cost in fees, see [`OBClient::set_max_fee_lamports`].
+    /// `None` sends at any fee.
+    pub max_fee_lamports: Option<u64>,
+    /// Bytes requested per `dataSlice` when reading the event queue, for RPCs that cap response
+    /// sizes. `None` reads the whole account at once.
+    pub event_queue_chunk_len: Option<usize>,
+    /// The orders placed by this client, see [`OBClient::load_order_state`].
+    pub order_state: OrderState,
+    /// Which transactions are sent with `skipPreflight`, see [`OBClient::set_skip_preflight`].
+    pub skip_preflight: SkipPreflight,
+    /// Whether requotes may cancel without replacing, see [`OBClient::set_cancel_policy`].
+    pub cancel_policy: CancelPolicy,
+    /// External price orders are checked against, see [`OBClient::set_reference_price`].
+    /// `None` disables the check.
+    pub reference_price: Option<f64>,
+    /// Deviation from `reference_price`, in bps, above which an order is refused.
+    pub max_ref_deviation_bps: f64,
+    /// Most resting orders per side, see [`OBClient::set_max_orders_per_side`]. `None` places
+    /// without counting.
+    pub max_orders_per_side: Option<usize>,
+    /// Whether orders below one base lot are rounded up rather than refused, see
+    /// [`OBClient::set_round_up_to_min_size`].
+    pub round_up_to_min_size: bool,
+    /// The SPL token program of the market's mints, see [`OBClient::set_token_program`].
+    pub token_program: Pubkey,
+    /// The associated token account program, see [`OBClient::set_ata_program`].
+    pub ata_program: Pubkey,
+    /// Whether settling first creates the owner's missing associated token accounts in the same
+    /// transaction, see [`OBClient::set_create_missing_atas`].
+    pub create_missing_atas: bool,
+    /// Whether placements are simulated first and refused if they would overdraw the wallet,
+    /// see [`OBClient::set_check_funds`].
+    pub check_funds: bool,
+    /// Lamports added on top of the rent exemption when creating an open orders account, see
+    /// [`OBClient::open_orders_account_lamports`].
+    pub open_orders_extra_lamports: u64,
+    /// Open orders accounts used in turn by [`OBClient::next_open_orders`], see
+    /// [`OBClient::ensure_open_orders_pool`].
+    pub open_orders_pool: Vec<Pubkey>,
+    /// Position of the next account of `open_orders_pool` to hand out.
+    pool_cursor: usize,
+}
+
+/// Wallet balances relevant to the market, in UI units.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct WalletBalances {
+    /// Native SOL balance of the owner.
+    pub sol: f64,
+    /// Balance of the base token account, `0` if the account does not exist.
+    pub base: f64,
+    /// Balance of the quote token account, `0` if the account does not exist.
+    pub quote: f64,
+}
+
+/// Trading fee rates of the market's base fee tier, as fractions of the quote traded, see
+/// [`OBClient::fee_rates`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct FeeRates {
+    /// Rebate paid to the maker of a fill.
+    pub maker_rebate: f64,
+    /// Fee charged to the taker of a fill.
+    pub taker: f64,
+}
+
+/// Wallet token balances before and after a simulated placement, in UI units, see
+/// [`OBClient::simulate_funds`].
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct FundsCheck {
+    /// Balance of the base token account before the transaction.
+    pub base_before: f64,
+    /// Balance of the base token account after the simulated transaction.
+    pub base_after: f64,
+    /// Balance of the quote token account before the transaction.
+    pub quote_before: f64,
+    /// Balance of the quote token account after the simulated transaction.
+    pub quote_after: f64,
+}
+
+/// Compute budget and cost of a transaction as it would be sent, see
+/// [`OBClient::transaction_budget`].
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct TxBudget {
+    /// Compute units requested with `SetComputeUnitLimit`.
+    pub compute_unit_limit: u32,
+    /// Price per compute unit set with `SetComputeUnitPrice`, in micro-lamports; `0` if none.
+    pub compute_unit_price: u64,
+    /// Number of signatures the transaction requires.
+    pub signatures: u64,
+    /// Estimated fee, see [`OBClient::estimate_fee`].
+    pub fee_lamports: u64,
+    /// Serialized size of the signed transaction, in bytes.
+    pub size: usize,
+}
+
+impl std::fmt::Display for TxBudget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "compute unit limit: {}, compute unit price: {} micro-lamports, signatures: {}, fee: {} lamports ({} SOL), size: {} / {} bytes",
+            self.compute_unit_limit,
+            self.compute_unit_price,
+            self.signatures,
+            self.fee_lamports,
+            self.fee_lamports as f64 / 1_000_000_000.0,
+            self.size,
+            PACKET_DATA_SIZE
+        )
+    }
+}
+
+/// Outcome of a sent transaction, printed as JSON by `--result-json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TxResult {
+    /// The transaction signature, all ones (`1111...`) if it was not sent.
+    #[serde(serialize_with = "serialize_signature")]
+    pub signature: Signature,
+    /// The slot the transaction was processed in, if it was.
+    pub slot: Option<u64>,
+    /// The transaction error, if it failed or was not sent.
+    pub err: Option<String>,
+    /// Whether the transaction reached the client's commitment.
+    pub confirmed: bool,
+}
+
+/// A transaction that touched an account, see `OBClient::signatures_for_open_orders`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SignatureInfo {
+    /// The transaction signature.
+    #[serde(serialize_with = "serialize_signature")]
+    pub signature: Signature,
+    /// The slot the transaction was processed in.
+    pub slot: u64,
+    /// Estimated production time of the block, as a Unix timestamp, if the node knows it.
+    pub block_time: Option<i64>,
+    /// The transaction error, if it failed.
+    pub err: Option<String>,
+    /// The memo of the transaction, if it has one.
+    pub memo: Option<String>,
+}
+
+/// Decoded market state written by the `Dump` command.
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketDump {
+    /// The public key of the wallet owning the open orders account.
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub owner: Pubkey,
+    /// The decoded market account.
+    pub market: Market,
+    /// Both sides of the order book.
+    pub order_book: OrderBook,
+    /// Header of the market's event queue.
+    pub event_queue: EventQueueStats,
+    /// The owner's open orders on the market.
+    pub open_orders: OpenOrders,
+    /// The owner's wallet balances.
+    pub balances: WalletBalances,
+    /// Tokens held in the market's base vault, in UI units.
+    pub base_vault: f64,
+    /// Tokens held in the market's quote vault, in UI units.
+    pub quote_vault: f64,
+    /// Base fees accrued by the market and not swept yet, in UI units.
+    pub accrued_fees_base: f64,
+    /// Quote fees accrued by the market and not swept yet, in UI units.
+    pub accrued_fees_quote: f64,
+}
+
+/// Human readable summary of the market shown by the `Info` command.
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketOverview {
+    /// The public key of the market.
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub market: Pubkey,
+    /// The base mint.
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub base_mint: Pubkey,
+    /// Decimals of the base mint.
+    pub base_decimals: u8,
+    /// The quote mint.
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub quote_mint: Pubkey,
+    /// Decimals of the quote mint.
+    pub quote_decimals: u8,
+    /// Smallest price increment, in UI units.
+    pub tick_size: f64,
+    /// Smallest order size, in base UI units.
+    pub min_order_size: f64,
+    /// Best bid of the last book snapshot, in UI units.
+    pub best_bid: Option<f64>,
+    /// Best ask of the last book snapshot, in UI units.
+    pub best_ask: Option<f64>,
+    /// Decimal places used to display prices.
+    pub price_decimals: usize,
+    /// Decimal places used to display sizes.
+    pub size_decimals: usize,
+}
+
+impl std::fmt::Display for MarketOverview {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let price = |price: Option<f64>| match price {
+            Some(price) => format!("{:.*}", self.price_decimals, price),
+            None => "-".to_string(),
+        };
+        writeln!(f, "Market {}", self.market)?;
+        writeln!(
+            f,
+            "    base mint:      {} ({} decimals)",
+            self.base_mint, self.base_decimals
+        )?;
+        writeln!(
+            f,
+            "    quote mint:     {} ({} decimals)",
+            self.quote_mint, self.quote_decimals
+        )?;
+        writeln!(
+            f,
+            "    tick size:      {:.*}",
+            self.price_decimals, self.tick_size
+        )?;
+        writeln!(
+            f,
+            "    min order size: {:.*}",
+            self.size_decimals, self.min_order_size
+        )?;
+        writeln!(f, "    best bid:       {}", price(self.best_bid))?;
+        write!(f, "    best ask:       {}", price(self.best_ask))
+    }
+}
+
+/// Kind of instruction a compute unit budget is reserved for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ComputeOp {
+    /// Placing an order (`new_order`, `send_take`).
+    Place,
+    /// Cancelling orders.
+    Cancel,
+    /// Settling funds.
+    Settle,
+    /// Matching orders.
+    Match,
+    /// Consuming events, permissioned or not.
+    Consume,
+    /// Initializing an open orders account.
+    InitOpenOrders,
+    /// Any non-OpenBook instruction, e.g. creating a token account.
+    Other,
+}
+
+impl ComputeOp {
+    /// Compute units reserved per instruction of this kind unless overridden.
+    ///
+    /// Matching and cranking scale with the number of orders or events they touch, so they get
+    /// the largest budgets.
+    pub fn default_units(self) -> u32 {
+        match self {
+            ComputeOp::Place => 120_000,
+            ComputeOp::Cancel => 25_000,
+            ComputeOp::Settle => 40_000,
+            ComputeOp::Match => 200_000,
+            ComputeOp::Consume => 200_000,
+            ComputeOp::InitOpenOrders => 20_000,
+            ComputeOp::Other => 30_000,
+        }
+    }
+
+    /// Classifies an instruction sent to the DEX program `program_id`.
+    pub fn of(instruction: &Instruction, program_id: &Pubkey) -> Self {
+        if instruction.program_id != *program_id {
+            return ComputeOp::Other;
+        }
+        match MarketInstruction::unpack(&instruction.data) {
+            Some(MarketInstruction::NewOrderV3(_)) | Some(MarketInstruction::SendTake(_)) => {
+                ComputeOp::Place
+            }
+            Some(MarketInstruction::CancelOrderV2(_))
+            | Some(MarketInstruction::CancelOrderByClientIdV2(_))
+            | Some(MarketInstruction::CancelOrdersByClientIds(_)) => ComputeOp::Cancel,
+            Some(MarketInstruction::SettleFunds) => ComputeOp::Settle,
+            Some(MarketInstruction::MatchOrders(_)) => ComputeOp::Match,
+            Some(MarketInstruction::ConsumeEvents(_))
+            | Some(MarketInstruction::ConsumeEventsPermissioned(_)) => ComputeOp::Consume,
+            Some(MarketInstruction::InitOpenOrders) => ComputeOp::InitOpenOrders,
+            _ => ComputeOp::Other,
+        }
+    }
+}
+
+impl FromStr for ComputeOp {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "place" => Ok(ComputeOp::Place),
+            "cancel" => Ok(ComputeOp::Cancel),
+            "settle" => Ok(ComputeOp::Settle),
+            "match" => Ok(ComputeOp::Match),
+            "consume" => Ok(ComputeOp::Consume),
+            "init-open-orders" => Ok(ComputeOp::InitOpenOrders),
+            "other" => Ok(ComputeOp::Other),
+            other => Err(anyhow!(
+                "Invalid operation {other:?}, expected place, cancel, settle, match, consume, \
+                 init-open-orders or other"
+            )),
+        }
+    }
+}
+
+/// Counts the events a consume events transaction popped from the queue headers read before and
+/// after it.
+///
+/// The program does not log how many events it popped: the pending count plus the events pushed
+/// in between (the `seq_num` delta), minus the pending count after. Events consumed by another
+/// cranker in that window are counted too; the count is capped at `limit`.
+fn consumed_count(before: &EventQueueStats, after: &EventQueueStats, limit: u16) -> u64 {
+    let produced = after.seq_num.wrapping_sub(before.seq_num);
+    (before.count + produced)
+        .saturating_sub(after.count)
+        .min(limit as u64)
+}
+
+/// Reads the compute unit limit and price, in micro-lamports, set by the compute budget
+/// instructions among `instructions`. The limit is `None` and the price `0` when not set.
+fn compute_budget_of(instructions: &[Instruction]) -> (Option<u32>, u64) {
+    let mut units = None;
+    let mut micro_lamports = 0u64;
+    for ix in instructions
+        .iter()
+        .filter(|ix| ix.program_id == compute_budget::id())
+    {
+        // The tag comes first, 2 is `SetComputeUnitLimit` and 3 `SetComputeUnitPrice`.
+        match ix.data.split_first() {
+            Some((2, value)) => {
+                units = value
+                    .get(..4)
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .map(u32::from_le_bytes);
+            }
+            Some((3, value)) => {
+                micro_lamports = value
+                    .get(..8)
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .unwrap_or_default();
+            }
+            _ => {}
+        }
+    }
+    (units, micro_lamports)
+}
+
+/// Checks that `account` is a market of `program_id` before it is decoded.
+///
+/// Decoding an account of another program fails deep in `MarketState::load` with an opaque
+/// error, so the owner and size are checked up front and known DEX programs are named.
+fn check_market_account(market_id: &Pubkey, account: &Account, program_id: &Pubkey) -> Result<()> {
+    if account.owner != *program_id {
+        let owner = account.owner.to_string();
+        let hint = if owner == OPENBOOK_V2_PROGRAM_ID {
+            " It is an OpenBook v2 market, which this client does not support."
+        } else if owner == SERUM_V3_PROGRAM_ID {
+            " It is a Serum v3 market, pass that program id to target it."
+        } else if owner == OPENBOOK_V1_PROGRAM_ID {
+            " It is an OpenBook v1 market, pass that program id to target it."
+        } else {
+            ""
+        };
+        return Err(anyhow!(
+            "Market {} is owned by program {}, not the configured program {}.{}",
+            market_id,
+            account.owner,
+            program_id,
+            hint
+        ));
+    }
+    if account.data.len() != MARKET_ACCOUNT_LEN && account.data.len() != MARKET_V2_ACCOUNT_LEN {
+        return Err(anyhow!(
+            "Account {} is owned by program {} but is not a market ({} bytes, expected {} or {})",
+            market_id,
+            program_id,
+            account.data.len(),
+            MARKET_ACCOUNT_LEN,
+            MARKET_V2_ACCOUNT_LEN
+        ));
+    }
+    Ok(())
+}
+
+/// Compute unit price of the client's transactions, see [`OBClient::priority_fee`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriorityFee {
+    /// A fixed price, in micro-lamports per compute unit.
+    Fixed(u64),
+    /// The given percentile (0-100) of the fees recently paid to write the market's accounts,
+    /// estimated before each transaction.
+    Auto(u8),
+}
+
+impl FromStr for PriorityFee {
+    type Err = Error;
+
+    /// Parses `<MICRO_LAMPORTS>` or `auto:<PERCENTILE>`.
+    fn from_str(s: &str) -> Result<Self> {
+        match s.strip_prefix("auto:") {
+            Some(percentile) => match percentile.parse::<u8>() {
+                Ok(percentile) if percentile <= 100 => Ok(PriorityFee::Auto(percentile)),
+                _ => Err(anyhow!(
+                    "Invalid priority fee percentile {percentile:?}, expected 0 to 100"
+                )),
+            },
+            None => s.parse().map(PriorityFee::Fixed).map_err(|_| {
+                anyhow!("Invalid priority fee {s:?}, expected micro-lamports or auto:<percentile>")
+            }),
+        }
+    }
+}
+
+/// Distance between the best price on the book and a limit order placed off it, see
+/// [`OBClient::place_limit_order_with_offset`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OffsetKind {
+    /// An absolute distance in quote currency (e.g. USDC).
+    Usdc(f64),
+    /// A distance in basis points of the best price.
+    Bps(f64),
+}
+
+impl OffsetKind {
+    /// Returns the price `self` away from `reference`, below it for bids and above it for asks.
+    pub fn apply(self, side: Side, reference: f64) -> f64 {
+        let offset = match self {
+            OffsetKind::Usdc(usdc) => usdc,
+            OffsetKind::Bps(bps) => reference * bps / 10_000.0,
+        };
+        match side {
+            Side::Bid => reference - offset,
+            Side::Ask => reference + offset,
+        }
+    }
+}
+
+/// How `OBClient::place_laddered` splits its size between the levels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LadderDistribution {
+    /// Every level gets the same size.
+    #[default]
+    Flat,
+    /// Level `i` (from 0) gets a size proportional to `i + 1`, so most of the size rests
+    /// furthest from the base price.
+    Weighted,
+}
+
+impl LadderDistribution {
+    /// Returns the relative weight of each of `levels` levels.
+    fn weights(self, levels: usize) -> Vec<f64> {
+        (0..levels)
+            .map(|level| match self {
+                LadderDistribution::Flat => 1.0,
+                LadderDistribution::Weighted => (level + 1) as f64,
+            })
+            .collect()
+    }
+}
+
+impl FromStr for LadderDistribution {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "flat" => Ok(LadderDistribution::Flat),
+            "weighted" => Ok(LadderDistribution::Weighted),
+            other => Err(anyhow!(
+                "Invalid distribution {other:?}, expected \"flat\" or \"weighted\""
+            )),
+        }
+    }
+}
+
+/// Which transactions `OBClient` sends without simulating them first.
+///
+/// Skipping preflight saves the simulation round trip on the node. A transaction that would
+/// fail is then only caught on-chain, and reported as unconfirmed by the confirmation step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SkipPreflight {
+    /// Every transaction is simulated before it is sent.
+    #[default]
+    Never,
+    /// Transactions made only of cancels skip the simulation, others keep it.
+    Cancels,
+    /// No transaction is simulated.
+    Always,
+}
+
+impl SkipPreflight {
+    /// Returns whether a transaction of `instructions` to `program_id` skips preflight.
+    pub fn applies(self, instructions: &[Instruction], program_id: &Pubkey) -> bool {
+        match self {
+            SkipPreflight::Never => false,
+            SkipPreflight::Always => true,
+            SkipPreflight::Cancels => {
+                let mut dex_instructions = instructions
+                    .iter()
+                    .filter(|ix| ix.program_id == *program_id)
+                    .peekable();
+                dex_instructions.peek().is_some()
+                    && dex_instructions.all(|ix| {
+                        matches!(
+                            MarketInstruction::unpack(&ix.data),
+                            Some(
+                                MarketInstruction::CancelOrderV2(_)
+                                    | MarketInstruction::CancelOrderByClientIdV2(_)
+                                    | MarketInstruction::CancelOrdersByClientIds(_)
+                            )
+                        )
+                    })
+            }
+        }
+    }
+}
+
+impl FromStr for SkipPreflight {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "never" => Ok(SkipPreflight::Never),
+            "cancels" => Ok(SkipPreflight::Cancels),
+            "always" => Ok(SkipPreflight::Always),
+            other => Err(anyhow!(
+                "Invalid preflight mode {other:?}, expected \"never\", \"cancels\" or \"always\""
+            )),
+        }
+    }
+}
+
+/// Whether requotes, `OBClient::apply_quote_plan` and the `cancel_settle_place` combos, may
+/// cancel an order without replacing it.
+///
+/// Cancels sent ahead of the placements leave the side empty until the placements land, or for
+/// good if they fail. `RequireReplacement` only cancels an order in the same transaction as a
+/// new quote on its side. Explicit cancels, such as `cancel_orders` or `flatten`, are not
+/// affected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CancelPolicy {
+    /// Cancels are sent first, then the placements, each in their own transactions.
+    #[default]
+    AllowBare,
+    /// Every cancel shares a transaction with at least one placement on the same side.
+    RequireReplacement,
 }
 
-/// Summary of the market's event queue state.
-#[derive(Debug, Clone, Copy, Default)]
-pub struct EventQueueStats {
-    /// Raw account flag bits stored on the queue.
-    pub account_flags: u64,
-    /// Current head pointer inside the ring buffer.
-    pub head: u64,
-    /// Number of pending events waiting to be consumed.
-    pub count: u64,
-    /// Monotonic event id.
-    pub seq_num: u64,
+impl FromStr for CancelPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "allow-bare" => Ok(CancelPolicy::AllowBare),
+            "require-replacement" => Ok(CancelPolicy::RequireReplacement),
+            other => Err(anyhow!(
+                "Invalid cancel policy {other:?}, expected \"allow-bare\" or \"require-replacement\""
+            )),
+        }
+    }
 }
 
 impl Debug for OBClient {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
         writeln!(f, "OB_V1_Client {{")?;
-        writeln!(f, "    owner: {:?}", self.owner.pubkey())?;
+        writeln!(f, "    owner: {:?}", self.owner_key)?;
+        writeln!(f, "    read_only: {:?}", self.read_only)?;
         writeln!(f, "    rpc_client: {:?}", self.rpc_client)?;
         writeln!(f, "    quote_ata: {:?}", self.quote_ata)?;
         writeln!(f, "    base_ata: {:?}", self.base_ata)?;
@@ -138,8 +838,8 @@ impl OBClient {
     ///
     /// 1. Retrieve necessary env vars, such as the `RPC_URL` and `KEY_PATH` path.
     /// 2. Read the owner's keypair from the specified key path.
-    /// 3. Initialize the RPC client with the given commitment configuration.
-    /// 4. Fetch the market account information on chain.
+    /// 3. Initialize the RPC client with the given commitment configuration, rate limited by `RPC_RATE_LIMIT` if set.
+    /// 4. Fetch the market account information on chain and check it belongs to `PROGRAM_ID`.
     /// 5. Load the market state and extract base and quote mints.
     /// 6. Initialize the `Market` struct with fetched market information.
     /// 7. Fetche associated token accounts (ATA) for the base and quote tokens.
@@ -153,406 +853,6223 @@ impl OBClient {
         load: bool,
         cache_ts: u128,
     ) -> Result<Self, Error> {
+        Self::with_rpc(Self::rpc_from_env(commitment)?, market_id, load, cache_ts).await
+    }
+
+    /// Builds the RPC client [`OBClient::new`] uses, from `RPC_URL` and `RPC_RATE_LIMIT`.
+    ///
+    /// Clones of the returned `Rpc` share its HTTP connection pool, rate limiter, reconnect
+    /// switch, profiler and blockhash cache, so building it once and handing a clone to every
+    /// client through [`OBClient::with_rpc`] makes the rate limit global to the process.
+    pub fn rpc_from_env(commitment: CommitmentConfig) -> Result<Rpc> {
         let rpc_url =
             std::env::var("RPC_URL").unwrap_or("https://api.mainnet-beta.solana.com".to_string());
-        let key_path = std::env::var("KEY_PATH").unwrap_or("".to_string());
-        let program_id_str =
-            std::env::var(PROGRAM_ID_ENV).unwrap_or_else(|_| SRM_PROGRAM_ID.to_string());
-        let program_id = Pubkey::from_str(&program_id_str)?;
-
-        let owner = read_keypair(&key_path);
-        let rpc_client = RpcClient::new_with_commitment(rpc_url, commitment);
-        let oos_key_str = std::env::var("OOS_KEY").unwrap_or("".to_string());
-
-        let orders_key = Pubkey::from_str(oos_key_str.as_str());
-
-        let pub_owner_key = owner.pubkey().clone();
-
-        let rpc_client = Rpc::new(rpc_client);
-
-        let mut account_1 = rpc_client.inner().get_account(&market_id).await?;
-        let mut account_2 = rpc_client.inner().get_account(&market_id).await?;
-        let account_info_1;
-        let account_info_2;
-        {
-            account_info_1 = create_account_info_from_account(
-                &mut account_1,
-                &market_id,
-                &program_id,
-                false,
-                false,
-            );
-            account_info_2 = create_account_info_from_account(
-                &mut account_2,
-                &market_id,
-                &program_id,
-                false,
-                false,
-            );
-        }
-        let market = MarketState::load(&account_info_1, &program_id, false)?;
-        let market_auth = MarketAuth::load(&account_info_2, &program_id, false)?;
-        let default_auth = Default::default();
-        let events_authority = market_auth
-            .consume_events_authority()
-            .unwrap_or(&default_auth);
-
-        let base_mint = Pubkey::from(u64_slice_to_pubkey(market.coin_mint));
-        let quote_mint = Pubkey::from(u64_slice_to_pubkey(market.pc_mint));
-
-        let market_info = Market::new(
-            rpc_client.clone(),
-            program_id,
-            market_id,
-            base_mint,
-            quote_mint,
-            *events_authority,
-            load,
-        )
-        .await?;
-
-        let base_ata = get_associated_token_address(&pub_owner_key.clone(), &market_info.base_mint);
-        let quote_ata =
-            get_associated_token_address(&pub_owner_key.clone(), &market_info.quote_mint);
-
-        let cloned_owner = owner.insecure_clone();
-        let open_orders = OpenOrders::new(
-            rpc_client.clone(),
-            program_id,
-            cloned_owner,
-            market_info.market_address,
-        )
-        .await?;
-        let mut open_orders_cache = HashMap::new();
-
-        let open_orders_cache_entry = OpenOrdersCacheEntry {
-            open_orders: open_orders.clone(),
-            ts: cache_ts,
-        };
-
-        open_orders_cache.insert(pub_owner_key, open_orders_cache_entry.clone());
-
-        let mut ob_client = Self {
-            rpc_client,
-            market_info,
-            owner: owner.into(),
-            quote_ata,
-            base_ata,
-            open_orders,
-            open_orders_cache,
-        };
-
-        if !orders_key.is_err() {
-            ob_client.open_orders.oo_key = orders_key.unwrap();
-        }
-
-        if load {
-            ob_client.load_bids_asks_info().await?;
+        let rpc_client = Rpc::with_url(rpc_url, commitment);
+        if let Ok(rate_limit) = std::env::var(RATE_LIMIT_ENV) {
+            rpc_client.set_rate_limit(rate_limit.parse()?)?;
         }
-
-        if let Some(entry) = ob_client.open_orders_cache.get_mut(&pub_owner_key) {
-            entry.open_orders = ob_client.open_orders.clone();
-        }
-
-        Ok(ob_client)
+        Ok(rpc_client)
     }
 
-    /// Loads information about bids, asks, and the open orders associated with the wallet from the market state.
+    /// Initializes a client like [`OBClient::new`], sending its requests through `rpc_client`.
     ///
-    /// This function fetches and processes bids information, including extracting the bids and asks addresses
-    /// and loading the open orders. It also determines the maximum bid price and minimum ask price.
+    /// Pass clones of one `Rpc` to the clients of several markets so they reuse one connection
+    /// pool and one rate limiter instead of opening their own. The commitment of `rpc_client` is
+    /// used for reads and confirmations.
     ///
     /// # Arguments
     ///
-    /// * `&mut self` - A mutable reference to the `OBClient` struct.
+    /// * `rpc_client` - The RPC client to share, e.g. from [`OBClient::rpc_from_env`].
+    /// * `market_id` - Public key (ID) of the market to fetch information about.
+    /// * `load` - Boolean indicating whether to load market data immediately after initialization.
+    /// * `cache_ts` - Timestamp for caching current open orders, used to manage the cache validity.
     ///
-    /// # Returns
+    /// # Examples
     ///
-    /// A `Result` containing a tuple of `(bids_address, asks_address, open_orders)` if successful,
-    /// or an error if loading the bids information fails.
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
     ///
-    /// # Errors
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let rpc = OBClient::rpc_from_env(CommitmentConfig::confirmed())?;
+    ///     rpc.set_rate_limit(10)?;
     ///
-    /// This function may return an error if there is an issue with fetching accounts
-    /// or processing the bids information.
-    pub async fn load_bids_asks_info(&mut self) -> Result<(Pubkey, Pubkey, OpenOrders)> {
-        let mut account = self
-            .rpc_client
-            .inner()
-            .get_account(&self.market_info.market_address)
-            .await?;
-        let program_id_binding = self.market_info.program_id;
-        let market_account_binding = self.market_info.market_address;
-        let account_info;
-        {
-            account_info = create_account_info_from_account(
-                &mut account,
-                &market_account_binding,
-                &program_id_binding,
-                false,
+    ///     // A comma separated list of market ids.
+    ///     let markets = std::env::var("MARKETS")?;
+    ///
+    ///     let mut clients = Vec::new();
+    ///     for market_id in markets.split(',') {
+    ///         let market_id = market_id.parse()?;
+    ///         clients.push(OBClient::with_rpc(rpc.clone(), market_id, true, 1000).await?);
+    ///     }
+    ///
+    ///     println!("{} clients on one connection", clients.len());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn with_rpc(
+        rpc_client: Rpc,
+        market_id: Pubkey,
+        load: bool,
+        cache_ts: u128,
+    ) -> Result<Self, Error> {
+        let key_path = std::env::var("KEY_PATH").unwrap_or("".to_string());
+        let owner = read_keypair(&key_path);
+        let pub_owner_key = Signer::pubkey(&owner);
+
+        Self::init(
+            rpc_client,
+            market_id,
+            load,
+            cache_ts,
+            Some(owner),
+            pub_owner_key,
+            None,
+        )
+        .await
+    }
+
+    /// Initializes a client like [`OBClient::with_rpc`], with the given base and quote decimals
+    /// instead of the values read from the mint accounts.
+    ///
+    /// The mints are not fetched at all, so use it when the RPC will not return them or they are
+    /// non-standard; [`OBClient::new`] refuses to start in that case unless `BASE_DECIMALS` /
+    /// `QUOTE_DECIMALS` are set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let rpc = OBClient::rpc_from_env(CommitmentConfig::confirmed())?;
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::with_decimals(rpc, market_id, true, 1000, 6, 6).await?;
+    ///
+    ///     println!("Base decimals: {}", ob_client.market_info.coin_decimals);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn with_decimals(
+        rpc_client: Rpc,
+        market_id: Pubkey,
+        load: bool,
+        cache_ts: u128,
+        base_decimals: u8,
+        quote_decimals: u8,
+    ) -> Result<Self, Error> {
+        let key_path = std::env::var("KEY_PATH").unwrap_or("".to_string());
+        let owner = read_keypair(&key_path);
+        let pub_owner_key = Signer::pubkey(&owner);
+
+        Self::init(
+            rpc_client,
+            market_id,
+            load,
+            cache_ts,
+            Some(owner),
+            pub_owner_key,
+            Some((base_decimals, quote_decimals)),
+        )
+        .await
+    }
+
+    /// Initializes a client that reads the accounts of `owner` without loading a keypair.
+    ///
+    /// Reads such as market info, the book, open orders and balances work as usual. Every method
+    /// that needs to sign a transaction returns an error instead. The open orders account is
+    /// taken from `OOS_KEY` like for a signing client, or else is the first one the owner holds
+    /// on the market.
+    ///
+    /// # Arguments
+    ///
+    /// * `commitment` - Commitment configuration used for reads.
+    /// * `market_id` - Public key (ID) of the market to fetch information about.
+    /// * `owner` - Public key of the wallet to inspect.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///     let owner = "BqpVsNuznx4tJPNC4RNd2FmoFzMu4wPbQMMwEHZiyV6B".parse()?;
+    ///
+    ///     let ob_client = OBClient::new_readonly(commitment, market_id, owner).await?;
+    ///
+    ///     println!("Open orders: {:?}", ob_client.open_orders);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn new_readonly(
+        commitment: CommitmentConfig,
+        market_id: Pubkey,
+        owner: Pubkey,
+    ) -> Result<Self, Error> {
+        Self::init(
+            Self::rpc_from_env(commitment)?,
+            market_id,
+            true,
+            0,
+            None,
+            owner,
+            None,
+        )
+        .await
+    }
+
+    /// Initializes a client that signs through `signer` rather than a keypair loaded from
+    /// `KEY_PATH`.
+    ///
+    /// The client is loaded like [`OBClient::new_readonly`] for the signer's public key, then
+    /// every transaction is signed with [`TransactionSigner::sign`]. The open orders account is
+    /// taken from `OOS_KEY`, or else is the first one the signer's wallet holds on the market;
+    /// without any, create one with [`OBClient::ensure_open_orders_pool`].
+    ///
+    /// # Arguments
+    ///
+    /// * `commitment` - Commitment configuration for transactions.
+    /// * `market_id` - Public key (ID) of the market to trade on.
+    /// * `signer` - Signer of the wallet, the fee payer of every transaction.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::keypair::Keypair;
+    /// use openbook::v1::ob_client::OBClient;
+    /// use std::sync::Arc;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///     // Stands in for a remote signer implementing `TransactionSigner`.
+    ///     let signer = Arc::new(Keypair::new());
+    ///
+    ///     let ob_client = OBClient::with_signer(commitment, market_id, signer).await?;
+    ///
+    ///     println!("Trading as {}", ob_client.owner_key);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn with_signer(
+        commitment: CommitmentConfig,
+        market_id: Pubkey,
+        signer: Arc<dyn TransactionSigner>,
+    ) -> Result<Self, Error> {
+        let mut ob_client = Self::init(
+            Self::rpc_from_env(commitment)?,
+            market_id,
+            true,
+            0,
+            None,
+            signer.pubkey(),
+            None,
+        )
+        .await?;
+        ob_client.set_transaction_signer(signer)?;
+        Ok(ob_client)
+    }
+
+    /// Builds a client for `pub_owner_key`, signing with `signer` unless it is `None`.
+    async fn init(
+        rpc_client: Rpc,
+        market_id: Pubkey,
+        load: bool,
+        cache_ts: u128,
+        signer: Option<Keypair>,
+        pub_owner_key: Pubkey,
+        decimals: Option<(u8, u8)>,
+    ) -> Result<Self, Error> {
+        let program_id_str =
+            std::env::var(PROGRAM_ID_ENV).unwrap_or_else(|_| OPENBOOK_V1_PROGRAM_ID.to_string());
+        let program_id = Pubkey::from_str(&program_id_str)?;
+
+        let oos_key_str = std::env::var("OOS_KEY").unwrap_or("".to_string());
+
+        let orders_key = Pubkey::from_str(oos_key_str.as_str());
+
+        let mut account_1 = rpc_client.inner().get_account(&market_id).await?;
+        check_market_account(&market_id, &account_1, &program_id)?;
+        let mut account_2 = rpc_client.inner().get_account(&market_id).await?;
+        let account_info_1;
+        let account_info_2;
+        {
+            account_info_1 = create_account_info_from_account(
+                &mut account_1,
+                &market_id,
+                &program_id,
+                false,
+                false,
+            );
+            account_info_2 = create_account_info_from_account(
+                &mut account_2,
+                &market_id,
+                &program_id,
+                false,
                 false,
             );
         }
-        let market_state = MarketState::load(&account_info, &self.market_info.program_id, false)?;
+        let market = MarketState::load(&account_info_1, &program_id, false)?;
+        let market_auth = MarketAuth::load(&account_info_2, &program_id, false)?;
+        let default_auth = Default::default();
+        let events_authority = market_auth
+            .consume_events_authority()
+            .unwrap_or(&default_auth);
 
-        let bids_address = self.market_info.bids_address;
-        let asks_address = self.market_info.asks_address;
+        let base_mint = Pubkey::from(u64_slice_to_pubkey(market.coin_mint));
+        let quote_mint = Pubkey::from(u64_slice_to_pubkey(market.pc_mint));
 
-        let mut bids_account = self.rpc_client.inner().get_account(&bids_address).await?;
-        let bids_info = create_account_info_from_account(
-            &mut bids_account,
-            &bids_address,
-            &self.market_info.program_id,
-            false,
-            false,
-        );
-        let mut bids = market_state.load_bids_mut(&bids_info)?;
-        let (open_bids, open_bids_prices, max_bid) = self.process_bids(&mut bids)?;
+        let market_info = Market::with_decimals(
+            rpc_client.clone(),
+            program_id,
+            market_id,
+            base_mint,
+            quote_mint,
+            *events_authority,
+            load,
+            decimals,
+        )
+        .await?;
 
-        let mut asks_account = self.rpc_client.inner().get_account(&asks_address).await?;
-        let asks_info = create_account_info_from_account(
-            &mut asks_account,
-            &asks_address,
-            &self.market_info.program_id,
-            false,
+        let read_only = signer.is_none();
+        let owner = signer.unwrap_or_else(Keypair::new);
+        let open_orders = if read_only {
+            OpenOrders::default()
+        } else {
+            OpenOrders::new(
+                rpc_client.clone(),
+                program_id,
+                owner.insecure_clone(),
+                market_info.market_address,
+            )
+            .await?
+        };
+
+        let mut ob_client = Self::from_parts(
+            rpc_client,
+            market_info,
+            owner,
+            pub_owner_key,
+            read_only,
+            open_orders,
+            cache_ts,
+        )?;
+
+        if !orders_key.is_err() {
+            ob_client.open_orders.oo_key = orders_key.unwrap();
+        } else if read_only {
+            // Without a keypair to create one, use the owner's existing account.
+            match ob_client.open_orders_accounts().await {
+                Ok(accounts) => match accounts.first() {
+                    Some(oo_key) => ob_client.open_orders.oo_key = *oo_key,
+                    None => warn!(
+                        "[*] No open orders account found for {} on this market",
+                        pub_owner_key
+                    ),
+                },
+                Err(err) => warn!("[*] Failed to look up the open orders accounts: {err}"),
+            }
+        }
+
+        if load {
+            ob_client.load_bids_asks_info().await?;
+        }
+
+        if let Some(entry) = ob_client.open_orders_cache.get_mut(&pub_owner_key) {
+            entry.open_orders = ob_client.open_orders.clone();
+        }
+
+        Ok(ob_client)
+    }
+
+    /// Builds a client from an already decoded market, without any RPC request.
+    ///
+    /// Meant for building instructions offline, e.g. to benchmark them against a fixture
+    /// market: every `execute = false` path of the order, cancel and settle methods works,
+    /// while reads and sends go to `RPC_URL` like for [`OBClient::new`] and fail without a node.
+    ///
+    /// # Arguments
+    ///
+    /// * `market_info` - The market, with its accounts, decimals and lot sizes filled in.
+    /// * `owner` - The keypair signing for the wallet.
+    /// * `open_orders` - The open orders account of `owner` on the market.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::pubkey::Pubkey;
+    /// use openbook::signature::Keypair;
+    /// use openbook::v1::market::Market;
+    /// use openbook::v1::ob_client::OBClient;
+    /// use openbook::v1::orders::OrderReturnType;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let market = Market {
+    ///         market_address: Pubkey::new_unique(),
+    ///         coin_decimals: 9,
+    ///         pc_decimals: 6,
+    ///         coin_lot_size: 1_000_000,
+    ///         pc_lot_size: 1,
+    ///         ..Market::default()
+    ///     };
+    ///     let ob_client = OBClient::offline(market, Keypair::new(), Pubkey::new_unique())?;
+    ///
+    ///     let settle = ob_client.settle_balance(false).await?;
+    ///     assert!(matches!(settle, Some(OrderReturnType::Instructions(_))));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn offline(market_info: Market, owner: Keypair, open_orders: Pubkey) -> Result<Self> {
+        let rpc_url =
+            std::env::var("RPC_URL").unwrap_or("https://api.mainnet-beta.solana.com".to_string());
+        let owner_key = Signer::pubkey(&owner);
+        let open_orders = OpenOrders {
+            oo_key: open_orders,
+            bids_address: market_info.bids_address,
+            asks_address: market_info.asks_address,
+            ..OpenOrders::default()
+        };
+        Self::from_parts(
+            Rpc::with_url(rpc_url, CommitmentConfig::confirmed()),
+            market_info,
+            owner,
+            owner_key,
             false,
-        );
-        let mut asks = market_state.load_asks_mut(&asks_info)?;
-        let (open_asks, open_asks_prices, min_ask) = self.process_asks(&mut asks)?;
+            open_orders,
+            0,
+        )
+    }
 
-        self.open_orders = OpenOrders {
-            oo_key: self.open_orders.oo_key,
-            min_ask,
-            max_bid,
-            open_asks,
-            open_bids,
-            bids_address,
-            asks_address,
-            open_asks_prices,
-            open_bids_prices,
-            base_total: 0.,
-            quote_total: 0.,
+    /// Assembles a client from its loaded parts, with every option at its default.
+    fn from_parts(
+        rpc_client: Rpc,
+        market_info: Market,
+        owner: Keypair,
+        pub_owner_key: Pubkey,
+        read_only: bool,
+        open_orders: OpenOrders,
+        cache_ts: u128,
+    ) -> Result<Self> {
+        let base_ata = get_associated_token_address(&pub_owner_key.clone(), &market_info.base_mint);
+        let quote_ata =
+            get_associated_token_address(&pub_owner_key.clone(), &market_info.quote_mint);
+
+        let mut open_orders_cache = HashMap::new();
+
+        let open_orders_cache_entry = OpenOrdersCacheEntry {
+            open_orders: open_orders.clone(),
+            ts: cache_ts,
+        };
+
+        open_orders_cache.insert(pub_owner_key, open_orders_cache_entry.clone());
+
+        Ok(Self {
+            rpc_client,
+            market_info,
+            owner: owner.into(),
+            transaction_signer: None,
+            owner_key: pub_owner_key,
+            read_only,
+            quote_ata,
+            base_ata,
+            open_orders,
+            open_orders_cache,
+            order_book: OrderBook::default(),
+            match_retries: DEFAULT_MATCH_RETRIES,
+            skip_empty_match: true,
+            place_retries: 0,
+            cancel_retries: DEFAULT_CANCEL_RETRIES,
+            max_book_move_bps: None,
+            cu_limits: HashMap::new(),
+            confirm_commitments: HashMap::new(),
+            expiry_ts: None,
+            match_limit: DEFAULT_MATCH_LIMIT,
+            priority_fee: None,
+            min_sol_balance: None,
+            max_fee_lamports: None,
+            event_queue_chunk_len: None,
+            order_state: OrderState::default(),
+            skip_preflight: SkipPreflight::default(),
+            cancel_policy: CancelPolicy::default(),
+            reference_price: None,
+            max_ref_deviation_bps: DEFAULT_MAX_REF_DEVIATION_BPS,
+            max_orders_per_side: None,
+            round_up_to_min_size: false,
+            token_program: SPL_TOKEN_ID.parse()?,
+            ata_program: spl_associated_token_account::id(),
+            create_missing_atas: false,
+            check_funds: false,
+            open_orders_extra_lamports: 0,
+            open_orders_pool: Vec::new(),
+            pool_cursor: 0,
+        })
+    }
+
+    /// Returns the signer of transactions, the external signer if one is set and the owner
+    /// keypair otherwise, or an error for a read-only client.
+    fn signer(&self) -> Result<Arc<dyn TransactionSigner>> {
+        if self.read_only {
+            return Err(anyhow!(
+                "Client is read-only for {}, load a keypair to send transactions",
+                self.owner_key
+            ));
+        }
+        Ok(match &self.transaction_signer {
+            Some(signer) => signer.clone(),
+            None => self.owner.clone() as Arc<dyn TransactionSigner>,
+        })
+    }
+
+    /// Signs every transaction with `signer` instead of the owner keypair, e.g. a hardware
+    /// wallet, an HSM or a cloud KMS, so the private key never has to be loaded.
+    ///
+    /// A read-only client becomes able to send transactions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `signer` is not for the wallet the client trades, since the open
+    /// orders and token accounts were derived from it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    /// use std::sync::Arc;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     // Any `TransactionSigner` for the owner's key, a keypair here.
+    ///     let signer = Arc::new(ob_client.owner.insecure_clone());
+    ///     ob_client.set_transaction_signer(signer)?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_transaction_signer(&mut self, signer: Arc<dyn TransactionSigner>) -> Result<()> {
+        let signer_key = signer.pubkey();
+        if signer_key != self.owner_key {
+            return Err(anyhow!(
+                "Signer {} does not match the client's wallet {}",
+                signer_key,
+                self.owner_key
+            ));
+        }
+        self.transaction_signer = Some(signer);
+        self.read_only = false;
+        Ok(())
+    }
+
+    /// Returns the market's vault signer, the PDA owning the base and quote vaults.
+    ///
+    /// It is derived once when the market is loaded, from the `vault_signer_nonce` stored in the
+    /// market account, so instructions built outside this client can reuse it.
+    pub fn vault_signer(&self) -> Pubkey {
+        self.market_info.vault_signer_key
+    }
+
+    /// Overrides the compute units reserved per instruction of kind `op`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::{ComputeOp, OBClient};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     ob_client.set_cu_limit_for(ComputeOp::Consume, 300_000);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_cu_limit_for(&mut self, op: ComputeOp, units: u32) {
+        self.cu_limits.insert(op, units);
+    }
+
+    /// Returns the compute units reserved per instruction of kind `op`.
+    pub fn cu_limit_for(&self, op: ComputeOp) -> u32 {
+        self.cu_limits
+            .get(&op)
+            .copied()
+            .unwrap_or_else(|| op.default_units())
+    }
+
+    /// Waits for `commitment` when confirming transactions containing instructions of kind `op`.
+    ///
+    /// A transaction mixing several kinds waits for the strongest of their commitments, the
+    /// client's commitment standing in for kinds without one, so a cancel, settle and place
+    /// combo with `Settle` at `finalized` is confirmed as finalized, and one with only `Cancel`
+    /// at `processed` is still confirmed at the client's commitment.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::{ComputeOp, OBClient};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     ob_client.set_confirm_commitment(ComputeOp::Settle, CommitmentConfig::finalized());
+    ///     ob_client.set_confirm_commitment(ComputeOp::Cancel, CommitmentConfig::processed());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_confirm_commitment(&mut self, op: ComputeOp, commitment: CommitmentConfig) {
+        self.confirm_commitments.insert(op, commitment);
+    }
+
+    /// Returns the commitment a transaction of `instructions` is confirmed at.
+    ///
+    /// Compute budget instructions are ignored. Every other instruction needs its override, or
+    /// the client's commitment without one, and the strongest of them is returned.
+    pub fn confirm_commitment_for(&self, instructions: &[Instruction]) -> CommitmentConfig {
+        let rank = |commitment: &CommitmentConfig| {
+            if commitment.is_finalized() {
+                2
+            } else if commitment.is_at_least_confirmed() {
+                1
+            } else {
+                0
+            }
+        };
+        let default = self.rpc_client.inner().commitment();
+        instructions
+            .iter()
+            .filter(|ix| ix.program_id != compute_budget::id())
+            .map(|ix| {
+                self.confirm_commitments
+                    .get(&ComputeOp::of(ix, &self.market_info.program_id))
+                    .copied()
+                    .unwrap_or(default)
+            })
+            .max_by_key(rank)
+            .unwrap_or(default)
+    }
+
+    /// Sums the compute units to reserve for `instructions`, ignoring compute budget instructions.
+    pub fn compute_unit_limit(&self, instructions: &[Instruction]) -> u32 {
+        instructions
+            .iter()
+            .filter(|ix| ix.program_id != compute_budget::id())
+            .map(|ix| self.cu_limit_for(ComputeOp::of(ix, &self.market_info.program_id)))
+            .fold(0u32, u32::saturating_add)
+            .min(MAX_COMPUTE_UNIT_LIMIT)
+    }
+
+    /// Prices every transaction at `percentile` of the fees recently paid to write this market's
+    /// accounts, re-estimated before each send, see `estimate_priority_fee`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     ob_client.auto_priority_fee(75);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn auto_priority_fee(&mut self, percentile: u8) {
+        self.priority_fee = Some(PriorityFee::Auto(percentile.min(100)));
+    }
+
+    /// Estimates a compute unit price from `getRecentPrioritizationFees` for the market, bids,
+    /// asks, request queue and event queue accounts.
+    ///
+    /// # Arguments
+    ///
+    /// * `percentile` - The percentile (0-100) of the recent fees to return.
+    ///
+    /// # Returns
+    ///
+    /// The fee in micro-lamports per compute unit, at least `PRIORITY_FEE_FLOOR`, which is also
+    /// returned when the RPC reports no recent fees.
+    pub async fn estimate_priority_fee(&self, percentile: u8) -> Result<u64> {
+        let accounts = [
+            self.market_info.market_address,
+            self.market_info.bids_address,
+            self.market_info.asks_address,
+            self.market_info.request_queue,
+            self.market_info.event_queue,
+        ];
+        let mut fees: Vec<u64> = self
+            .rpc_client
+            .inner()
+            .get_recent_prioritization_fees(&accounts)
+            .await?
+            .into_iter()
+            .map(|fee| fee.prioritization_fee)
+            .collect();
+        if fees.is_empty() {
+            debug!("[*] No recent prioritization fees, using the floor");
+            return Ok(PRIORITY_FEE_FLOOR);
+        }
+
+        fees.sort_unstable();
+        let rank = (fees.len() * percentile.min(100) as usize).div_ceil(100);
+        let fee = fees[rank.saturating_sub(1)];
+        debug!(
+            "[*] Priority fee p{} of {} slots: {}",
+            percentile,
+            fees.len(),
+            fee
+        );
+
+        Ok(fee.max(PRIORITY_FEE_FLOOR))
+    }
+
+    /// Returns the compute unit price to attach to a transaction, following `priority_fee`, or
+    /// the highest recent fee when it is not set.
+    async fn compute_unit_price(&self) -> Result<u64> {
+        match self.priority_fee {
+            Some(PriorityFee::Fixed(fee)) => Ok(fee),
+            Some(PriorityFee::Auto(percentile)) => self.estimate_priority_fee(percentile).await,
+            None => Ok(self
+                .rpc_client
+                .inner()
+                .get_recent_prioritization_fees(&[])
+                .await?
+                .into_iter()
+                .map(|fee| fee.prioritization_fee)
+                .fold(PRIORITY_FEE_FLOOR, u64::max)),
+        }
+    }
+
+    /// Refuses to send transactions once the fee payer holds less than `lamports`.
+    ///
+    /// The balance is checked before every write, so a client running out of SOL fails with a
+    /// clear error instead of having its transactions dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     ob_client.set_min_sol_balance(10_000_000);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_min_sol_balance(&mut self, lamports: u64) {
+        self.min_sol_balance = Some(lamports);
+    }
+
+    /// Refuses to send transactions whose estimated fee is above `lamports`.
+    ///
+    /// The estimate, see [`OBClient::estimate_fee`], includes the priority fee, so during a fee
+    /// spike a client on `PriorityFee::Auto` stops sending instead of paying ever more.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     ob_client.auto_priority_fee(75);
+    ///     ob_client.set_max_fee_lamports(100_000);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_max_fee_lamports(&mut self, lamports: u64) {
+        self.max_fee_lamports = Some(lamports);
+    }
+
+    /// Estimates the fee of a transaction of `instructions` paid by the owner, in lamports.
+    ///
+    /// That is `LAMPORTS_PER_SIGNATURE` per required signature plus the compute unit limit times
+    /// the compute unit price, read from the compute budget instructions. Without a limit
+    /// instruction the one `send_instructions` would add is assumed; without a price, none.
+    pub fn estimate_fee(&self, instructions: &[Instruction]) -> u64 {
+        let (units, micro_lamports) = compute_budget_of(instructions);
+        let units = units.unwrap_or_else(|| self.compute_unit_limit(instructions));
+        let signatures = Message::new(instructions, Some(&self.owner_key))
+            .header
+            .num_required_signatures as u64;
+        let priority = (units as u128 * micro_lamports as u128).div_ceil(1_000_000);
+
+        signatures * LAMPORTS_PER_SIGNATURE + priority.min(u64::MAX as u128) as u64
+    }
+
+    /// Reports the compute budget, fee and size of a transaction of `instructions` as
+    /// `send_instructions` would send it, without sending anything.
+    ///
+    /// The compute unit limit and, with a priority fee configured, the compute unit price are
+    /// added the same way as on send, so the report covers the instructions returned by a dry
+    /// run as well as ones that already set their budget.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the priority fee cannot be fetched.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::matching::Side;
+    /// use openbook::v1::ob_client::OBClient;
+    /// use openbook::v1::orders::OrderReturnType;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     if let Some(OrderReturnType::Instructions(instructions)) = ob_client
+    ///         .place_limit_order(5.0, Side::Bid, 0.0, false, 2.1)
+    ///         .await?
+    ///     {
+    ///         println!("{}", ob_client.transaction_budget(&instructions).await?);
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn transaction_budget(&self, instructions: &[Instruction]) -> Result<TxBudget> {
+        let instructions = self.with_compute_budget(instructions.to_vec()).await?;
+        let (units, micro_lamports) = compute_budget_of(&instructions);
+        let signatures = Message::new(&instructions, Some(&self.owner_key))
+            .header
+            .num_required_signatures as u64;
+
+        Ok(TxBudget {
+            compute_unit_limit: units.unwrap_or_else(|| self.compute_unit_limit(&instructions)),
+            compute_unit_price: micro_lamports,
+            signatures,
+            fee_lamports: self.estimate_fee(&instructions),
+            size: self.transaction_size(&instructions),
+        })
+    }
+
+    /// Returns an error if the estimated fee of `instructions` is above `max_fee_lamports`.
+    fn check_fee(&self, instructions: &[Instruction]) -> Result<()> {
+        let Some(max_fee) = self.max_fee_lamports else {
+            return Ok(());
+        };
+        let fee = self.estimate_fee(instructions);
+        if fee > max_fee {
+            error!(
+                "[*] Transaction fee of {} lamports is above the cap of {}, not sending",
+                fee, max_fee
+            );
+            return Err(anyhow!(
+                "Estimated transaction fee of {fee} lamports is above the configured maximum of {max_fee} lamports"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns an error if the fee payer holds less than `min_sol_balance`.
+    async fn check_sol_balance(&self) -> Result<()> {
+        let Some(min_lamports) = self.min_sol_balance else {
+            return Ok(());
+        };
+        let lamports = self.rpc_client.inner().get_balance(&self.owner_key).await?;
+        if lamports < min_lamports {
+            error!(
+                "[*] Fee payer {} holds {} lamports, below the minimum of {}",
+                self.owner_key, lamports, min_lamports
+            );
+            return Err(anyhow!(
+                "Fee payer {} holds {} SOL, below the configured minimum of {} SOL, top it up before sending",
+                self.owner_key,
+                lamports as f64 / 1_000_000_000.0,
+                min_lamports as f64 / 1_000_000_000.0
+            ));
+        }
+        Ok(())
+    }
+
+    /// Simulates `instructions` when `check_funds` is set and they place an order, and returns
+    /// an error if the simulation fails, e.g. because the wallet cannot pay for the order.
+    async fn check_placement_funds(&self, instructions: &[Instruction]) -> Result<()> {
+        let places = instructions
+            .iter()
+            .any(|ix| ComputeOp::of(ix, &self.market_info.program_id) == ComputeOp::Place);
+        if !self.check_funds || !places {
+            return Ok(());
+        }
+        let funds = self.simulate_funds(instructions).await?;
+        debug!("[*] Simulated placement: {:?}", funds);
+        Ok(())
+    }
+
+    /// Signs and sends `instructions`, reserving compute units for them unless a limit is set.
+    async fn send_instructions(&self, instructions: Vec<Instruction>) -> Result<(bool, Signature)> {
+        let (instructions, skip_preflight, commitment) =
+            self.prepare_instructions(instructions).await?;
+        self.rpc_client
+            .send_and_confirm_with_signer(
+                self.signer()?.as_ref(),
+                instructions,
+                skip_preflight,
+                commitment,
+            )
+            .await
+    }
+
+    /// Runs the checks every transaction goes through and adds its compute budget.
+    ///
+    /// Returns the instructions to sign, whether to skip preflight and the commitment to
+    /// confirm at.
+    async fn prepare_instructions(
+        &self,
+        instructions: Vec<Instruction>,
+    ) -> Result<(Vec<Instruction>, bool, CommitmentConfig)> {
+        self.check_sol_balance().await?;
+        let instructions = self.with_compute_budget(instructions).await?;
+        self.check_transaction_size(&instructions)?;
+        self.check_fee(&instructions)?;
+        self.check_placement_funds(&instructions).await?;
+
+        let skip_preflight = self
+            .skip_preflight
+            .applies(&instructions, &self.market_info.program_id);
+        let commitment = self.confirm_commitment_for(&instructions);
+        Ok((instructions, skip_preflight, commitment))
+    }
+
+    /// Adds the compute unit limit, and the compute unit price when a priority fee is
+    /// configured, unless `instructions` already set them.
+    async fn with_compute_budget(
+        &self,
+        mut instructions: Vec<Instruction>,
+    ) -> Result<Vec<Instruction>> {
+        // The first byte of a compute budget instruction is its tag, 2 is `SetComputeUnitLimit`.
+        let has_limit = instructions
+            .iter()
+            .any(|ix| ix.program_id == compute_budget::id() && ix.data.first() == Some(&2));
+        if !has_limit {
+            let units = self.compute_unit_limit(&instructions);
+            instructions.insert(0, ComputeBudgetInstruction::set_compute_unit_limit(units));
+        }
+        // 3 is `SetComputeUnitPrice`.
+        let has_price = instructions
+            .iter()
+            .any(|ix| ix.program_id == compute_budget::id() && ix.data.first() == Some(&3));
+        if !has_price && self.priority_fee.is_some() {
+            let price = self.compute_unit_price().await?;
+            instructions.insert(0, ComputeBudgetInstruction::set_compute_unit_price(price));
+        }
+
+        Ok(instructions)
+    }
+
+    /// Returns the size of a signed transaction of `instructions` paid by the owner, in bytes.
+    fn transaction_size(&self, instructions: &[Instruction]) -> usize {
+        let message = Message::new(instructions, Some(&self.owner_key));
+        let signatures = message.header.num_required_signatures as usize;
+        // Short vec length prefix, then the signatures and the message.
+        1 + signatures * 64 + message.serialize().len()
+    }
+
+    /// Errors if a transaction of `instructions` paid by the owner would not fit in a packet.
+    fn check_transaction_size(&self, instructions: &[Instruction]) -> Result<()> {
+        let size = self.transaction_size(instructions);
+        if size > PACKET_DATA_SIZE {
+            return Err(anyhow!(
+                "Transaction of {} instructions is {size} bytes, over the {PACKET_DATA_SIZE} byte limit",
+                instructions.len()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Loads information about bids, asks, and the open orders associated with the wallet from the market state.
+    ///
+    /// This function fetches and processes bids information, including extracting the bids and asks addresses
+    /// and loading the open orders. It also determines the maximum bid price and minimum ask price.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `OBClient` struct.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a tuple of `(bids_address, asks_address, open_orders)` if successful,
+    /// or an error if loading the bids information fails.
+    ///
+    /// # Errors
+    ///
+    /// This function may return an error if there is an issue with fetching accounts
+    /// or processing the bids information.
+    pub async fn load_bids_asks_info(&mut self) -> Result<(Pubkey, Pubkey, OpenOrders)> {
+        let mut account = self
+            .rpc_client
+            .inner()
+            .get_account(&self.market_info.market_address)
+            .await?;
+        let program_id_binding = self.market_info.program_id;
+        let market_account_binding = self.market_info.market_address;
+        let account_info;
+        {
+            account_info = create_account_info_from_account(
+                &mut account,
+                &market_account_binding,
+                &program_id_binding,
+                false,
+                false,
+            );
+        }
+        let market_state = MarketState::load(&account_info, &self.market_info.program_id, false)?;
+
+        let bids_address = self.market_info.bids_address;
+        let asks_address = self.market_info.asks_address;
+
+        let mut bids_account = self.rpc_client.inner().get_account(&bids_address).await?;
+        let mut bids_book_account = bids_account.clone();
+        let bids_book_info = create_account_info_from_account(
+            &mut bids_book_account,
+            &bids_address,
+            &self.market_info.program_id,
+            false,
+            false,
+        );
+        let mut bids_book = market_state.load_bids_mut(&bids_book_info)?;
+        let book_bids = drain_slab(&mut bids_book, Side::Bid, &self.market_info);
+
+        let bids_info = create_account_info_from_account(
+            &mut bids_account,
+            &bids_address,
+            &self.market_info.program_id,
+            false,
+            false,
+        );
+        let mut bids = market_state.load_bids_mut(&bids_info)?;
+        let (open_bids, open_bids_prices, max_bid) = self.process_bids(&mut bids)?;
+
+        let mut asks_account = self.rpc_client.inner().get_account(&asks_address).await?;
+        let mut asks_book_account = asks_account.clone();
+        let asks_book_info = create_account_info_from_account(
+            &mut asks_book_account,
+            &asks_address,
+            &self.market_info.program_id,
+            false,
+            false,
+        );
+        let mut asks_book = market_state.load_asks_mut(&asks_book_info)?;
+        let book_asks = drain_slab(&mut asks_book, Side::Ask, &self.market_info);
+
+        let asks_info = create_account_info_from_account(
+            &mut asks_account,
+            &asks_address,
+            &self.market_info.program_id,
+            false,
+            false,
+        );
+        let mut asks = market_state.load_asks_mut(&asks_info)?;
+        let (open_asks, open_asks_prices, min_ask) = self.process_asks(&mut asks)?;
+
+        self.open_orders = OpenOrders {
+            oo_key: self.open_orders.oo_key,
+            min_ask,
+            max_bid,
+            open_asks,
+            open_bids,
+            bids_address,
+            asks_address,
+            open_asks_prices,
+            open_bids_prices,
+            base_total: 0.,
+            quote_total: 0.,
+        };
+        self.order_book = OrderBook {
+            bids: book_bids,
+            asks: book_asks,
+        };
+
+        Ok((bids_address, asks_address, self.open_orders.clone()))
+    }
+
+    /// Summarizes the market in UI units: mints, tick size, minimum order size and top of book.
+    ///
+    /// The best bid and ask come from the last loaded book snapshot, no RPC call is made.
+    pub fn market_overview(&self) -> MarketOverview {
+        let market = &self.market_info;
+        MarketOverview {
+            market: market.market_address,
+            base_mint: market.base_mint,
+            base_decimals: market.coin_decimals,
+            quote_mint: market.quote_mint,
+            quote_decimals: market.pc_decimals,
+            tick_size: market.tick_size(),
+            min_order_size: market.min_order_size(),
+            best_bid: self.order_book.best_bid().map(|order| order.price),
+            best_ask: self.order_book.best_ask().map(|order| order.price),
+            price_decimals: market.price_decimals(),
+            size_decimals: market.size_decimals(),
+        }
+    }
+
+    /// Estimates the fill of a market order against the last loaded book snapshot.
+    ///
+    /// This is a purely local computation: it walks the opposing levels of `order_book`
+    /// without any RPC call, so call `load_bids_asks_info` first for a fresh snapshot.
+    ///
+    /// # Arguments
+    ///
+    /// * `side` - The side of the market order (`Bid` buys from the asks, `Ask` sells into the bids).
+    /// * `size_quote` - The order size in quote currency.
+    ///
+    /// # Returns
+    ///
+    /// A `FillEstimate` with the average and worst fill prices, the slippage relative to the
+    /// best opposing price, and the filled and unfilled amounts. Fees are not included.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `size_quote` is not a positive amount.
+    pub fn simulate_market_fill(&self, side: Side, size_quote: f64) -> Result<FillEstimate> {
+        if size_quote.is_nan() || size_quote <= 0.0 {
+            return Err(anyhow!(
+                "Market order size must be positive, got {size_quote}"
+            ));
+        }
+
+        Ok(self.order_book.estimate_fill(side, size_quote))
+    }
+
+    /// Limits how many RPC requests per second this client (and every clone sharing its
+    /// connection) may send.
+    ///
+    /// When the limit is reached, requests wait for their turn instead of failing, so batched
+    /// operations pace themselves on throttled RPC providers. Passing `0` removes the limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `requests_per_second` - The maximum number of requests per second.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the client's `Rpc` was built with [`Rpc::new`], whose requests
+    /// cannot be rate limited.
+    pub fn set_rate_limit(&self, requests_per_second: u32) -> Result<()> {
+        self.rpc_client.set_rate_limit(requests_per_second)
+    }
+
+    /// Enables or disables rebuilding the RPC connection after connection-level errors.
+    ///
+    /// On by default: a request failing because the connection was refused or reset is retried
+    /// once on a fresh connection. Disable it to see those errors right away.
+    pub fn set_reconnect(&self, enabled: bool) {
+        self.rpc_client.set_reconnect(enabled);
+    }
+
+    /// Times every RPC request sent from now on, logging each duration at debug level.
+    ///
+    /// Use `rpc_profile` at the end of a run to see which calls the time went to.
+    pub fn enable_rpc_profiling(&self) {
+        self.rpc_client.enable_profiling();
+    }
+
+    /// Returns the count, p50, p99 and total latency of each RPC method called since
+    /// `enable_rpc_profiling`, slowest total first.
+    pub fn rpc_profile(&self) -> Vec<RpcCallStats> {
+        self.rpc_client.profile_summary()
+    }
+
+    /// Chooses which transactions are sent with `skipPreflight`.
+    ///
+    /// Skipping the simulation lowers latency, e.g. for cancels racing a moving market, while
+    /// placements keep it with `SkipPreflight::Cancels`. Every transaction is still confirmed,
+    /// so one that fails on-chain or is dropped is reported as unconfirmed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::{OBClient, SkipPreflight};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     ob_client.set_skip_preflight(SkipPreflight::Cancels);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_skip_preflight(&mut self, skip_preflight: SkipPreflight) {
+        self.skip_preflight = skip_preflight;
+    }
+
+    /// Sets whether `apply_quote_plan` and the `cancel_settle_place` combos may cancel orders
+    /// without replacing them.
+    ///
+    /// With `CancelPolicy::RequireReplacement`, a requote never leaves a side without a quote:
+    /// each cancel is sent atomically with a new order on its side, and a plan cancelling a
+    /// side without quoting it again is refused. A combo whose cancels would empty a side it
+    /// places nothing on, e.g. `cancel_settle_place_bid` with asks resting or an order rounding
+    /// to zero lots, is refused too. Flatten and cancel commands are unaffected.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::{CancelPolicy, OBClient};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     ob_client.set_cancel_policy(CancelPolicy::RequireReplacement);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_cancel_policy(&mut self, cancel_policy: CancelPolicy) {
+        self.cancel_policy = cancel_policy;
+    }
+
+    /// Fetches the raw data of any account through the client's RPC connection.
+    ///
+    /// The request uses the client's endpoint, commitment and rate limit, so auxiliary accounts
+    /// (oracles, mints, ...) can be read without a second RPC client.
+    ///
+    /// # Arguments
+    ///
+    /// * `pubkey` - The account to fetch.
+    ///
+    /// # Returns
+    ///
+    /// The account data and the slot it was read at.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the account does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let (data, slot) = ob_client
+    ///         .fetch_account_raw(ob_client.market_info.base_mint)
+    ///         .await?;
+    ///
+    ///     println!("{} bytes at slot {}", data.len(), slot);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn fetch_account_raw(&self, pubkey: Pubkey) -> Result<(Vec<u8>, u64)> {
+        let response = self
+            .rpc_client
+            .inner()
+            .get_account_with_commitment(&pubkey, self.rpc_client.inner().commitment())
+            .await?;
+        let account = response
+            .value
+            .ok_or_else(|| anyhow!("Account {} not found", pubkey))?;
+
+        Ok((account.data, response.context.slot))
+    }
+
+    /// Reuses one blockhash across the transactions sent within `window`, see
+    /// [`Rpc::set_blockhash_reuse`]. `Duration::ZERO` fetches one per transaction.
+    pub fn set_blockhash_reuse(&self, window: Duration) {
+        self.rpc_client.set_blockhash_reuse(window);
+    }
+
+    /// Fetches a transaction and decodes its OpenBook instructions.
+    ///
+    /// Instructions of the client's program are decoded whatever market they target; those of
+    /// the client's own market also show prices and sizes in UI units.
+    ///
+    /// # Arguments
+    ///
+    /// * `signature` - The signature of the transaction, sent by anyone.
+    ///
+    /// # Returns
+    ///
+    /// The decoded instructions in message order, empty if none targets the program.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the transaction cannot be fetched or decoded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::signature::Signature;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     for instruction in ob_client.decode_transaction(&Signature::default()).await? {
+    ///         println!("{}", instruction);
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn decode_transaction(
+        &self,
+        signature: &Signature,
+    ) -> Result<Vec<DecodedInstruction>> {
+        let confirmed_tx = self.rpc_client.fetch_transaction(signature).await?;
+        let transaction = confirmed_tx
+            .transaction
+            .transaction
+            .decode()
+            .ok_or_else(|| anyhow!("Unable to decode transaction {}", signature))?;
+        let loaded_addresses: Vec<Pubkey> = confirmed_tx
+            .transaction
+            .meta
+            .as_ref()
+            .and_then(|meta| Option::<&UiLoadedAddresses>::from(meta.loaded_addresses.as_ref()))
+            .map(|loaded| {
+                loaded
+                    .writable
+                    .iter()
+                    .chain(&loaded.readonly)
+                    .filter_map(|key| key.parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(decode_instructions(
+            &transaction,
+            &loaded_addresses,
+            &self.market_info.program_id,
+            Some(&self.market_info),
+        ))
+    }
+
+    /// Lists the transactions that touched this client's open orders account, newest first.
+    ///
+    /// This wraps `getSignaturesForAddress`, so it reaches as far back as the node keeps
+    /// history, well beyond the fills the event queue still holds. Page through it by passing
+    /// the last signature of a page as `before` of the next, and decode each transaction with
+    /// [`OBClient::decode_transaction`].
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The most signatures to return, 1 to 1000.
+    /// * `before` - Start after this signature, newest first when `None`.
+    /// * `until` - Stop at this signature, excluded; back to the oldest known when `None`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `limit` is out of range or the node cannot be queried.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let mut before = None;
+    ///     loop {
+    ///         let page = ob_client.signatures_for_open_orders(100, before, None).await?;
+    ///         for info in &page {
+    ///             println!("{} {:?}", info.signature, info.block_time);
+    ///         }
+    ///         match page.last() {
+    ///             Some(last) => before = Some(last.signature),
+    ///             None => break,
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn signatures_for_open_orders(
+        &self,
+        limit: usize,
+        before: Option<Signature>,
+        until: Option<Signature>,
+    ) -> Result<Vec<SignatureInfo>> {
+        if !(1..=MAX_SIGNATURES_PER_PAGE).contains(&limit) {
+            return Err(anyhow!(
+                "Signature limit must be between 1 and {MAX_SIGNATURES_PER_PAGE}, got {limit}"
+            ));
+        }
+        let statuses = self
+            .rpc_client
+            .fetch_signatures_page(&self.open_orders.oo_key, Some(limit), before, until)
+            .await?;
+        statuses
+            .into_iter()
+            .map(|status| {
+                Ok(SignatureInfo {
+                    signature: status
+                        .signature
+                        .parse()
+                        .map_err(|e| anyhow!("Invalid signature {}: {e}", status.signature))?,
+                    slot: status.slot,
+                    block_time: status.block_time,
+                    err: status.err.map(|err| err.to_string()),
+                    memo: status.memo,
+                })
+            })
+            .collect()
+    }
+
+    /// Waits for a transaction to reach the client's commitment and collects its outcome.
+    ///
+    /// # Returns
+    ///
+    /// A `TxResult` with the slot and error reported by `getSignatureStatuses`. A default
+    /// signature, returned by the send path when nothing was sent, is reported as not sent
+    /// without any RPC call.
+    pub async fn transaction_result(&self, signature: &Signature) -> Result<TxResult> {
+        if *signature == Signature::default() {
+            return Ok(TxResult {
+                signature: *signature,
+                slot: None,
+                err: Some("transaction was not sent".to_string()),
+                confirmed: false,
+            });
+        }
+
+        let commitment = self.rpc_client.inner().commitment();
+        let confirmed = self
+            .confirm_signature(signature, commitment, DEFAULT_CONFIRM_TIMEOUT)
+            .await?;
+        let status = self
+            .rpc_client
+            .inner()
+            .get_signature_statuses(&[*signature])
+            .await?
+            .value
+            .into_iter()
+            .next()
+            .flatten();
+
+        Ok(TxResult {
+            signature: *signature,
+            slot: status.as_ref().map(|status| status.slot),
+            err: status
+                .and_then(|status| status.err)
+                .map(|err| err.to_string()),
+            confirmed,
+        })
+    }
+
+    /// Waits until a transaction signature reaches the given commitment level.
+    ///
+    /// Confirmation is done by polling `getSignatureStatuses`, which is much cheaper than
+    /// fetching the full transaction with `fetch_transaction`.
+    ///
+    /// # Arguments
+    ///
+    /// * `signature` - The signature of the transaction to confirm.
+    /// * `commitment` - The commitment level the transaction must reach.
+    /// * `timeout` - How long to keep polling before giving up.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the transaction reached the commitment without error, `false` if it failed
+    /// on-chain or was not confirmed within `timeout`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::signature::Signature;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let confirmed = ob_client
+    ///         .confirm_signature(&Signature::default(), commitment, Duration::from_secs(1))
+    ///         .await?;
+    ///
+    ///     println!("{:?}", confirmed);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn confirm_signature(
+        &self,
+        signature: &Signature,
+        commitment: CommitmentConfig,
+        timeout: Duration,
+    ) -> Result<bool> {
+        Ok(self
+            .rpc_client
+            .confirm_signature(signature, commitment, timeout)
+            .await?)
+    }
+
+    /// Re-reads the book and looks for a resting order of this client by its client order id.
+    ///
+    /// A confirmed placement may still not rest on the book, e.g. when it matched immediately or
+    /// was a rejected post-only order. This closes that gap.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_order_id` - The client order id the order was placed with.
+    ///
+    /// # Returns
+    ///
+    /// The resting order, with its on-book order id, or `None` if it is not on the book.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let order = ob_client.find_resting_order(42).await?;
+    ///
+    ///     println!("{:?}", order);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn find_resting_order(&mut self, client_order_id: u64) -> Result<Option<BookOrder>> {
+        self.load_bids_asks_info().await?;
+
+        let oo_key = self.open_orders.oo_key;
+        Ok(self
+            .order_book
+            .bids
+            .iter()
+            .chain(self.order_book.asks.iter())
+            .find(|order| order.owner == oo_key && order.client_order_id == client_order_id)
+            .copied())
+    }
+
+    /// Builds the order id the program gives an order of `side` at `price` with sequence
+    /// number `seq`, see [`encode_order_id`].
+    ///
+    /// The price is converted to lots the way orders are placed, rounded down to the tick.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::matching::Side;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let order_id = ob_client.order_id_for(2.1, Side::Bid, 42);
+    ///     assert_eq!(ob_client.order_seq_from_order_id(order_id, Side::Bid), 42);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn order_id_for(&self, price: f64, side: Side, seq: u64) -> u128 {
+        let price_lots = (price * self.market_info.price_factor()) as u64;
+        encode_order_id(price_lots, side, seq)
+    }
+
+    /// Returns the UI price encoded in the high bits of `order_id`.
+    pub fn price_from_order_id(&self, order_id: u128, side: Side) -> f64 {
+        let (price_lots, _) = decode_order_id(order_id, side);
+        self.market_info.price_lots_to_ui(price_lots)
+    }
+
+    /// Returns the sequence number encoded in the low bits of an order id of `side`.
+    pub fn order_seq_from_order_id(&self, order_id: u128, side: Side) -> u64 {
+        decode_order_id(order_id, side).1
+    }
+
+    /// Converts an order size in quote currency into base lots at the given price.
+    ///
+    /// This is the sizing used by `place_limit_order`: `quote / price` base units, rounded down
+    /// to whole base lots.
+    ///
+    /// # Arguments
+    ///
+    /// * `quote` - The order size in quote UI units.
+    /// * `price` - The limit price in UI units.
+    ///
+    /// # Returns
+    ///
+    /// The number of base lots, `0` if `price` is not positive or the size is below one lot.
+    pub fn quote_to_base_size(&self, quote: f64, price: f64) -> u64 {
+        if price.is_nan() || price <= 0.0 {
+            return 0;
+        }
+        let base_d_factor = 10f64.powi(self.market_info.coin_decimals as i32);
+        let base_lot_factor = self.market_info.coin_lot_size as f64;
+
+        (quote / price * base_d_factor / base_lot_factor) as u64
+    }
+
+    /// Converts an order size in quote currency into base lots, honoring the minimum order size.
+    ///
+    /// The program rejects orders of zero base lots, so the smallest order is one lot of
+    /// `coin_lot_size` native units, `Market::min_order_size` in UI units. A positive size
+    /// below it is refused, or rounded up to one lot with `round_up_to_min_size`.
+    ///
+    /// # Arguments
+    ///
+    /// * `quote` - The order size in quote UI units.
+    /// * `price` - The limit price in UI units.
+    ///
+    /// # Returns
+    ///
+    /// The number of base lots, `0` if `quote` or `price` is not positive.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error stating the minimum if a positive size rounds to zero lots and rounding
+    /// up is disabled.
+    pub fn order_base_lots(&self, quote: f64, price: f64) -> Result<u64> {
+        let base_lots = self.quote_to_base_size(quote, price);
+        if base_lots > 0 || quote.is_nan() || quote <= 0.0 || price.is_nan() || price <= 0.0 {
+            return Ok(base_lots);
+        }
+
+        let min_size = self.market_info.min_order_size();
+        if self.round_up_to_min_size {
+            warn!(
+                "[*] {} quote @ {} is below the minimum order size, rounded up to {} base",
+                quote,
+                price,
+                self.market_info.format_size(min_size)
+            );
+            return Ok(1);
+        }
+        Err(anyhow!(
+            "Order of {} quote @ {} is {} base, below the market's minimum order size of {} base ({} quote at this price)",
+            quote,
+            price,
+            quote / price,
+            self.market_info.format_size(min_size),
+            min_size * price
+        ))
+    }
+
+    /// Fetches the raw event queue header so callers can see if cranking is needed.
+    ///
+    /// With `event_queue_chunk_len` set, only the header is requested, through a `dataSlice`.
+    pub async fn fetch_event_queue_stats(&self) -> Result<EventQueueStats> {
+        if self.event_queue_chunk_len.is_some() {
+            let header = self
+                .rpc_client
+                .fetch_account_data_slice(
+                    &self.market_info.event_queue,
+                    0,
+                    EventQueueStats::HEADER_LEN,
+                )
+                .await?;
+            return EventQueueStats::from_account_prefix(&header);
+        }
+
+        let data = self.fetch_event_queue_data().await?;
+        let queue = EventQueueView::from_account_data(&data)?;
+
+        Ok(queue.stats)
+    }
+
+    /// Fetches the header of the market's request queue.
+    ///
+    /// Together with `fetch_event_queue_stats` this shows whether orders are stuck before or
+    /// after matching. Markets whose program does not keep a request queue report
+    /// `RequestQueueStats::NotApplicable` rather than an error.
+    ///
+    /// # Returns
+    ///
+    /// The queue header with its pending request count, or why the market has no request queue.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the account cannot be fetched.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let stats = ob_client.request_queue_status().await?;
+    ///
+    ///     println!("{:?}", stats.pending());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn request_queue_status(&self) -> Result<RequestQueueStats> {
+        let address = self.market_info.request_queue;
+        if address == Pubkey::default() {
+            return Ok(RequestQueueStats::NotApplicable {
+                reason: "the market does not reference a request queue".to_string(),
+            });
+        }
+        let account = self
+            .rpc_client
+            .fetch_multiple_accounts(&[address])
+            .await?
+            .pop()
+            .flatten();
+
+        Ok(match account {
+            None => RequestQueueStats::NotApplicable {
+                reason: format!("request queue account {address} does not exist"),
+            },
+            Some(account) if account.owner != self.market_info.program_id => {
+                RequestQueueStats::NotApplicable {
+                    reason: format!(
+                        "request queue account {address} is owned by {}, not the DEX program",
+                        account.owner
+                    ),
+                }
+            }
+            Some(account) => RequestQueueStats::from_account_data(&account.data),
+        })
+    }
+
+    /// Samples the event queue twice, `interval` apart, to measure how fast it grows and drains.
+    ///
+    /// The first sample reads the whole queue for its capacity, the second only its header.
+    /// Consumption covers every cranker of the market, not just this client.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - Time between the two samples. Longer intervals smooth out bursts.
+    ///
+    /// # Returns
+    ///
+    /// A `CrankStatus` with the produced and consumed event counts, from which rates and the
+    /// time to clear or overflow the queue are derived.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the event queue cannot be fetched or decoded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let status = ob_client.crank_status(Duration::from_secs(10)).await?;
+    ///
+    ///     println!("Inflow: {}/s, keeping up: {}", status.inflow_per_sec(), status.keeping_up());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn crank_status(&self, interval: Duration) -> Result<CrankStatus> {
+        let data = self.fetch_event_queue_data().await?;
+        let queue = EventQueueView::from_account_data(&data)?;
+        let before = queue.stats;
+        let started = std::time::Instant::now();
+
+        tokio::time::sleep(interval).await;
+
+        let after = self.fetch_event_queue_stats().await?;
+
+        Ok(CrankStatus::from_samples(
+            &before,
+            &after,
+            started.elapsed(),
+            queue.capacity() as u64,
+        ))
+    }
+
+    /// Reads the whole event queue account, in `event_queue_chunk_len` slices if set.
+    pub async fn fetch_event_queue_data(&self) -> Result<Vec<u8>> {
+        match self.event_queue_chunk_len {
+            Some(chunk_len) => {
+                self.rpc_client
+                    .fetch_account_data_chunked(&self.market_info.event_queue, chunk_len)
+                    .await
+            }
+            None => Ok(self
+                .rpc_client
+                .inner()
+                .get_account(&self.market_info.event_queue)
+                .await?
+                .data),
+        }
+    }
+
+    /// Checks whether the event queue has pending events to consume.
+    ///
+    /// Cranking is permissionless unless the market has an `events_authority`, in which case
+    /// only `consume_events_permissioned_instruction` signed by it is accepted.
+    ///
+    /// # Returns
+    ///
+    /// Whether a crank is needed, and the number of pending events to size its `limit`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let (needs_crank, pending) = ob_client.needs_crank().await?;
+    ///     if needs_crank {
+    ///         let open_orders = ob_client
+    ///             .collect_event_queue_open_orders(pending as usize)
+    ///             .await?;
+    ///         let limit = pending.min(u16::MAX as u64) as u16;
+    ///         ob_client.consume_events_instruction(open_orders, limit).await?;
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn needs_crank(&self) -> Result<(bool, u64)> {
+        let stats = self.fetch_event_queue_stats().await?;
+
+        Ok((stats.needs_crank(), stats.count))
+    }
+
+    /// Waits for a signature to reach `commitment` through a `signatureSubscribe` websocket.
+    ///
+    /// The websocket endpoint is read from `WS_URL`, or derived from the RPC url by swapping
+    /// `http(s)` for `ws(s)`. Falls back to polling if the websocket is unavailable.
+    ///
+    /// # Arguments
+    ///
+    /// * `signature` - The signature of the transaction to confirm.
+    /// * `commitment` - The commitment level the transaction must reach.
+    ///
+    /// # Returns
+    ///
+    /// `true` once confirmed, `false` if the transaction failed or was not confirmed within
+    /// `DEFAULT_CONFIRM_TIMEOUT`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::signature::Signature;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let confirmed = ob_client
+    ///         .confirm_via_signature_subscribe(&Signature::default(), commitment)
+    ///         .await?;
+    ///
+    ///     println!("{:?}", confirmed);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn confirm_via_signature_subscribe(
+        &self,
+        signature: &Signature,
+        commitment: CommitmentConfig,
+    ) -> Result<bool> {
+        Ok(self
+            .rpc_client
+            .confirm_via_signature_subscribe(
+                &self.ws_url(),
+                signature,
+                commitment,
+                DEFAULT_CONFIRM_TIMEOUT,
+            )
+            .await?)
+    }
+
+    /// Returns the websocket endpoint: `WS_URL` if set, otherwise the RPC URL with a `ws` scheme.
+    fn ws_url(&self) -> String {
+        std::env::var(WS_URL_ENV).unwrap_or_else(|_| {
+            let rpc_url = self.rpc_client.inner().url();
+            if let Some(rest) = rpc_url.strip_prefix("https://") {
+                format!("wss://{rest}")
+            } else if let Some(rest) = rpc_url.strip_prefix("http://") {
+                format!("ws://{rest}")
+            } else {
+                rpc_url
+            }
+        })
+    }
+
+    /// Streams the top of the book, calling `on_update` every `interval` until it fails.
+    ///
+    /// The bids and asks accounts are followed with websocket subscriptions, on the `WS_URL`
+    /// endpoint (derived from `RPC_URL` by default), and polled when websockets are not
+    /// available. `order_book` is not updated.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - Time between two updates.
+    /// * `on_update` - Receives the best bid and ask, and the size resting at each.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the market or book accounts cannot be fetched or decoded, or as soon
+    /// as `on_update` returns one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     ob_client
+    ///         .watch_top_of_book(Duration::from_millis(500), |top| {
+    ///             println!("{:?} / {:?}", top.best_bid, top.best_ask);
+    ///             Ok(())
+    ///         })
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn watch_top_of_book<F>(&self, interval: Duration, mut on_update: F) -> Result<()>
+    where
+        F: FnMut(&TopOfBook) -> Result<()>,
+    {
+        let market_account = self
+            .rpc_client
+            .inner()
+            .get_account(&self.market_info.market_address)
+            .await?;
+        let book_accounts = [self.market_info.bids_address, self.market_info.asks_address];
+
+        self.rpc_client
+            .watch_accounts(&self.ws_url(), &book_accounts, interval, |accounts| {
+                let mut book = OrderBook::default();
+                for (side, account) in [(Side::Bid, &accounts[0]), (Side::Ask, &accounts[1])] {
+                    let account = account.as_ref().ok_or_else(|| {
+                        anyhow!("The {:?} account of the market is missing", side)
+                    })?;
+                    let orders = self.decode_book_side(&market_account, side, account)?;
+                    match side {
+                        Side::Bid => book.bids = orders,
+                        Side::Ask => book.asks = orders,
+                    }
+                }
+                on_update(&book.top())
+            })
+            .await
+    }
+
+    /// Captures the whole book, and the fills pushed to the event queue, every `interval`.
+    ///
+    /// The bids, asks and event queue accounts are followed like in `watch_top_of_book`. Each
+    /// capture holds the fills whose sequence number is past those of the previous capture,
+    /// so a fill is recorded once as long as no crank consumes it between two captures.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - Time between two captures.
+    /// * `on_record` - Called with every capture; returning an error stops the watch.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the accounts cannot be fetched or decoded, or as soon as
+    /// `on_record` returns one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    /// use openbook::v1::record::{RecordWriter, DEFAULT_ROTATE_INTERVAL};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let mut writer = RecordWriter::new("records", market_id, DEFAULT_ROTATE_INTERVAL)?;
+    ///     ob_client
+    ///         .watch_book_records(Duration::from_secs(1), |record| writer.write(&record))
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn watch_book_records<F>(&self, interval: Duration, mut on_record: F) -> Result<()>
+    where
+        F: FnMut(BookRecord) -> Result<()>,
+    {
+        let market_account = self
+            .rpc_client
+            .inner()
+            .get_account(&self.market_info.market_address)
+            .await?;
+        let accounts = [
+            self.market_info.bids_address,
+            self.market_info.asks_address,
+            self.market_info.event_queue,
+        ];
+        let mut next_seq_num: Option<u64> = None;
+
+        self.rpc_client
+            .watch_accounts(&self.ws_url(), &accounts, interval, |accounts| {
+                let missing = |name: &str| anyhow!("The {name} account of the market is missing");
+                let bids = accounts[0].as_ref().ok_or_else(|| missing("bids"))?;
+                let asks = accounts[1].as_ref().ok_or_else(|| missing("asks"))?;
+                let event_queue = accounts[2].as_ref().ok_or_else(|| missing("event queue"))?;
+
+                let queue = EventQueueView::from_account_data(&event_queue.data)?;
+                let fills = queue
+                    .audit_events(
+                        usize::MAX,
+                        self.market_info.coin_decimals,
+                        self.market_info.pc_decimals,
+                    )
+                    .into_iter()
+                    .filter(|event| event.fill)
+                    .filter(|event| !matches!(next_seq_num, Some(next) if event.seq_num < next))
+                    .collect();
+                next_seq_num = Some(queue.stats.seq_num);
+
+                let ts_ms = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+                on_record(BookRecord {
+                    version: RECORD_SCHEMA_VERSION,
+                    ts_ms,
+                    market: self.market_info.market_address,
+                    bids: self.decode_book_side(&market_account, Side::Bid, bids)?,
+                    asks: self.decode_book_side(&market_account, Side::Ask, asks)?,
+                    fills,
+                })
+            })
+            .await
+    }
+
+    /// Streams the state of this client's open orders account each time it changes.
+    ///
+    /// The account is followed with an `accountSubscribe` websocket on the `WS_URL` endpoint
+    /// (derived from `RPC_URL` by default), see [`Rpc::subscribe_account`] for the reconnection
+    /// handling. The current state is emitted first. A fill shows up as an order leaving
+    /// `orders` or a balance moving from locked to free, a settlement as free balances dropping
+    /// to zero. Updates that cannot be decoded are logged and skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use futures::StreamExt;
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let mut snapshots = Box::pin(ob_client.subscribe_open_orders());
+    ///     while let Some(snapshot) = snapshots.next().await {
+    ///         println!("slot {}: {} orders", snapshot.slot, snapshot.orders.len());
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn subscribe_open_orders(&self) -> impl Stream<Item = OoSnapshot> {
+        let oo_key = self.open_orders.oo_key;
+        let coin_decimals = self.market_info.coin_decimals;
+        let pc_decimals = self.market_info.pc_decimals;
+
+        self.rpc_client
+            .subscribe_account(&self.ws_url(), oo_key)
+            .filter_map(move |(slot, account)| {
+                let snapshot =
+                    OoSnapshot::from_account_data(slot, &account.data, coin_decimals, pc_decimals);
+                futures::future::ready(match snapshot {
+                    Ok(snapshot) => Some(snapshot),
+                    Err(err) => {
+                        warn!(
+                            "[*] Skipping update of {} at slot {}: {}",
+                            oo_key, slot, err
+                        );
+                        None
+                    }
+                })
+            })
+    }
+
+    /// Decodes the bids or asks `account` of the market whose account is `market_account`.
+    fn decode_book_side(
+        &self,
+        market_account: &Account,
+        side: Side,
+        account: &Account,
+    ) -> Result<Vec<BookOrder>> {
+        let program_id = self.market_info.program_id;
+        let mut market_account = market_account.clone();
+        let market_info = create_account_info_from_account(
+            &mut market_account,
+            &self.market_info.market_address,
+            &program_id,
+            false,
+            false,
+        );
+        let market_state = MarketState::load(&market_info, &program_id, false)?;
+
+        let mut account = account.clone();
+        let address = match side {
+            Side::Bid => self.market_info.bids_address,
+            Side::Ask => self.market_info.asks_address,
+        };
+        let info =
+            create_account_info_from_account(&mut account, &address, &program_id, false, false);
+        let mut slab = match side {
+            Side::Bid => market_state.load_bids_mut(&info)?,
+            Side::Ask => market_state.load_asks_mut(&info)?,
+        };
+        let orders = drain_slab(&mut slab, side, &self.market_info);
+        Ok(orders)
+    }
+
+    /// Fetches the owner's SOL balance and the balances of the base and quote token accounts.
+    ///
+    /// Token accounts that do not exist yet are reported with a zero balance.
+    pub async fn wallet_balances(&self) -> Result<WalletBalances> {
+        let lamports = self.rpc_client.inner().get_balance(&self.owner_key).await?;
+
+        let mut balances = WalletBalances {
+            sol: lamports as f64 / 1_000_000_000.0,
+            ..WalletBalances::default()
+        };
+
+        for (account, balance) in [
+            (self.base_ata, &mut balances.base),
+            (self.quote_ata, &mut balances.quote),
+        ] {
+            match self
+                .rpc_client
+                .inner()
+                .get_token_account_balance(&account)
+                .await
+            {
+                Ok(amount) => *balance = amount.ui_amount.unwrap_or_default(),
+                Err(err) => debug!("[*] No balance for token account {}: {}", account, err),
+            }
+        }
+
+        Ok(balances)
+    }
+
+    /// Fetches the token balances held in the market's base and quote vaults.
+    ///
+    /// The vaults hold everything deposited in the market's open orders accounts, locked or
+    /// free, plus the fees not yet swept. Comparing them with the open orders totals is a
+    /// solvency check, and the change across a settle is what it moved out.
+    ///
+    /// # Returns
+    ///
+    /// The base and quote vault balances, in UI units.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a vault cannot be fetched or is not a token account.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let (base, quote) = ob_client.vault_balances().await?;
+    ///
+    ///     println!("base vault {}, quote vault {}", base, quote);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn vault_balances(&self) -> Result<(f64, f64)> {
+        let vaults = [self.market_info.coin_vault, self.market_info.pc_vault];
+        let accounts = self.rpc_client.fetch_multiple_accounts(&vaults).await?;
+
+        let mut amounts = [0u64; 2];
+        for ((amount, vault), account) in amounts.iter_mut().zip(vaults).zip(accounts) {
+            let account = account.ok_or_else(|| anyhow!("Vault {vault} not found"))?;
+            *amount = token_account_amount(&account.data)
+                .ok_or_else(|| anyhow!("Vault {vault} is not a token account"))?;
+        }
+
+        Ok((
+            amounts[0] as f64 / 10f64.powi(self.market_info.coin_decimals as i32),
+            amounts[1] as f64 / 10f64.powi(self.market_info.pc_decimals as i32),
+        ))
+    }
+
+    /// Reads the taker fees the market accrued since they were last swept by its authority.
+    ///
+    /// The market account is re-read, so fees from fills since the client was created are
+    /// included. The program adds the taker fees net of referrer rebates to these counters on
+    /// every fill, and a `SweepFees` by the market authority resets them.
+    ///
+    /// # Returns
+    ///
+    /// The accrued base and quote fees, in UI units.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the market account cannot be fetched or is too short.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let (base, quote) = ob_client.accrued_fees().await?;
+    ///     println!("accrued fees: base {}, quote {}", base, quote);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn accrued_fees(&self) -> Result<(f64, f64)> {
+        let market = self.market_info.market_address;
+        let (data, _) = self.fetch_account_raw(market).await?;
+        let read = |offset: usize| {
+            let bytes = data.get(offset..offset + 8)?;
+            Some(u64::from_le_bytes(bytes.try_into().ok()?))
+        };
+        let (Some(base), Some(quote)) = (
+            read(MARKET_COIN_FEES_ACCRUED_OFFSET),
+            read(MARKET_PC_FEES_ACCRUED_OFFSET),
+        ) else {
+            return Err(anyhow!(
+                "Market account {market} is too short ({} bytes)",
+                data.len()
+            ));
+        };
+
+        Ok((
+            base as f64 / 10f64.powi(self.market_info.coin_decimals as i32),
+            quote as f64 / 10f64.powi(self.market_info.pc_decimals as i32),
+        ))
+    }
+
+    /// Lists every open orders account of the owner on every market of `program_id`, with its
+    /// orders and balances.
+    ///
+    /// The markets and their mints are read to convert balances with each market's decimals;
+    /// an account whose market or mints cannot be read is skipped with a warning.
+    ///
+    /// # Arguments
+    ///
+    /// * `program_id` - The DEX program to scan, e.g. `self.market_info.program_id`.
+    ///
+    /// # Returns
+    ///
+    /// One `OoSummary` per account, sorted by market then account.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let program_id = ob_client.market_info.program_id;
+    ///     for summary in ob_client.all_open_orders_summaries(program_id).await? {
+    ///         println!(
+    ///             "{} on {}: {} orders, unsettled {} base / {} quote",
+    ///             summary.open_orders,
+    ///             summary.market,
+    ///             summary.resting_orders,
+    ///             summary.balances.coin_free,
+    ///             summary.balances.pc_free
+    ///         );
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn all_open_orders_summaries(&self, program_id: Pubkey) -> Result<Vec<OoSummary>> {
+        let accounts = self
+            .rpc_client
+            .fetch_v1_open_orders_of_owner(program_id, self.owner_key)
+            .await?;
+        let read = |data: &[u8], offset: usize| {
+            let bytes = <[u8; 32]>::try_from(data.get(offset..offset + 32)?).ok()?;
+            Some(Pubkey::from(bytes))
+        };
+
+        let mut markets: Vec<Pubkey> = accounts
+            .iter()
+            .filter_map(|(_, account)| read(&account.data, 13))
+            .collect();
+        markets.sort();
+        markets.dedup();
+        let mut market_mints: HashMap<Pubkey, (Pubkey, Pubkey)> = HashMap::new();
+        for chunk in markets.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let market_accounts = self.rpc_client.fetch_multiple_accounts(chunk).await?;
+            for (market, account) in chunk.iter().zip(market_accounts) {
+                let data = account.map(|account| account.data).unwrap_or_default();
+                match (
+                    read(&data, MARKET_COIN_MINT_OFFSET),
+                    read(&data, MARKET_PC_MINT_OFFSET),
+                ) {
+                    (Some(coin_mint), Some(pc_mint)) => {
+                        market_mints.insert(*market, (coin_mint, pc_mint));
+                    }
+                    _ => warn!("[*] Skipping {}: not a readable market account", market),
+                }
+            }
+        }
+
+        let mut mints: Vec<Pubkey> = market_mints
+            .values()
+            .flat_map(|(coin_mint, pc_mint)| [*coin_mint, *pc_mint])
+            .collect();
+        mints.sort();
+        mints.dedup();
+        let mut decimals: HashMap<Pubkey, u8> = HashMap::new();
+        for chunk in mints.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let mint_accounts = self.rpc_client.fetch_multiple_accounts(chunk).await?;
+            for (mint, account) in chunk.iter().zip(mint_accounts) {
+                if let Some(mint_decimals) = account
+                    .as_ref()
+                    .and_then(|account| account.data.get(MINT_DECIMALS_OFFSET).copied())
+                {
+                    decimals.insert(*mint, mint_decimals);
+                }
+            }
+        }
+
+        let mut summaries = Vec::with_capacity(accounts.len());
+        for (key, account) in accounts {
+            let Some((coin_mint, pc_mint)) =
+                read(&account.data, 13).and_then(|market| market_mints.get(&market))
+            else {
+                continue;
+            };
+            let (Some(coin_decimals), Some(pc_decimals)) =
+                (decimals.get(coin_mint), decimals.get(pc_mint))
+            else {
+                warn!(
+                    "[*] Skipping {}: unable to read its market's mint decimals",
+                    key
+                );
+                continue;
+            };
+            summaries.push(OoSummary::from_account_data(
+                key,
+                &account.data,
+                *coin_decimals,
+                *pc_decimals,
+            )?);
+        }
+        summaries.sort_by_key(|summary| (summary.market, summary.open_orders));
+
+        Ok(summaries)
+    }
+
+    /// Fetches and decodes the free and total balances of the owner's open orders account.
+    ///
+    /// The free balances are what a settle moves to the wallet, the rest is locked by resting
+    /// orders.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the open orders account cannot be fetched or decoded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let balances = ob_client.open_orders_balances().await?;
+    ///
+    ///     println!("Base free to settle: {}", balances.coin_free);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn open_orders_balances(&self) -> Result<OoBalances> {
+        let oo_key = self.open_orders.oo_key;
+        let data = self
+            .rpc_client
+            .inner()
+            .get_account_data(&oo_key)
+            .await
+            .map_err(|e| anyhow!("Failed to fetch open orders account {}: {}", oo_key, e))?;
+
+        OoBalances::from_account_data(
+            &data,
+            self.market_info.coin_decimals,
+            self.market_info.pc_decimals,
+        )
+    }
+
+    /// Returns the maker rebate and taker fee rates orders of this client pay.
+    ///
+    /// Orders are placed without an SRM or MSRM discount account, so the program applies the
+    /// base fee tier of the market, or the stable tier on the stable markets it lists.
+    pub fn fee_rates(&self) -> FeeRates {
+        // The tiers only expose fee amounts, so the rates are read off a large quantity.
+        const SAMPLE_NATIVE: u64 = 1_000_000_000_000;
+        let tier = FeeTier::from_srm_and_msrm_balances(&self.market_info.market_address, 0, 0);
+        FeeRates {
+            maker_rebate: tier.maker_rebate(SAMPLE_NATIVE) as f64 / SAMPLE_NATIVE as f64,
+            taker: tier.taker_fee(SAMPLE_NATIVE) as f64 / SAMPLE_NATIVE as f64,
+        }
+    }
+
+    /// Computes the largest order the owner can fund at `price`, in quote notional.
+    ///
+    /// An order draws on the free balance of the open orders account first, then on the wallet,
+    /// so both are added up. A bid keeps room for the taker fee in case it matches; an ask is
+    /// paid in base and its fees come out of the quote it receives. The size is rounded down to
+    /// whole base lots.
+    ///
+    /// # Arguments
+    ///
+    /// * `side` - The side of the order.
+    /// * `price` - The limit price in UI units.
+    ///
+    /// # Returns
+    ///
+    /// The notional of the largest fundable order, `0` when the token account it is paid from
+    /// is empty or does not exist. A missing open orders account counts as empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `price` is not positive, or if the balances cannot be fetched.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::matching::Side;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let max_buy = ob_client.max_order_size(Side::Bid, 2.1).await?;
+    ///
+    ///     println!("Largest bid at 2.1: {} quote", max_buy);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn max_order_size(&self, side: Side, price: f64) -> Result<f64> {
+        if price.is_nan() || price <= 0.0 {
+            return Err(anyhow!("Order price must be positive, got {price}"));
+        }
+        let wallet = self.wallet_balances().await?;
+        // A wallet that has not traded on the market yet has no open orders account.
+        let oo_key = self.open_orders.oo_key;
+        let account = if oo_key == Pubkey::default() {
+            None
+        } else {
+            self.rpc_client
+                .inner()
+                .get_account_with_commitment(&oo_key, self.rpc_client.inner().commitment())
+                .await?
+                .value
+        };
+        let open_orders = match account {
+            Some(account) => OoBalances::from_account_data(
+                &account.data,
+                self.market_info.coin_decimals,
+                self.market_info.pc_decimals,
+            )?,
+            None => OoBalances::default(),
+        };
+
+        let base_lots = match side {
+            Side::Bid => {
+                let quote = (wallet.quote + open_orders.pc_free) / (1.0 + self.fee_rates().taker);
+                self.quote_to_base_size(quote, price)
+            }
+            Side::Ask => {
+                let base = wallet.base + open_orders.coin_free;
+                (base / self.market_info.min_order_size()) as u64
+            }
+        };
+
+        Ok(self.market_info.base_lots_to_ui(base_lots) * price)
+    }
+
+    /// Computes the owner's net base position on this market, in base UI units, long positive.
+    ///
+    /// The position is the base held in the wallet plus the base total of the open orders
+    /// account, free and locked, minus the remaining size of the owner's resting asks: what the
+    /// owner would hold if every ask filled. Resting bids are not counted since their base is not
+    /// owned yet. Asks come from the last loaded book snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the open orders account cannot be fetched or decoded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let position = ob_client.net_position().await?;
+    ///
+    ///     println!("Net position: {}", position);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn net_position(&self) -> Result<f64> {
+        let wallet_base = self.wallet_balances().await?.base;
+        let open_orders_base = self.open_orders_balances().await?.coin_total;
+
+        let resting_asks: f64 = self
+            .order_book
+            .asks
+            .iter()
+            .filter(|order| order.owner == self.open_orders.oo_key)
+            .map(|order| order.quantity)
+            .sum();
+
+        Ok(wallet_base + open_orders_base - resting_asks)
+    }
+
+    /// Sums the quote notional of the owner's resting orders, price times remaining size.
+    ///
+    /// Orders come from the last loaded book snapshot; call `load_bids_asks_info` first for a fresh view.
+    ///
+    /// # Returns
+    ///
+    /// The notional of the resting bids and of the resting asks, in quote UI units.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let (bids, asks) = ob_client.my_resting_notional();
+    ///
+    ///     println!("Working bids: {}, working asks: {}", bids, asks);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn my_resting_notional(&self) -> (f64, f64) {
+        let notional = |orders: &[BookOrder]| -> f64 {
+            orders
+                .iter()
+                .filter(|order| order.owner == self.open_orders.oo_key)
+                .map(|order| order.price * order.quantity)
+                .sum()
+        };
+
+        (
+            notional(&self.order_book.bids),
+            notional(&self.order_book.asks),
+        )
+    }
+
+    /// Reloads the book and open orders and collects the decoded market state into one document.
+    ///
+    /// # Returns
+    ///
+    /// A `MarketDump` holding the market, both book sides, the event queue header,
+    /// the owner's open orders and wallet balances.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let dump = ob_client.dump_state().await?;
+    ///
+    ///     println!("{}", serde_json::to_string_pretty(&dump)?);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn dump_state(&mut self) -> Result<MarketDump> {
+        self.load_bids_asks_info().await?;
+        let event_queue = self.fetch_event_queue_stats().await?;
+        let balances = self.wallet_balances().await?;
+        let (base_vault, quote_vault) = self.vault_balances().await?;
+        let (accrued_fees_base, accrued_fees_quote) = self.accrued_fees().await?;
+
+        Ok(MarketDump {
+            owner: self.owner_key,
+            market: self.market_info.clone(),
+            order_book: self.order_book.clone(),
+            event_queue,
+            open_orders: self.open_orders.clone(),
+            balances,
+            base_vault,
+            quote_vault,
+            accrued_fees_base,
+            accrued_fees_quote,
+        })
+    }
+
+    /// Collects unique open orders accounts referenced in the pending event queue.
+    pub async fn collect_event_queue_open_orders(
+        &self,
+        max_accounts: usize,
+    ) -> Result<Vec<Pubkey>> {
+        let event_queue_data = self.fetch_event_queue_data().await?;
+        let Ok(queue) = EventQueueView::from_account_data(&event_queue_data) else {
+            return Ok(Vec::new());
+        };
+
+        let mut seen = HashSet::new();
+        let mut owners = Vec::new();
+        for event in queue.iter() {
+            let owner = Pubkey::from(u64_slice_to_pubkey(event.owner));
+            if seen.insert(owner) {
+                owners.push(owner);
+                if max_accounts > 0 && owners.len() >= max_accounts {
+                    break;
+                }
+            }
+        }
+
+        Ok(owners)
+    }
+
+    /// Cranks the event queue only as far as the last pending event of this client's open orders account.
+    ///
+    /// `ConsumeEvents` stops at the first event whose owner is not passed in, so the accounts of
+    /// every event ahead of ours are included as well. Nothing past our last event is needed,
+    /// which keeps the transaction small and settles our fills without cranking the whole queue.
+    /// If the events ahead of ours touch more than `MAX_CRANK_ACCOUNTS` accounts, the crank
+    /// stops before the first account that does not fit and a later call consumes the rest.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The maximum number of events to scan and consume.
+    ///
+    /// # Returns
+    ///
+    /// Whether the consume transaction was confirmed, its signature and the number of events
+    /// consumed. `None` if none of the first `limit` pending events touch our open orders
+    /// account.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let result = ob_client.consume_events_for_owner(10).await?;
+    ///
+    ///     println!("{:?}", result);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn consume_events_for_owner(
+        &self,
+        limit: u16,
+    ) -> Result<Option<(bool, Signature, u64)>> {
+        let event_queue_data = self.fetch_event_queue_data().await?;
+        let queue = EventQueueView::from_account_data(&event_queue_data)?;
+
+        let (_, events, open_orders_accounts) = queue.owner_events(
+            &[self.open_orders.oo_key],
+            limit as usize,
+            MAX_CRANK_ACCOUNTS,
+        );
+        if open_orders_accounts.is_empty() {
+            debug!("[*] No pending events for {}", self.open_orders.oo_key);
+            return Ok(None);
+        }
+
+        self.consume_events_instruction(open_orders_accounts, events as u16)
+            .await
+            .map(Some)
+    }
+
+    /// Finds every market of `program_id` where `owner`'s open orders accounts have pending events.
+    ///
+    /// The owner's open orders accounts are listed with `getProgramAccounts`, then the markets
+    /// and their event queues are read in batches. Only the first `limit` events of each queue
+    /// are scanned, as a crank of that `limit` would.
+    ///
+    /// # Arguments
+    ///
+    /// * `program_id` - The DEX program to search.
+    /// * `owner` - The wallet owning the open orders accounts.
+    /// * `limit` - The maximum number of events scanned per queue.
+    ///
+    /// # Returns
+    ///
+    /// The markets with at least one pending event of the owner, deepest queue first.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let markets = ob_client
+    ///         .markets_with_owner_events(ob_client.market_info.program_id, ob_client.owner_key, 100)
+    ///         .await?;
+    ///     for market in &markets {
+    ///         ob_client.crank_market(market, 100).await?;
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn markets_with_owner_events(
+        &self,
+        program_id: Pubkey,
+        owner: Pubkey,
+        limit: u16,
+    ) -> Result<Vec<OwnerMarketEvents>> {
+        let mut open_orders: HashMap<Pubkey, Vec<Pubkey>> = HashMap::new();
+        for (key, market) in self
+            .rpc_client
+            .fetch_v1_open_orders_markets(program_id, owner)
+            .await?
+        {
+            open_orders.entry(market).or_default().push(key);
+        }
+        let markets: Vec<Pubkey> = open_orders.keys().copied().collect();
+
+        let mut found = Vec::new();
+        for chunk in markets.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let market_accounts = self.rpc_client.fetch_multiple_accounts(chunk).await?;
+            let mut queued = Vec::new();
+            for (market, account) in chunk.iter().zip(market_accounts) {
+                let read = |offset: usize| {
+                    let data = &account.as_ref()?.data;
+                    let bytes = <[u8; 32]>::try_from(data.get(offset..offset + 32)?).ok()?;
+                    Some(Pubkey::from(bytes))
+                };
+                match (
+                    read(MARKET_EVENT_QUEUE_OFFSET),
+                    read(MARKET_COIN_VAULT_OFFSET),
+                    read(MARKET_PC_VAULT_OFFSET),
+                ) {
+                    (Some(event_queue), Some(coin_vault), Some(pc_vault)) => {
+                        queued.push((*market, event_queue, coin_vault, pc_vault))
+                    }
+                    _ => warn!("[*] Skipping {}: not a readable market account", market),
+                }
+            }
+
+            let queues: Vec<Pubkey> = queued.iter().map(|(_, queue, _, _)| *queue).collect();
+            let queue_data: Vec<Option<Vec<u8>>> = match self.event_queue_chunk_len {
+                Some(chunk_len) => {
+                    let mut data = Vec::with_capacity(queues.len());
+                    for queue in &queues {
+                        data.push(
+                            self.rpc_client
+                                .fetch_account_data_chunked(queue, chunk_len)
+                                .await
+                                .ok(),
+                        );
+                    }
+                    data
+                }
+                None => self
+                    .rpc_client
+                    .fetch_multiple_accounts(&queues)
+                    .await?
+                    .into_iter()
+                    .map(|account| account.map(|account| account.data))
+                    .collect(),
+            };
+            for ((market, event_queue, coin_vault, pc_vault), data) in
+                queued.into_iter().zip(queue_data)
+            {
+                let Some(data) = data else {
+                    warn!(
+                        "[*] Skipping {}: event queue {} not found",
+                        market, event_queue
+                    );
+                    continue;
+                };
+                let queue = EventQueueView::from_account_data(&data)?;
+                let owner_open_orders = open_orders.remove(&market).unwrap_or_default();
+                let (owner_events, crank_events, crank_accounts) =
+                    queue.owner_events(&owner_open_orders, limit as usize, MAX_CRANK_ACCOUNTS);
+                if owner_events == 0 {
+                    continue;
+                }
+                found.push(OwnerMarketEvents {
+                    market,
+                    event_queue,
+                    coin_vault,
+                    pc_vault,
+                    open_orders: owner_open_orders,
+                    pending: queue.stats.count,
+                    owner_events,
+                    crank_events: crank_events as u16,
+                    crank_accounts,
+                });
+            }
+        }
+
+        found.sort_by(|a, b| b.pending.cmp(&a.pending));
+        Ok(found)
+    }
+
+    /// Cranks a market found by [`OBClient::markets_with_owner_events`] through the owner's
+    /// last pending event.
+    ///
+    /// The market does not have to be the client's own, only the program id is shared. If the
+    /// events ahead of the owner's touch more accounts than fit in one transaction, the crank
+    /// stops before the first one that does not fit and the market is found again next time.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - The market and the accounts to crank with.
+    /// * `limit` - The maximum number of events to consume.
+    ///
+    /// # Returns
+    ///
+    /// Whether the consume transaction was confirmed, and its signature.
+    pub async fn crank_market(
+        &self,
+        events: &OwnerMarketEvents,
+        limit: u16,
+    ) -> Result<(bool, Signature)> {
+        let ix = openbook_dex::instruction::consume_events(
+            &self.market_info.program_id,
+            events.crank_accounts.iter().collect(),
+            &events.market,
+            &events.event_queue,
+            &events.coin_vault,
+            &events.pc_vault,
+            limit.min(events.crank_events),
+        )?;
+
+        self.send_instructions(vec![ix]).await
+    }
+
+    /// Plans the consume events transactions needed to drain the event queue.
+    ///
+    /// The pending events are split in queue order into passes of at most `per_tx_limit`
+    /// events, each with the open orders accounts its events touch. A pass also ends when it
+    /// would need more accounts than fit in a transaction, so a queue touching many accounts
+    /// takes more passes than `pending / per_tx_limit`.
+    ///
+    /// # Arguments
+    ///
+    /// * `per_tx_limit` - The most events consumed per transaction.
+    ///
+    /// # Returns
+    ///
+    /// The plan for the events pending now. Events pushed after the read are left for a later
+    /// plan.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `per_tx_limit` is zero or the event queue cannot be read.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let plan = ob_client.crank_plan(20).await?;
+    ///     println!("{} events in {} passes", plan.pending, plan.passes.len());
+    ///     ob_client.execute_crank_plan(&plan).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn crank_plan(&self, per_tx_limit: u16) -> Result<CrankPlan> {
+        if per_tx_limit == 0 {
+            return Err(anyhow!("The per transaction event limit must be positive"));
+        }
+        let event_queue_data = self.fetch_event_queue_data().await?;
+        let queue = EventQueueView::from_account_data(&event_queue_data)?;
+
+        Ok(queue.crank_plan(per_tx_limit, MAX_CRANK_ACCOUNTS))
+    }
+
+    /// Sends the passes of `plan` one after the other, each confirmed before the next is sent.
+    ///
+    /// A pass assumes the ones before it consumed their events, so execution stops at the
+    /// first unconfirmed pass. A pass consuming fewer events than planned, e.g. because
+    /// another cranker moved the queue head, is logged and the next pass is still sent.
+    ///
+    /// # Returns
+    ///
+    /// Whether each sent pass was confirmed, its signature and the events it consumed, in
+    /// order.
+    pub async fn execute_crank_plan(
+        &self,
+        plan: &CrankPlan,
+    ) -> Result<Vec<(bool, Signature, u64)>> {
+        let mut results = Vec::with_capacity(plan.passes.len());
+        for (index, pass) in plan.passes.iter().enumerate() {
+            let (confirmed, signature, consumed) = self
+                .consume_events_instruction(pass.open_orders.clone(), pass.events)
+                .await?;
+            info!(
+                "[*] Crank pass {}/{}: consumed {} of {} events, signature: {}",
+                index + 1,
+                plan.passes.len(),
+                consumed,
+                pass.events,
+                signature
+            );
+            results.push((confirmed, signature, consumed));
+            if !confirmed {
+                warn!(
+                    "[*] Crank pass {} not confirmed, stopping the plan",
+                    index + 1
+                );
+                break;
+            }
+            if consumed < pass.events as u64 {
+                warn!(
+                    "[*] Crank pass {} consumed {} events instead of {}",
+                    index + 1,
+                    consumed,
+                    pass.events
+                );
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Processes bids information to find the maximum bid price.
+    ///
+    /// This function removes bids from the provided `Slab` to find the maximum bid price.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - A reference to the `OBClient` struct.
+    /// * `bids` - A mutable reference to the `Slab` containing bids information.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the maximum bid price if successful, or an error if processing bids fails.
+    ///
+    /// # Errors
+    ///
+    /// This function may return an error if there is an issue with processing the bids information.
+    pub fn process_bids(&self, bids: &mut RefMut<Slab>) -> Result<(Vec<u128>, Vec<f64>, u64)> {
+        let mut max_bid = 0;
+        let mut open_bids = Vec::new();
+        let mut open_bids_prices = Vec::new();
+        loop {
+            let node = bids.remove_max();
+            match node {
+                Some(node) => {
+                    let owner = node.owner();
+                    let bytes = u64_slice_to_pubkey(owner);
+                    let owner_address = Pubkey::from(bytes);
+
+                    let order_id = node.order_id();
+                    let price_raw = node.price().get();
+                    let ui_price = price_raw as f64 / 1e4;
+
+                    debug!("[*] Bid: {price_raw}");
+
+                    if max_bid == 0 {
+                        max_bid = price_raw;
+                    }
+
+                    if owner_address == self.open_orders.oo_key {
+                        open_bids.push(order_id);
+                        open_bids_prices.push(ui_price);
+                    }
+                }
+                None => {
+                    break;
+                }
+            }
+        }
+        Ok((open_bids, open_bids_prices, max_bid))
+    }
+
+    /// Processes asks information to fetch asks info.
+    ///
+    /// This function iteratively removes asks from the provided `Slab` until
+    /// it finds all asks.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - A reference to the `OBClient` struct.
+    /// * `asks` - A mutable reference to the `Slab` containing asks information.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a tuple of `(open_asks, open_asks_prices, min_ask)` if successful,
+    /// or an error if processing asks fails.
+    pub fn process_asks(&self, asks: &mut RefMut<Slab>) -> Result<(Vec<u128>, Vec<f64>, u64)> {
+        let mut min_ask = 0;
+        let mut open_asks = Vec::new();
+        let mut open_asks_prices = Vec::new();
+        loop {
+            let node = asks.remove_min();
+            match node {
+                Some(node) => {
+                    let owner = node.owner();
+                    let bytes = u64_slice_to_pubkey(owner);
+                    let owner_address = Pubkey::from(bytes);
+
+                    let order_id = node.order_id();
+                    let price_raw = node.price().get();
+                    let ui_price = price_raw as f64 / 1e4;
+
+                    debug!("[*] Ask: {price_raw}");
+
+                    if min_ask == 0 {
+                        min_ask = price_raw;
+                    }
+
+                    if owner_address == self.open_orders.oo_key {
+                        open_asks.push(order_id);
+                        open_asks_prices.push(ui_price);
+                    }
+                }
+                None => {
+                    break;
+                }
+            }
+        }
+        Ok((open_asks, open_asks_prices, min_ask))
+    }
+
+    /// Loads the open bids from the market.
+    ///
+    /// # Returns
+    ///
+    /// The open bids stored in the market.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is an issue with loading the bids.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let result = ob_client.load_bids()?;
+    ///
+    ///     println!("{:?}", result);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn load_bids(&mut self) -> Result<Vec<u128>, ProgramError> {
+        Ok(self.open_orders.open_bids.clone())
+    }
+
+    /// Loads the open asks from the market.
+    ///
+    /// # Returns
+    ///
+    /// The open asks stored in the market.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is an issue with loading the asks.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let result = ob_client.load_asks()?;
+    ///
+    ///     println!("{:?}", result);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn load_asks(&mut self) -> Result<Vec<u128>, ProgramError> {
+        Ok(self.open_orders.open_asks.clone())
+    }
+    /// Places a limit order on the market.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - A reference to the `OBClient` struct.
+    /// * `target_amount_quote` - The target amount in quote currency for the order.
+    /// * `side` - The side of the order (buy or sell).
+    /// * `best_offset_usdc` - The best offset in USDC for the order.
+    /// * `execute` - A boolean indicating whether to execute the order immediately.
+    /// * `target_price` - The target price for the order.
+    ///
+    /// # Returns
+    ///
+    /// The order instructions, or once sent `OrderReturnType::Placed` with the signature and the
+    /// `SubmittedOrder` holding the exact lots, on-tick price, client order id and notional.
+    /// `None` if the size is not positive.
+    ///
+    /// # Errors
+    ///
+    /// This function may return an error if there is an issue with creating or sending the transaction,
+    /// if the size is below the market's minimum order size, or if `expiry_ts` is set but already
+    /// passed or not supported by the program id.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::matching::Side;
+    /// use openbook::v1::orders::OrderReturnType;
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let target_amount_quote = 5.0;
+    ///     let side = Side::Bid; // or Side::Ask
+    ///     let best_offset_usdc = 5.0;
+    ///     let execute = true;
+    ///     let target_price = 2.1;
+    ///
+    ///     if let Some(ord_ret_type) = ob_client.place_limit_order(
+    ///         target_amount_quote,
+    ///         side,
+    ///         best_offset_usdc,
+    ///         execute,
+    ///         target_price
+    ///     ).await?
+    ///     {
+    ///         match ord_ret_type {
+    ///             OrderReturnType::Instructions(insts) => {
+    ///                 println!("[*] Got Instructions: {:?}", insts);
+    ///             }
+    ///             OrderReturnType::Signature(sign) | OrderReturnType::Placed(sign, _) => {
+    ///                 println!("[*] Transaction successful, signature: {:?}", sign);
+    ///             }
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn place_limit_order(
+        &self,
+        target_amount_quote: f64,
+        side: Side,
+        best_offset_usdc: f64,
+        execute: bool,
+        target_price: f64,
+    ) -> Result<Option<OrderReturnType>, Error> {
+        self.place_limit_order_with_offset(
+            target_amount_quote,
+            side,
+            OffsetKind::Usdc(best_offset_usdc),
+            execute,
+            target_price,
+        )
+        .await
+    }
+
+    /// Places a limit order on the market, with the offset from the best price given either in
+    /// USDC or in bps of that price.
+    ///
+    /// Same as [`OBClient::place_limit_order`], which is this function with
+    /// `OffsetKind::Usdc`. A bps offset scales with the price, so it stays meaningful on markets
+    /// priced far from 1.0.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - A reference to the `OBClient` struct.
+    /// * `target_amount_quote` - The target amount in quote currency for the order.
+    /// * `side` - The side of the order (buy or sell).
+    /// * `offset` - How far below the best bid (or above the best ask) to price the order.
+    /// * `execute` - A boolean indicating whether to execute the order immediately.
+    /// * `target_price` - The target price for the order.
+    ///
+    /// # Returns
+    ///
+    /// See [`OBClient::place_limit_order`].
+    ///
+    /// # Errors
+    ///
+    /// See [`OBClient::place_limit_order`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::matching::Side;
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::{OBClient, OffsetKind};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     // 25 bps below the best bid
+    ///     let result = ob_client
+    ///         .place_limit_order_with_offset(5.0, Side::Bid, OffsetKind::Bps(25.0), false, 2.1)
+    ///         .await?;
+    ///
+    ///     println!("{:?}", result);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn place_limit_order_with_offset(
+        &self,
+        target_amount_quote: f64,
+        side: Side,
+        offset: OffsetKind,
+        execute: bool,
+        target_price: f64,
+    ) -> Result<Option<OrderReturnType>, Error> {
+        self.place_limit_order_with_client_id(
+            target_amount_quote,
+            side,
+            offset,
+            execute,
+            target_price,
+            None,
+        )
+        .await
+    }
+
+    /// Places a limit order like [`OBClient::place_limit_order_with_offset`], with a chosen
+    /// client order id.
+    ///
+    /// The client order id is the idempotency key of the placement: with `place_retries` set,
+    /// an order whose transaction timed out is only resent once the blockhash of the previous
+    /// attempt expired and its signature is known not to have landed. An order that failed
+    /// on-chain or was rejected by the node is an error right away, since a resend would fail
+    /// the same way. Every
+    /// resend carries the same id, so a duplicate left by any other path can be cancelled by
+    /// its id with `cancel_orders_by_client_id_range`.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_order_id` - The client order id, a random one if `None`.
+    ///
+    /// See [`OBClient::place_limit_order_with_offset`] for the others.
+    ///
+    /// # Returns
+    ///
+    /// See [`OBClient::place_limit_order`].
+    ///
+    /// # Errors
+    ///
+    /// See [`OBClient::place_limit_order`]. The error of the last attempt is returned if
+    /// every attempt failed to send.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::matching::Side;
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::{OBClient, OffsetKind};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     ob_client.place_retries = 2;
+    ///     let result = ob_client
+    ///         .place_limit_order_with_client_id(
+    ///             5.0,
+    ///             Side::Bid,
+    ///             OffsetKind::Usdc(0.0),
+    ///             true,
+    ///             2.1,
+    ///             Some(42),
+    ///         )
+    ///         .await?;
+    ///
+    ///     println!("{:?}", result);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn place_limit_order_with_client_id(
+        &self,
+        target_amount_quote: f64,
+        side: Side,
+        offset: OffsetKind,
+        execute: bool,
+        target_price: f64,
+        client_order_id: Option<u64>,
+    ) -> Result<Option<OrderReturnType>, Error> {
+        let price_factor = self.market_info.price_factor();
+
+        let price = if execute {
+            target_price
+        } else {
+            let best = match side {
+                Side::Bid => self.open_orders.max_bid,
+                Side::Ask => self.open_orders.min_ask,
+            };
+            offset.apply(side, best as f64 / price_factor)
+        };
+
+        if execute {
+            self.check_order_count(side, 0, 1).await?;
+        }
+        let client_order_id = client_order_id.unwrap_or_else(random);
+        let Some((place_order_ix, mut submitted)) =
+            self.new_order_instruction_with_id(side, target_amount_quote, price, client_order_id)?
+        else {
+            return Ok(None);
+        };
+        let instructions = vec![place_order_ix];
+
+        if !execute {
+            return Ok(Some(OrderReturnType::Instructions(instructions)));
+        }
+
+        let (confirmed, signature) = self.send_placement(instructions, &submitted).await?;
+
+        if confirmed {
+            self.track_placed(std::slice::from_mut(&mut submitted))
+                .await;
+        }
+
+        Ok(Some(OrderReturnType::Placed(signature, submitted)))
+    }
+
+    /// Reads the outcome of confirmed placements into their `outcome`, logging each, and
+    /// tracks the orders still resting, or whose outcome could not be read, in `order_state`.
+    ///
+    /// A post-only order that would have crossed is dropped by the program while its
+    /// transaction succeeds, so this is what tells the caller to requote it. Orders filled as
+    /// makers in the meantime are reported filled from their fill events.
+    async fn track_placed(&self, orders: &mut [SubmittedOrder]) {
+        match self.order_outcomes(orders).await {
+            Ok(outcomes) => {
+                for (order, outcome) in orders.iter_mut().zip(outcomes) {
+                    match outcome {
+                        OrderOutcome::PostOnlyRejected => warn!(
+                            "[*] Order {}: {}, requote it unless its fills were already cranked",
+                            order.client_order_id, outcome
+                        ),
+                        _ => info!("[*] Order {}: {}", order.client_order_id, outcome),
+                    }
+                    order.outcome = Some(outcome);
+                }
+            }
+            Err(err) => warn!("[*] Unable to read the order outcomes: {}", err),
+        }
+        for order in orders.iter() {
+            let order_id = match order.outcome {
+                Some(OrderOutcome::Resting { order_id })
+                | Some(OrderOutcome::PartiallyFilled {
+                    resting_order_id: order_id,
+                    ..
+                }) => Some(order_id),
+                _ => None,
+            };
+            if order_id.is_some() || order.outcome.is_none() {
+                self.order_state.insert(order, order_id, get_unix_secs());
+            }
+        }
+    }
+
+    /// Sends a placement, resending it up to `place_retries` times while it did not land.
+    ///
+    /// Each attempt is signed with a fresh blockhash. An attempt that failed on-chain or was
+    /// rejected by the node returns its error. One whose status is unknown is only judged once
+    /// the block height passed the last valid block height of its blockhash, when it can no
+    /// longer land: it is resent only if the node has no successful status for its signature,
+    /// see [`OBClient::placement_landed`], so an order that landed late, or that rested and was
+    /// filled meanwhile, is not placed twice.
+    async fn send_placement(
+        &self,
+        instructions: Vec<Instruction>,
+        order: &SubmittedOrder,
+    ) -> Result<(bool, Signature)> {
+        let (instructions, skip_preflight, commitment) =
+            self.prepare_instructions(instructions).await?;
+        let signer = self.signer()?;
+        let mut attempt = 0;
+        loop {
+            let (recent_hash, last_valid_block_height) =
+                self.rpc_client.blockhash_with_expiry().await?;
+            let txn = sign_transaction(signer.as_ref(), &instructions, recent_hash).await?;
+            let signature = txn.signatures[0];
+            let (outcome, _) = self
+                .rpc_client
+                .send_and_confirm_transaction(&txn, skip_preflight, commitment)
+                .await?;
+            match outcome {
+                SendOutcome::Confirmed => return Ok((true, signature)),
+                // Resending would fail the same way, so only an unknown status is retried.
+                SendOutcome::Failed(err) => {
+                    return Err(anyhow!("Placement {signature} failed on-chain: {err}"));
+                }
+                SendOutcome::Rejected(err) => {
+                    return Err(anyhow!("Placement {signature} was rejected: {err}"));
+                }
+                SendOutcome::Unknown => {}
+            }
+            if attempt >= self.place_retries {
+                return Ok((false, signature));
+            }
+
+            warn!(
+                "[*] Placement {} not confirmed, waiting for block height {} before resending",
+                signature, last_valid_block_height
+            );
+            if self
+                .placement_landed(&signature, last_valid_block_height, commitment)
+                .await?
+            {
+                info!(
+                    "[*] Order {} landed after all, not resending",
+                    order.client_order_id
+                );
+                return Ok((true, signature));
+            }
+            attempt += 1;
+            warn!(
+                "[*] Order {} did not land, resending ({}/{})",
+                order.client_order_id, attempt, self.place_retries
+            );
+        }
+    }
+
+    /// Returns whether the placement `signature` landed, once its blockhash, valid until
+    /// `last_valid_block_height`, expired.
+    ///
+    /// Past that height the transaction can no longer be processed, so the status the node
+    /// returns, searching its history, is final: a signature it does not know never landed.
+    async fn placement_landed(
+        &self,
+        signature: &Signature,
+        last_valid_block_height: u64,
+        commitment: CommitmentConfig,
+    ) -> Result<bool> {
+        self.rpc_client
+            .wait_blockhash_expired(last_valid_block_height)
+            .await?;
+        let status = self
+            .rpc_client
+            .inner()
+            .get_signature_status_with_commitment_and_history(signature, commitment, true)
+            .await?;
+        Ok(matches!(status, Some(Ok(()))))
+    }
+
+    /// Tracks the orders placed by this client in `path`, kept across runs.
+    ///
+    /// The orders saved by a previous run are loaded and reconciled with the open orders
+    /// account: those no longer resting are dropped, and the others get their on-book order id.
+    /// Orders placed from now on are added to the file as soon as they are confirmed.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The JSON state file, created on the first placement if missing.
+    ///
+    /// # Returns
+    ///
+    /// The number of orders still resting, and the number pruned.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be parsed or the open orders account cannot be read.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let (resting, pruned) = ob_client.load_order_state("orders.json").await?;
+    ///     println!("{} orders resting, {} gone", resting, pruned);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn load_order_state(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(usize, usize)> {
+        self.order_state = OrderState::load(path)?;
+        let pruned = self.reconcile_order_state().await?;
+        Ok((self.order_state.orders().len(), pruned))
+    }
+
+    /// Drops the tracked orders that no longer rest in the open orders account and records the
+    /// on-book order id of the others.
+    ///
+    /// # Returns
+    ///
+    /// The number of orders dropped.
+    pub async fn reconcile_order_state(&self) -> Result<usize> {
+        let (open_orders, _) = self.fetch_account_raw(self.open_orders.oo_key).await?;
+        let mut pruned = 0;
+        self.order_state.update(|orders| {
+            orders.retain(|client_order_id, order| {
+                match find_resting_order_id(&open_orders, *client_order_id) {
+                    Some(order_id) => {
+                        order.order_id = Some(order_id);
+                        true
+                    }
+                    None => {
+                        debug!("[*] Order {} is gone, forgetting it", client_order_id);
+                        pruned += 1;
+                        false
+                    }
+                }
+            });
+        });
+        Ok(pruned)
+    }
+
+    /// Reads what became of a confirmed limit order: resting, filled, or dropped.
+    ///
+    /// The order is looked up by client order id in the open orders account, and its fills in
+    /// the event queue, whether it took liquidity on placement or was hit later as a maker.
+    /// Fills are only seen until the queue is cranked, so an order fully filled and already
+    /// consumed is reported as `PostOnlyRejected`; call this right after confirmation, as
+    /// `place_limit_order` does.
+    ///
+    /// # Arguments
+    ///
+    /// * `order` - The order as returned in `OrderReturnType::Placed`.
+    ///
+    /// # Returns
+    ///
+    /// The `OrderOutcome` of the order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the open orders account or the event queue cannot be read.
+    pub async fn order_outcome(&self, order: &SubmittedOrder) -> Result<OrderOutcome> {
+        let mut outcomes = self.order_outcomes(std::slice::from_ref(order)).await?;
+        Ok(outcomes.remove(0))
+    }
+
+    /// Reads what became of several confirmed limit orders, see [`OBClient::order_outcome`].
+    ///
+    /// The open orders account and the event queue are read once for all of them, so this
+    /// suits the orders of one transaction, such as those of `place_both` or `place_laddered`.
+    ///
+    /// # Returns
+    ///
+    /// The `OrderOutcome` of each order, in the order of `orders`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the open orders account or the event queue cannot be read.
+    pub async fn order_outcomes(&self, orders: &[SubmittedOrder]) -> Result<Vec<OrderOutcome>> {
+        let (open_orders, _) = self.fetch_account_raw(self.open_orders.oo_key).await?;
+        let event_queue = self.fetch_event_queue_data().await?;
+
+        // Native base and quote fills per client order id, as taker or maker.
+        let mut fills: HashMap<u64, (u64, u64)> = HashMap::new();
+        let queue = EventQueueView::from_account_data(&event_queue)?;
+        for event in queue.iter() {
+            if Pubkey::from(u64_slice_to_pubkey(event.owner)) != self.open_orders.oo_key {
+                continue;
+            }
+            let Ok(EventView::Fill {
+                side,
+                native_qty_paid,
+                native_qty_received,
+                client_order_id: Some(client_order_id),
+                ..
+            }) = event.as_view()
+            else {
+                continue;
+            };
+            let (base, quote) = match side {
+                Side::Bid => (native_qty_received, native_qty_paid),
+                Side::Ask => (native_qty_paid, native_qty_received),
+            };
+            let fill = fills.entry(client_order_id.get()).or_default();
+            fill.0 += base;
+            fill.1 += quote;
+        }
+
+        Ok(orders
+            .iter()
+            .map(|order| {
+                let (base_native, quote_native) = fills
+                    .get(&order.client_order_id)
+                    .copied()
+                    .unwrap_or_default();
+                let base = base_native as f64 / 10f64.powi(self.market_info.coin_decimals as i32);
+                let quote = quote_native as f64 / 10f64.powi(self.market_info.pc_decimals as i32);
+
+                let resting = find_resting_order_id(&open_orders, order.client_order_id);
+                match (resting, base_native > 0) {
+                    (Some(order_id), false) => OrderOutcome::Resting { order_id },
+                    (Some(resting_order_id), true) => OrderOutcome::PartiallyFilled {
+                        filled: base,
+                        resting_order_id,
+                    },
+                    (None, true) => OrderOutcome::FullyFilled { base, quote },
+                    (None, false) => OrderOutcome::PostOnlyRejected,
+                }
+            })
+            .collect())
+    }
+
+    /// Builds a post-only `new_order` instruction for `target_amount_quote` at `price`, rounded
+    /// down to the lot and tick grids, with a fresh random client order id.
+    ///
+    /// Returns `None` if the size is not positive, and an error if it is below the minimum order
+    /// size, see [`OBClient::order_base_lots`], or if the price is too far from the reference
+    /// price.
+    fn new_order_instruction(
+        &self,
+        side: Side,
+        target_amount_quote: f64,
+        price: f64,
+    ) -> Result<Option<(Instruction, SubmittedOrder)>> {
+        self.new_order_instruction_with_id(side, target_amount_quote, price, random())
+    }
+
+    /// Builds the instruction of [`OBClient::new_order_instruction`] with `client_order_id`.
+    fn new_order_instruction_with_id(
+        &self,
+        side: Side,
+        target_amount_quote: f64,
+        price: f64,
+        client_order_id: u64,
+    ) -> Result<Option<(Instruction, SubmittedOrder)>> {
+        self.check_reference_price(price)?;
+
+        let price_factor = self.market_info.price_factor();
+        let quote_lot_factor = self.market_info.pc_lot_size as f64;
+        let input_ata = match side {
+            Side::Bid => &self.quote_ata,
+            Side::Ask => &self.base_ata,
+        };
+
+        let limit_price_lots = (price * price_factor) as u64;
+        let target_base_lots = self.order_base_lots(target_amount_quote, price)?;
+        let target_quote_lots_w_fee =
+            (target_base_lots as f64 * quote_lot_factor * limit_price_lots as f64) as u64;
+
+        info!(
+            "[*] Order sizing => quote: {}, price: {}, base size: {}, base lots: {}, price lots: {}",
+            target_amount_quote,
+            price,
+            self.market_info.base_lots_to_ui(target_base_lots),
+            target_base_lots,
+            limit_price_lots
+        );
+
+        if target_base_lots == 0 {
+            debug!(
+                "[*] Got zero base lots, and quote: {:?}",
+                target_amount_quote
+            );
+            return Ok(None);
+        }
+
+        let (Some(limit_price), Some(max_coin_qty), Some(max_native_pc_qty_including_fees)) = (
+            NonZeroU64::new(limit_price_lots),
+            NonZeroU64::new(target_base_lots),
+            NonZeroU64::new(target_quote_lots_w_fee),
+        ) else {
+            return Err(anyhow!(
+                "Price {price} rounds to {limit_price_lots} price lots, below one tick"
+            ));
+        };
+        let max_ts = self.order_max_ts()?;
+        info!("[*] Using client order id: {}", client_order_id);
+
+        let place_order_ix = openbook_dex::instruction::new_order(
+            &self.market_info.market_address,
+            &self.open_orders.oo_key,
+            &self.market_info.request_queue,
+            &self.market_info.event_queue,
+            &self.market_info.bids_address,
+            &self.market_info.asks_address,
+            input_ata,
+            &self.owner_key,
+            &self.market_info.coin_vault,
+            &self.market_info.pc_vault,
+            &self.token_program,
+            &rent::ID,
+            None,
+            &self.market_info.program_id,
+            side,
+            limit_price,
+            max_coin_qty,
+            OrderType::PostOnly,
+            client_order_id,
+            SelfTradeBehavior::AbortTransaction,
+            self.match_limit,
+            max_native_pc_qty_including_fees,
+            max_ts,
+        )?;
+
+        let order_price = self.market_info.price_lots_to_ui(limit_price_lots);
+        let order_size = self.market_info.base_lots_to_ui(target_base_lots);
+        let submitted = SubmittedOrder {
+            side,
+            client_order_id,
+            price_lots: limit_price_lots,
+            base_lots: target_base_lots,
+            max_native_quote: target_quote_lots_w_fee,
+            price: order_price,
+            size: order_size,
+            notional: order_price * order_size,
+            max_ts,
+            outcome: None,
+        };
+
+        Ok(Some((place_order_ix, submitted)))
+    }
+
+    /// Spreads `total_quote` over `levels` post-only orders stepping away from `base_price`.
+    ///
+    /// Level `i` (from 0) is priced `i * step_bps` below `base_price` for bids and above it for
+    /// asks, then rounded down to the tick grid. Sizes follow `distribution`. Orders are sent
+    /// `MAX_PLACE_ORDERS` per transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `side` - The side of every order.
+    /// * `total_quote` - The total size in quote currency, split between the levels.
+    /// * `base_price` - The price of the first level.
+    /// * `levels` - The number of orders.
+    /// * `step_bps` - The distance between two levels, in bps of `base_price`.
+    /// * `distribution` - How `total_quote` is split between the levels.
+    /// * `execute` - Whether to send the orders or only build them.
+    ///
+    /// # Returns
+    ///
+    /// A `LadderResult` with the submitted orders, including their client order ids, and one
+    /// `OrderReturnType` per transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the arguments are not positive, if a bid level would be priced at
+    /// or below zero or sized below the minimum order size, or if a transaction fails to send.
+    /// Nothing is sent when a level is refused.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::matching::Side;
+    /// use openbook::v1::ob_client::{LadderDistribution, OBClient};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let ladder = ob_client
+    ///         .place_laddered(Side::Bid, 500.0, 2.1, 5, 10.0, LadderDistribution::Weighted, true)
+    ///         .await?;
+    ///
+    ///     println!("Client order ids: {:?}", ladder.client_order_ids());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub async fn place_laddered(
+        &self,
+        side: Side,
+        total_quote: f64,
+        base_price: f64,
+        levels: usize,
+        step_bps: f64,
+        distribution: LadderDistribution,
+        execute: bool,
+    ) -> Result<LadderResult> {
+        let positive = |value: f64| value > 0.0;
+        if levels == 0
+            || !positive(total_quote)
+            || !positive(base_price)
+            || step_bps.is_nan()
+            || step_bps < 0.0
+        {
+            return Err(anyhow!(
+                "Ladder needs at least one level and positive size, price and step, got {levels} levels of {total_quote} quote at {base_price} every {step_bps} bps"
+            ));
+        }
+
+        let weights = distribution.weights(levels);
+        let total_weight: f64 = weights.iter().sum();
+
+        let mut orders = Vec::with_capacity(levels);
+        let mut instructions = Vec::with_capacity(levels);
+        for (level, weight) in weights.into_iter().enumerate() {
+            let offset = base_price * step_bps * level as f64 / 10_000.0;
+            let price = match side {
+                Side::Bid => base_price - offset,
+                Side::Ask => base_price + offset,
+            };
+            if price <= 0.0 {
+                return Err(anyhow!(
+                    "Ladder level {level} would be priced at {price}, reduce the levels or the step"
+                ));
+            }
+            let quote = total_quote * weight / total_weight;
+            match self.new_order_instruction(side, quote, price)? {
+                Some((instruction, order)) => {
+                    if orders
+                        .iter()
+                        .any(|other: &SubmittedOrder| other.price_lots == order.price_lots)
+                    {
+                        warn!(
+                            "[*] Ladder level {} rounds to the same tick as a previous level ({})",
+                            level, order.price
+                        );
+                    }
+                    instructions.push(instruction);
+                    orders.push(order);
+                }
+                None => warn!("[*] Ladder level {} rounds to zero lots, skipped", level),
+            }
+        }
+        self.check_order_count(side, 0, orders.len()).await?;
+
+        let mut transactions = Vec::new();
+        for (chunk, chunk_orders) in instructions
+            .chunks(MAX_PLACE_ORDERS)
+            .zip(orders.chunks_mut(MAX_PLACE_ORDERS))
+        {
+            if execute {
+                let (confirmed, signature) = self.send_instructions(chunk.to_vec()).await?;
+                if confirmed {
+                    self.track_placed(chunk_orders).await;
+                }
+                transactions.push(OrderReturnType::Signature(signature));
+            } else {
+                transactions.push(OrderReturnType::Instructions(chunk.to_vec()));
+            }
+        }
+
+        Ok(LadderResult {
+            orders,
+            transactions,
+        })
+    }
+
+    /// Places a post-only bid and ask together in one transaction, without cancelling or settling.
+    ///
+    /// Both orders land or neither does, which suits the first quotes on an empty book better
+    /// than `cancel_settle_place`. Prices are rounded down to the tick grid.
+    ///
+    /// # Arguments
+    ///
+    /// * `bid` - Size and price of the bid.
+    /// * `ask` - Size and price of the ask.
+    /// * `execute` - Whether to send the orders or only build them.
+    ///
+    /// # Returns
+    ///
+    /// A `PlaceBothResult` with both submitted orders, including their client order ids, and the
+    /// signature or instructions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bid is not below the ask, if either side is below the minimum
+    /// order size, if
+    /// the transaction would not fit in a packet, or if it fails to send.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    /// use openbook::v1::orders::PlaceSpec;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let bid = PlaceSpec { size_quote: 5.0, price: 2.1 };
+    ///     let ask = PlaceSpec { size_quote: 5.0, price: 2.3 };
+    ///     let placed = ob_client.place_both(bid, ask, true).await?;
+    ///
+    ///     println!("Client order ids: {:?}", placed.client_order_ids());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn place_both(
+        &self,
+        bid: PlaceSpec,
+        ask: PlaceSpec,
+        execute: bool,
+    ) -> Result<PlaceBothResult> {
+        if bid.price.is_nan() || ask.price.is_nan() || bid.price >= ask.price {
+            return Err(anyhow!(
+                "Bid price {} must be below ask price {}",
+                bid.price,
+                ask.price
+            ));
+        }
+
+        self.check_order_count(Side::Bid, 0, 1).await?;
+        self.check_order_count(Side::Ask, 0, 1).await?;
+
+        let zero_lots = |side: &str| anyhow!("The {side} rounds to zero lots, nothing to place");
+        let (bid_ix, bid_order) = self
+            .new_order_instruction(Side::Bid, bid.size_quote, bid.price)?
+            .ok_or_else(|| zero_lots("bid"))?;
+        let (ask_ix, ask_order) = self
+            .new_order_instruction(Side::Ask, ask.size_quote, ask.price)?
+            .ok_or_else(|| zero_lots("ask"))?;
+        let instructions = vec![bid_ix, ask_ix];
+
+        let mut orders = [bid_order, ask_order];
+        let transaction = if execute {
+            let (confirmed, signature) = self.send_instructions(instructions).await?;
+            if confirmed {
+                self.track_placed(&mut orders).await;
+            }
+            OrderReturnType::Signature(signature)
+        } else {
+            self.check_transaction_size(&instructions)?;
+            OrderReturnType::Instructions(instructions)
+        };
+
+        let [bid, ask] = orders;
+        Ok(PlaceBothResult {
+            bid,
+            ask,
+            transaction,
+        })
+    }
+
+    /// Places a post-only bid and ask symmetrically around a fair value, in one transaction.
+    ///
+    /// The bid is priced `spread_bps / 2` below `fair` and the ask the same distance above, then
+    /// both go through [`OBClient::place_both`]. Unlike the USDC offsets of `place_limit_order`,
+    /// the book is not read: the prices come only from `fair`, e.g. a pricing model's output.
+    /// Prices are rounded down to the tick grid, so the ask may sit up to a tick closer to
+    /// `fair` than the bid.
+    ///
+    /// # Arguments
+    ///
+    /// * `fair` - The fair value, in quote UI units per base unit.
+    /// * `spread_bps` - Full distance between the bid and the ask, in bps of `fair`.
+    /// * `size_quote` - The size of each order in quote currency.
+    /// * `execute` - Whether to send the orders or only build them.
+    ///
+    /// # Returns
+    ///
+    /// A `PlaceBothResult` with both submitted orders and the signature or instructions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `fair` or `spread_bps` is not positive, if `spread_bps` is 20000 or
+    /// more, if the bid rounds to a zero price, if the spread is narrower than a tick so both
+    /// quotes round to the same price, or for any error of `place_both`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     // Bid at 2.198, ask at 2.202.
+    ///     let placed = ob_client.quote_around(2.2, 20.0, 5.0, true).await?;
+    ///
+    ///     println!("Client order ids: {:?}", placed.client_order_ids());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn quote_around(
+        &self,
+        fair: f64,
+        spread_bps: f64,
+        size_quote: f64,
+        execute: bool,
+    ) -> Result<PlaceBothResult> {
+        if fair.is_nan() || fair <= 0.0 {
+            return Err(anyhow!("Fair value must be positive, got {fair}"));
+        }
+        if spread_bps.is_nan() || spread_bps <= 0.0 {
+            return Err(anyhow!("Spread must be positive, got {spread_bps} bps"));
+        }
+        if spread_bps >= 20_000.0 {
+            return Err(anyhow!(
+                "Spread must be below 20000 bps, got {spread_bps} bps, which puts the bid at or below zero"
+            ));
+        }
+
+        let half_spread = spread_bps / 2.0 / 10_000.0;
+        let bid_price = fair * (1.0 - half_spread);
+        let ask_price = fair * (1.0 + half_spread);
+        let price_factor = self.market_info.price_factor();
+        if (bid_price * price_factor) as u64 == 0 {
+            return Err(anyhow!(
+                "A {spread_bps} bps spread around {fair} puts the bid at {bid_price}, below one tick"
+            ));
+        }
+        if (bid_price * price_factor) as u64 >= (ask_price * price_factor) as u64 {
+            return Err(anyhow!(
+                "A {spread_bps} bps spread around {fair} is narrower than a tick, bid and ask round to the same price"
+            ));
+        }
+
+        debug!(
+            "[*] Quotes around fair => fair: {}, bid: {}, ask: {}",
+            fair, bid_price, ask_price
+        );
+
+        let bid = PlaceSpec {
+            size_quote,
+            price: bid_price,
+        };
+        let ask = PlaceSpec {
+            size_quote,
+            price: ask_price,
+        };
+        self.place_both(bid, ask, execute).await
+    }
+
+    /// Returns the `max_ts` of a new limit order, checking `expiry_ts` against the program.
+    fn order_max_ts(&self) -> Result<i64> {
+        let Some(expiry_ts) = self.expiry_ts else {
+            return Ok((get_unix_secs() + DEFAULT_ORDER_TTL_SECS) as i64);
+        };
+        // The deployed Serum v3 program predates `max_ts` and ignores it.
+        if self.market_info.program_id == Pubkey::from_str(SERUM_V3_PROGRAM_ID)? {
+            return Err(anyhow!(
+                "Order expiry is not supported by program id {}",
+                self.market_info.program_id
+            ));
+        }
+        let now = get_unix_secs() as i64;
+        if expiry_ts <= now {
+            return Err(anyhow!(
+                "Order expiry {expiry_ts} is not in the future (now {now})"
+            ));
+        }
+        Ok(expiry_ts)
+    }
+
+    /// Crosses the book with an immediate-or-cancel order priced off the best opposing order.
+    ///
+    /// The limit price is the best opposing price moved by `slippage_bps` in the direction of the
+    /// trade. The book is re-read first, and the resting size within that limit is compared with
+    /// `size_quote` before anything is built. At most `match_limit` resting orders are matched,
+    /// the rest of the order is cancelled.
+    ///
+    /// # Arguments
+    ///
+    /// * `side` - The side of the order (`Bid` buys base, `Ask` sells base).
+    /// * `size_quote` - The order size in quote currency.
+    /// * `slippage_bps` - How far past the best opposing price the order may fill, in bps.
+    /// * `allow_partial` - Whether to send the order when the book cannot fill all of `size_quote`
+    ///                     within the slippage; the rest is cancelled by the IOC order.
+    /// * `execute` - Whether to execute the order immediately.
+    ///
+    /// # Returns
+    ///
+    /// The order instructions, or the signature if executed. `None` if the size is not positive.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the book has no opposing orders, or if the depth within the slippage
+    /// does not cover `size_quote` and `allow_partial` is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::matching::Side;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let result = ob_client
+    ///         .place_market_order(Side::Bid, 5.0, 50.0, false, false)
+    ///         .await?;
+    ///
+    ///     println!("{:?}", result);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn place_market_order(
+        &mut self,
+        side: Side,
+        size_quote: f64,
+        slippage_bps: f64,
+        allow_partial: bool,
+        execute: bool,
+    ) -> Result<Option<OrderReturnType>, Error> {
+        if size_quote.is_nan() || size_quote <= 0.0 {
+            return Err(anyhow!(
+                "Market order size must be positive, got {size_quote}"
+            ));
+        }
+        if slippage_bps.is_nan() || slippage_bps < 0.0 {
+            return Err(anyhow!("Slippage must not be negative, got {slippage_bps}"));
+        }
+
+        self.load_bids_asks_info().await?;
+
+        let (opposite, slippage_factor) = match side {
+            Side::Bid => (Side::Ask, 1.0 + slippage_bps / 10_000.0),
+            Side::Ask => (Side::Bid, 1.0 - slippage_bps / 10_000.0),
+        };
+        let levels = self.order_book.side(opposite);
+        let best_price = levels
+            .first()
+            .map(|order| order.price)
+            .ok_or_else(|| anyhow!("The book has no opposing orders to cross"))?;
+        let limit_price = best_price * slippage_factor;
+        self.check_reference_price(limit_price)?;
+
+        let available_quote: f64 = levels
+            .iter()
+            .take_while(|order| match side {
+                Side::Bid => order.price <= limit_price,
+                Side::Ask => order.price >= limit_price,
+            })
+            .map(|order| order.price * order.quantity)
+            .sum();
+        if available_quote < size_quote {
+            if !allow_partial {
+                return Err(anyhow!(
+                    "Only {available_quote} of {size_quote} quote rests within {slippage_bps} bps of {best_price}"
+                ));
+            }
+            warn!(
+                "[*] Only {} of {} quote rests within the slippage, the rest will be cancelled",
+                available_quote, size_quote
+            );
+        }
+
+        let limit_price_lots = (limit_price * self.market_info.price_factor()) as u64;
+        let max_base_lots = self.order_base_lots(size_quote, best_price)?;
+        let (input_ata, max_native_pc_qty) = match side {
+            Side::Bid => (
+                &self.quote_ata,
+                (size_quote * 10f64.powi(self.market_info.pc_decimals as i32)) as u64,
+            ),
+            Side::Ask => (&self.base_ata, u64::MAX),
+        };
+
+        info!(
+            "[*] Market order => quote: {}, best price: {}, limit price: {}, base lots: {}, price lots: {}",
+            size_quote, best_price, limit_price, max_base_lots, limit_price_lots
+        );
+
+        let (Some(limit_price), Some(max_coin_qty), Some(max_native_pc_qty_including_fees)) = (
+            NonZeroU64::new(limit_price_lots),
+            NonZeroU64::new(max_base_lots),
+            NonZeroU64::new(max_native_pc_qty),
+        ) else {
+            debug!("[*] Market order rounds to zero lots, nothing to place");
+            return Ok(None);
+        };
+
+        let place_order_ix = openbook_dex::instruction::new_order(
+            &self.market_info.market_address,
+            &self.open_orders.oo_key,
+            &self.market_info.request_queue,
+            &self.market_info.event_queue,
+            &self.market_info.bids_address,
+            &self.market_info.asks_address,
+            input_ata,
+            &self.owner_key,
+            &self.market_info.coin_vault,
+            &self.market_info.pc_vault,
+            &self.token_program,
+            &rent::ID,
+            None,
+            &self.market_info.program_id,
+            side,
+            limit_price,
+            max_coin_qty,
+            OrderType::ImmediateOrCancel,
+            random::<u64>(),
+            SelfTradeBehavior::AbortTransaction,
+            self.match_limit,
+            max_native_pc_qty_including_fees,
+            (get_unix_secs() + DEFAULT_ORDER_TTL_SECS) as i64,
+        )?;
+
+        let instructions = vec![place_order_ix];
+
+        if !execute {
+            return Ok(Some(OrderReturnType::Instructions(instructions)));
+        }
+
+        let (_, signature) = self.send_instructions(instructions).await?;
+
+        Ok(Some(OrderReturnType::Signature(signature)))
+    }
+
+    /// Sends a small marketable order, cranks its fill and settles it, timing every stage.
+    ///
+    /// The order is the immediate-or-cancel order of `place_market_order`, partial fills
+    /// allowed. Its send and confirmation are timed apart, then our events are consumed and
+    /// the open orders account settled, each waiting for confirmation. This spends real funds:
+    /// the order trades against the book and pays taker fees, and every stage pays a
+    /// transaction fee. Being immediate-or-cancel, no part of the order is left resting.
+    ///
+    /// # Arguments
+    ///
+    /// * `side` - The side of the order.
+    /// * `size_quote` - The order size in quote currency, at least the minimum order size.
+    /// * `slippage_bps` - How far past the best opposing price the order may fill, in bps.
+    /// * `crank_limit` - The maximum number of events scanned and consumed by the crank.
+    ///
+    /// # Returns
+    ///
+    /// A `PingFillReport` with the latency of each stage and whether the order filled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the client is read-only, if the order cannot be built, or if the
+    /// order or the crank is not confirmed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::matching::Side;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let report = ob_client.ping_fill(Side::Bid, 1.0, 50.0, 10).await?;
+    ///
+    ///     println!("Send {:?}, confirm {:?}", report.send, report.confirm);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn ping_fill(
+        &mut self,
+        side: Side,
+        size_quote: f64,
+        slippage_bps: f64,
+        crank_limit: u16,
+    ) -> Result<PingFillReport> {
+        let signer = self.signer()?;
+        let Some(OrderReturnType::Instructions(instructions)) = self
+            .place_market_order(side, size_quote, slippage_bps, true, false)
+            .await?
+        else {
+            return Err(anyhow!(
+                "Ping order of {size_quote} quote has nothing to place"
+            ));
+        };
+        let order = instructions
+            .iter()
+            .find_map(|ix| match MarketInstruction::unpack(&ix.data) {
+                Some(MarketInstruction::NewOrderV3(order)) => {
+                    let price = self.market_info.price_lots_to_ui(order.limit_price.get());
+                    let size = self.market_info.base_lots_to_ui(order.max_coin_qty.get());
+                    Some(SubmittedOrder {
+                        side,
+                        client_order_id: order.client_order_id,
+                        price_lots: order.limit_price.get(),
+                        base_lots: order.max_coin_qty.get(),
+                        max_native_quote: order.max_native_pc_qty_including_fees.get(),
+                        price,
+                        size,
+                        notional: price * size,
+                        max_ts: order.max_ts,
+                        outcome: None,
+                    })
+                }
+                _ => None,
+            })
+            .ok_or_else(|| anyhow!("Ping order instructions hold no new order"))?;
+
+        let (instructions, skip_preflight, commitment) =
+            self.prepare_instructions(instructions).await?;
+
+        let started = std::time::Instant::now();
+        let signature = self
+            .rpc_client
+            .send_transaction(signer.as_ref(), &instructions, skip_preflight)
+            .await?;
+        let send = started.elapsed();
+        info!("[*] Ping order {} sent in {:?}", signature, send);
+        let confirmed = self
+            .confirm_signature(&signature, commitment, DEFAULT_CONFIRM_TIMEOUT)
+            .await?;
+        let confirm = started.elapsed() - send;
+        if !confirmed {
+            return Err(anyhow!(
+                "Ping order {signature} was not confirmed after {confirm:?}"
+            ));
+        }
+        info!("[*] Ping order confirmed in {:?}", confirm);
+
+        let outcome = self
+            .order_outcomes(std::slice::from_ref(&order))
+            .await?
+            .pop();
+        let filled = matches!(
+            outcome,
+            Some(OrderOutcome::FullyFilled { .. } | OrderOutcome::PartiallyFilled { .. })
+        );
+
+        let crank_started = std::time::Instant::now();
+        let crank = match self.consume_events_for_owner(crank_limit).await? {
+            Some((true, _, consumed)) => {
+                let crank = crank_started.elapsed();
+                info!("[*] Consumed {} events in {:?}", consumed, crank);
+                Some(crank)
+            }
+            Some((false, crank_signature, _)) => {
+                return Err(anyhow!("Crank {crank_signature} was not confirmed"));
+            }
+            None => None,
+        };
+
+        let settle_started = std::time::Instant::now();
+        let (settled, settle_signature) = self
+            .send_instructions(vec![
+                self.settle_instruction(&self.base_ata, &self.quote_ata)?
+            ])
+            .await?;
+        let settle = settle_started.elapsed();
+        if !settled {
+            warn!("[*] Settle {} was not confirmed", settle_signature);
+        }
+        let total = started.elapsed();
+
+        Ok(PingFillReport {
+            signature,
+            send,
+            confirm,
+            crank,
+            settle,
+            total,
+            filled,
+        })
+    }
+
+    /// Cancels up to `MAX_CANCEL_ORDERS` limit orders in the market.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - A reference to the `OBClient` struct.
+    /// * `execute` - A boolean indicating whether to execute the order immediately.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the transaction signature if successful,
+    /// or an error if canceling all orders fails.
+    ///
+    /// # Errors
+    ///
+    /// This function may return an error if there is an issue with creating or sending the transaction.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::v1::orders::OrderReturnType;
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     if let Some(ord_ret_type) = ob_client
+    ///         .cancel_orders(true)
+    ///         .await?
+    ///     {
+    ///         match ord_ret_type {
+    ///             OrderReturnType::Instructions(insts) => {
+    ///                 println!("[*] Got Instructions: {:?}", insts);
+    ///             }
+    ///             OrderReturnType::Signature(sign) | OrderReturnType::Placed(sign, _) => {
+    ///                 println!("[*] Transaction successful, signature: {:?}", sign);
+    ///             }
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn cancel_orders(&self, execute: bool) -> Result<Option<OrderReturnType>, Error> {
+        if execute {
+            let mut processed = 0;
+            let mut last_signature = None;
+
+            for oid in &self.open_orders.open_bids {
+                if processed >= MAX_CANCEL_ORDERS {
+                    break;
+                }
+
+                let ix = openbook_dex::instruction::cancel_order(
+                    &self.market_info.program_id,
+                    &self.market_info.market_address,
+                    &self.market_info.bids_address,
+                    &self.market_info.asks_address,
+                    &self.open_orders.oo_key,
+                    &self.owner_key,
+                    &self.market_info.event_queue,
+                    Side::Bid,
+                    *oid,
+                )?;
+
+                let (_, signature) = self.send_instructions(vec![ix]).await?;
+
+                last_signature = Some(signature);
+                processed += 1;
+            }
+
+            for oid in &self.open_orders.open_asks {
+                if processed >= MAX_CANCEL_ORDERS {
+                    break;
+                }
+
+                let ix = openbook_dex::instruction::cancel_order(
+                    &self.market_info.program_id,
+                    &self.market_info.market_address,
+                    &self.market_info.bids_address,
+                    &self.market_info.asks_address,
+                    &self.open_orders.oo_key,
+                    &self.owner_key,
+                    &self.market_info.event_queue,
+                    Side::Ask,
+                    *oid,
+                )?;
+
+                let (_, signature) = self.send_instructions(vec![ix]).await?;
+
+                last_signature = Some(signature);
+                processed += 1;
+            }
+
+            return Ok(last_signature.map(OrderReturnType::Signature));
+        }
+
+        let mut ixs = Vec::new();
+
+        for oid in &self.open_orders.open_bids {
+            if ixs.len() >= MAX_CANCEL_ORDERS {
+                break;
+            }
+
+            let ix = openbook_dex::instruction::cancel_order(
+                &self.market_info.program_id,
+                &self.market_info.market_address,
+                &self.market_info.bids_address,
+                &self.market_info.asks_address,
+                &self.open_orders.oo_key,
+                &self.owner_key,
+                &self.market_info.event_queue,
+                Side::Bid,
+                *oid,
+            )?;
+            ixs.push(ix);
+        }
+
+        for oid in &self.open_orders.open_asks {
+            if ixs.len() >= MAX_CANCEL_ORDERS {
+                break;
+            }
+
+            let ix = openbook_dex::instruction::cancel_order(
+                &self.market_info.program_id,
+                &self.market_info.market_address,
+                &self.market_info.bids_address,
+                &self.market_info.asks_address,
+                &self.open_orders.oo_key,
+                &self.owner_key,
+                &self.market_info.event_queue,
+                Side::Ask,
+                *oid,
+            )?;
+            ixs.push(ix);
+        }
+
+        if ixs.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(OrderReturnType::Instructions(ixs)))
+    }
+
+    /// Overrides the token accounts used to fund orders and receive settled funds.
+    ///
+    /// By default the client uses the owner's associated token accounts. Wallets whose funds
+    /// live in non-associated token accounts can point the client at them instead. Each
+    /// override is fetched and checked to be a token account of the owner holding the market's
+    /// mint before it is applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_token_account` - Optional token account holding the base mint.
+    /// * `quote_token_account` - Optional token account holding the quote mint.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an account cannot be fetched, is not a token account of the
+    /// client's token program, belongs to another wallet, or holds a different mint than the
+    /// market's base or quote mint.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let quote_account = ob_client.quote_ata;
+    ///     ob_client.set_token_accounts(None, Some(quote_account)).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn set_token_accounts(
+        &mut self,
+        base_token_account: Option<Pubkey>,
+        quote_token_account: Option<Pubkey>,
+    ) -> Result<()> {
+        if let Some(account) = base_token_account {
+            self.validate_token_account(&account, &self.market_info.base_mint)
+                .await?;
+            self.base_ata = account;
+        }
+        if let Some(account) = quote_token_account {
+            self.validate_token_account(&account, &self.market_info.quote_mint)
+                .await?;
+            self.quote_ata = account;
+        }
+        Ok(())
+    }
+
+    /// Overrides the SPL token program passed to order, settle and token account creation
+    /// instructions, for clusters where it is deployed at another address.
+    ///
+    /// Base and quote accounts that are still the owner's associated token accounts are
+    /// re-derived for the new program; accounts set with `set_token_accounts` are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     ob_client.set_token_program("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb".parse()?);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_token_program(&mut self, token_program: Pubkey) {
+        let previous = self.associated_token_accounts();
+        self.token_program = token_program;
+        self.rederive_associated_token_accounts(previous);
+    }
+
+    /// Overrides the associated token account program used to derive and create the owner's
+    /// token accounts, for clusters where it is deployed at another address.
+    ///
+    /// Base and quote accounts that are still the owner's associated token accounts are
+    /// re-derived for the new program; accounts set with `set_token_accounts` are kept.
+    pub fn set_ata_program(&mut self, ata_program: Pubkey) {
+        let previous = self.associated_token_accounts();
+        self.ata_program = ata_program;
+        self.rederive_associated_token_accounts(previous);
+    }
+
+    /// Sets the base and quote decimals instead of the values read from the mint accounts.
+    ///
+    /// Use it when the mints are non-standard. If the mint accounts cannot be fetched at all,
+    /// the client fails to start before this can be called; construct it with
+    /// [`OBClient::with_decimals`] instead, so the mints are not read. UI prices and sizes of the
+    /// current book snapshot are not converted again: reload the book afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     ob_client.set_decimals_override(6, 6);
+    ///     ob_client.load_bids_asks_info().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_decimals_override(&mut self, base: u8, quote: u8) {
+        self.market_info.coin_decimals = base;
+        self.market_info.pc_decimals = quote;
+    }
+
+    /// Returns the owner's associated token account for `mint` under the configured programs.
+    pub fn associated_token_address(&self, mint: &Pubkey) -> Pubkey {
+        self.wallet_token_address(&self.owner_key, mint)
+    }
+
+    /// Returns the associated token account of `wallet` for `mint` under the configured
+    /// programs.
+    pub fn wallet_token_address(&self, wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[wallet.as_ref(), self.token_program.as_ref(), mint.as_ref()],
+            &self.ata_program,
+        )
+        .0
+    }
+
+    /// Builds an idempotent instruction creating `account`, the associated token account of
+    /// `wallet` for `mint`, paid by the owner.
+    fn create_ata_instruction(
+        &self,
+        wallet: &Pubkey,
+        account: &Pubkey,
+        mint: &Pubkey,
+    ) -> Instruction {
+        let mut instruction = create_associated_token_account_idempotent(
+            &self.owner_key,
+            wallet,
+            mint,
+            &self.token_program,
+        );
+        // The builder derives the account under the default program ids.
+        instruction.program_id = self.ata_program;
+        instruction.accounts[1].pubkey = *account;
+        instruction
+    }
+
+    /// Returns the owner's base and quote associated token accounts.
+    fn associated_token_accounts(&self) -> (Pubkey, Pubkey) {
+        (
+            self.associated_token_address(&self.market_info.base_mint),
+            self.associated_token_address(&self.market_info.quote_mint),
+        )
+    }
+
+    /// Moves the base and quote accounts still equal to `previous` to the current derivation.
+    fn rederive_associated_token_accounts(&mut self, previous: (Pubkey, Pubkey)) {
+        let (base, quote) = self.associated_token_accounts();
+        if self.base_ata == previous.0 {
+            self.base_ata = base;
+        }
+        if self.quote_ata == previous.1 {
+            self.quote_ata = quote;
+        }
+    }
+
+    /// Checks that `token_account` is a token account of the client's token program, owned by
+    /// the owner and holding `mint`.
+    async fn validate_token_account(&self, token_account: &Pubkey, mint: &Pubkey) -> Result<()> {
+        let account = self.rpc_client.inner().get_account(token_account).await?;
+        if account.owner != self.token_program {
+            return Err(anyhow!(
+                "Account {token_account} is owned by {}, not the token program {}",
+                account.owner,
+                self.token_program
+            ));
+        }
+        // Token-2022 accounts append their extensions after the base layout.
+        let len = account.data.len();
+        let classic = self.token_program.to_string() == SPL_TOKEN_ID;
+        if len < spl_token::state::Account::LEN
+            || (classic && len != spl_token::state::Account::LEN)
+        {
+            return Err(anyhow!(
+                "Account {token_account} is not a token account ({len} bytes)"
+            ));
+        }
+        let read = |offset: usize| Pubkey::try_from(&account.data[offset..offset + 32]);
+        let account_mint = read(0)?;
+        if account_mint != *mint {
+            return Err(anyhow!(
+                "Token account {token_account} holds mint {account_mint}, expected {mint}"
+            ));
+        }
+        let account_owner = read(32)?;
+        if account_owner != self.owner_key {
+            return Err(anyhow!(
+                "Token account {token_account} belongs to {account_owner}, not {}",
+                self.owner_key
+            ));
+        }
+        Ok(())
+    }
+
+    /// Builds idempotent instructions creating the owner's missing associated token accounts.
+    ///
+    /// Only the base and quote accounts that are still the owner's associated token accounts
+    /// are considered; overridden token accounts are never created.
+    ///
+    /// # Returns
+    ///
+    /// The list of `createAssociatedTokenAccountIdempotent` instructions, empty if both
+    /// accounts already exist.
+    pub async fn missing_ata_instructions(&self) -> Result<Vec<Instruction>> {
+        let owner = self.owner_key;
+        let candidates = [
+            (self.base_ata, self.market_info.base_mint),
+            (self.quote_ata, self.market_info.quote_mint),
+        ];
+        let candidates: Vec<(Pubkey, Pubkey)> = candidates
+            .into_iter()
+            .filter(|(account, mint)| *account == self.associated_token_address(mint))
+            .collect();
+        let keys: Vec<Pubkey> = candidates.iter().map(|(account, _)| *account).collect();
+        let accounts = self.rpc_client.fetch_multiple_accounts(&keys).await?;
+
+        let mut instructions = Vec::new();
+        for ((account, mint), fetched) in candidates.iter().zip(accounts) {
+            if fetched.is_none() {
+                debug!("[*] Missing associated token account: {:?}", account);
+                instructions.push(self.create_ata_instruction(&owner, account, mint));
+            }
+        }
+        Ok(instructions)
+    }
+
+    /// Makes every settlement create the owner's missing associated token accounts first.
+    ///
+    /// `SettleFunds` fails when the base or quote account does not exist, which is the case on
+    /// a new wallet's first settle. With this set, `settle_balance` looks the accounts up and
+    /// prepends a `createAssociatedTokenAccountIdempotent` instruction for each missing one to
+    /// the settle transaction, including the settle of the cancel / settle / place combos.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     ob_client.set_create_missing_atas(true);
+    ///     let result = ob_client.settle_balance(true).await?;
+    ///
+    ///     println!("{:?}", result);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_create_missing_atas(&mut self, enabled: bool) {
+        self.create_missing_atas = enabled;
+    }
+
+    /// Simulates every transaction that places an order before sending it, and refuses to send
+    /// it if the simulation fails, see [`OBClient::simulate_funds`].
+    ///
+    /// This costs one more RPC round trip per placement, but an order the wallet cannot pay for,
+    /// e.g. a marketable order taking more than the token account holds, fails without paying a
+    /// fee.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     ob_client.set_check_funds(true);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_check_funds(&mut self, enabled: bool) {
+        self.check_funds = enabled;
+    }
+
+    /// Simulates `instructions` and reads the wallet's base and quote token balances before and
+    /// after.
+    ///
+    /// Token balances cannot go negative: the token program fails a transfer larger than the
+    /// balance, so an order that would overdraw a token account makes the simulation fail.
+    ///
+    /// # Returns
+    ///
+    /// The balances in UI units. An account the simulation did not return keeps its balance.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the client is read-only or the simulation fails, with the last
+    /// program log line.
+    pub async fn simulate_funds(&self, instructions: &[Instruction]) -> Result<FundsCheck> {
+        let payer = self.signer()?.pubkey();
+        let accounts = [self.base_ata, self.quote_ata];
+        let before: Vec<u64> = self
+            .rpc_client
+            .fetch_multiple_accounts(&accounts)
+            .await?
+            .iter()
+            .map(|account| {
+                account
+                    .as_ref()
+                    .and_then(|account| token_account_amount(&account.data))
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        let simulation = self
+            .rpc_client
+            .simulate_with_accounts(&payer, instructions, &accounts)
+            .await?;
+        if let Some(err) = simulation.err {
+            let logs = simulation.logs.unwrap_or_default();
+            error!("[*] Simulated placement failed: {}, logs: {:?}", err, logs);
+            return Err(anyhow!(
+                "Simulated placement failed ({err}), the wallet may not hold enough funds: {}",
+                logs.last().map(String::as_str).unwrap_or("no logs")
+            ));
+        }
+        let after: Vec<u64> = simulation
+            .accounts
+            .unwrap_or_default()
+            .into_iter()
+            .chain(std::iter::repeat(None))
+            .zip(&before)
+            .map(|(account, before)| {
+                account
+                    .and_then(|account| account.decode::<Account>())
+                    .and_then(|account| token_account_amount(&account.data))
+                    .unwrap_or(*before)
+            })
+            .collect();
+
+        let base_scale = 10f64.powi(self.market_info.coin_decimals as i32);
+        let quote_scale = 10f64.powi(self.market_info.pc_decimals as i32);
+        Ok(FundsCheck {
+            base_before: before[0] as f64 / base_scale,
+            base_after: after[0] as f64 / base_scale,
+            quote_before: before[1] as f64 / quote_scale,
+            quote_after: after[1] as f64 / quote_scale,
+        })
+    }
+
+    /// Creates the owner's missing base and quote associated token accounts.
+    ///
+    /// # Arguments
+    ///
+    /// * `execute` - A boolean indicating whether to send the transaction or only return its instructions.
+    ///
+    /// # Returns
+    ///
+    /// `None` if both accounts already exist, otherwise the instructions or the signature of
+    /// the transaction creating them.
+    pub async fn create_associated_token_accounts(
+        &self,
+        execute: bool,
+    ) -> Result<Option<OrderReturnType>, Error> {
+        let instructions = self.missing_ata_instructions().await?;
+        if instructions.is_empty() {
+            return Ok(None);
+        }
+
+        if !execute {
+            return Ok(Some(OrderReturnType::Instructions(instructions)));
+        }
+
+        let (_, signature) = self.send_instructions(instructions).await?;
+
+        Ok(Some(OrderReturnType::Signature(signature)))
+    }
+
+    /// Cancels up to `max_orders` open orders, sending `per_tx` cancels per transaction.
+    ///
+    /// Every chunk is signed with a fresh blockhash, and an unconfirmed chunk is resent up to
+    /// `cancel_retries` times, so a large cancel set does not fail because the first blockhash
+    /// expired. A failing chunk does not stop the following ones.
+    ///
+    /// Once every chunk was sent the book is re-read, which also refreshes `open_orders` and
+    /// `order_book`, to tell which orders still rest.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_orders` - The maximum number of orders to cancel, bids first.
+    /// * `per_tx` - The number of cancel instructions per transaction.
+    ///
+    /// # Returns
+    ///
+    /// One `CancelChunkResult` per chunk, in sending order, and the client order ids still
+    /// resting afterwards, so the caller can retry only what did not cancel.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let result = ob_client.cancel_orders_chunked(10, 5).await?;
+    ///     for chunk in &result.chunks {
+    ///         println!("{:?}", chunk);
+    ///     }
+    ///     println!("still resting: {:?}", result.still_resting);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn cancel_orders_chunked(
+        &mut self,
+        max_orders: usize,
+        per_tx: usize,
+    ) -> Result<ChunkedCancelResult> {
+        // Fail before sending anything on a read-only client.
+        self.signer()?;
+        let orders: Vec<(Side, u128)> = self
+            .open_orders
+            .open_bids
+            .iter()
+            .map(|oid| (Side::Bid, *oid))
+            .chain(
+                self.open_orders
+                    .open_asks
+                    .iter()
+                    .map(|oid| (Side::Ask, *oid)),
+            )
+            .take(max_orders)
+            .collect();
+        let oo_key = self.open_orders.oo_key;
+        let client_order_ids: HashMap<u128, u64> = self
+            .order_book
+            .bids
+            .iter()
+            .chain(self.order_book.asks.iter())
+            .filter(|order| order.owner == oo_key)
+            .map(|order| (order.order_id, order.client_order_id))
+            .collect();
+
+        let mut results = Vec::new();
+        for chunk in orders.chunks(per_tx.max(1)) {
+            let instructions = self.cancel_instructions(oo_key, chunk)?;
+
+            let mut result = CancelChunkResult {
+                order_ids: chunk.iter().map(|(_, oid)| *oid).collect(),
+                client_order_ids: chunk
+                    .iter()
+                    .map(|(_, oid)| client_order_ids.get(oid).copied().unwrap_or_default())
+                    .collect(),
+                signature: None,
+                attempts: 0,
+                confirmed: false,
+            };
+            for attempt in 0..=self.cancel_retries {
+                result.attempts = attempt + 1;
+                match self.send_instructions(instructions.clone()).await {
+                    Ok((confirmed, signature)) => {
+                        result.confirmed = confirmed;
+                        if signature != Signature::default() {
+                            result.signature = Some(signature);
+                        }
+                    }
+                    Err(err) => warn!("[*] Cancel chunk attempt {} failed: {}", attempt, err),
+                }
+                if result.confirmed {
+                    break;
+                }
+            }
+            if !result.confirmed {
+                warn!("[*] Cancel chunk {:?} was not confirmed", result.order_ids);
+            }
+            results.push(result);
+        }
+
+        let still_resting = if results.is_empty() {
+            Some(Vec::new())
+        } else {
+            match self.load_bids_asks_info().await {
+                Ok(_) => Some(
+                    self.order_book
+                        .bids
+                        .iter()
+                        .chain(self.order_book.asks.iter())
+                        .filter(|order| order.owner == oo_key)
+                        .map(|order| order.client_order_id)
+                        .collect(),
+                ),
+                Err(err) => {
+                    warn!("[*] Unable to re-read the book after cancelling: {}", err);
+                    None
+                }
+            }
+        };
+
+        Ok(ChunkedCancelResult {
+            chunks: results,
+            still_resting,
+        })
+    }
+
+    /// Builds one `cancel_order` instruction per `(side, order_id)` of the open orders account
+    /// `oo_key`.
+    fn cancel_instructions(
+        &self,
+        oo_key: Pubkey,
+        orders: &[(Side, u128)],
+    ) -> Result<Vec<Instruction>> {
+        let mut instructions = Vec::with_capacity(orders.len());
+        for (side, oid) in orders {
+            instructions.push(openbook_dex::instruction::cancel_order(
+                &self.market_info.program_id,
+                &self.market_info.market_address,
+                &self.market_info.bids_address,
+                &self.market_info.asks_address,
+                &oo_key,
+                &self.owner_key,
+                &self.market_info.event_queue,
+                *side,
+                *oid,
+            )?);
+        }
+        Ok(instructions)
+    }
+
+    /// Lists every open orders account the owner holds on this market.
+    pub async fn open_orders_accounts(&self) -> Result<Vec<Pubkey>> {
+        self.rpc_client
+            .fetch_v1_open_orders_accounts(
+                self.market_info.program_id,
+                self.market_info.market_address,
+                self.owner_key,
+            )
+            .await
+    }
+
+    /// Makes sure the owner holds `n` open orders accounts on this market and pools them.
+    ///
+    /// Orders of different open orders accounts do not contend for the same account, so a taker
+    /// can match from several in parallel. Existing accounts are reused, the configured one
+    /// first; missing ones are created with `create_account_with_seed`, from the owner and a seed
+    /// made of the market address and the pool index, so the owner is their only signer and the
+    /// same accounts are found again on the next run.
+    ///
+    /// Each created account holds `OPEN_ORDERS_ACCOUNT_LEN` bytes and is funded with
+    /// [`OBClient::open_orders_account_lamports`], about 0.0234 SOL, until it is closed. The
+    /// exact amount is returned with the accounts.
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - The number of accounts the pool should hold.
+    /// * `execute` - Whether to create the missing accounts or only build their instructions.
+    ///   Accounts that are not created are left out of the pool.
+    ///
+    /// # Returns
+    ///
+    /// The lamports funding each created account, and each missing account with its signature
+    /// or instructions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the accounts cannot be listed or one cannot be created.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let (rent, created) = ob_client.ensure_open_orders_pool(4, true).await?;
+    ///     println!("created {} accounts, {} lamports each", created.len(), rent);
+    ///
+    ///     let oo_key = ob_client.next_open_orders();
+    ///     println!("next orders go through {}", oo_key);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn ensure_open_orders_pool(
+        &mut self,
+        n: usize,
+        execute: bool,
+    ) -> Result<(u64, Vec<(Pubkey, OrderReturnType)>)> {
+        if execute {
+            self.signer()?;
+        }
+        let mut existing = self.open_orders_accounts().await?;
+        // Keep the configured account at the front of the pool.
+        existing.sort_by_key(|key| *key != self.open_orders.oo_key);
+
+        let mut pool: Vec<Pubkey> = existing.iter().copied().take(n).collect();
+        let lamports = self.open_orders_account_lamports().await?;
+
+        let mut created = Vec::new();
+        // Accounts only planned in dry runs, created ones are counted in the pool.
+        let mut planned = 0;
+        let mut index = 0;
+        while pool.len() + planned < n {
+            let (address, instructions) = self.create_open_orders_instructions(index, lamports)?;
+            index += 1;
+            if existing.contains(&address) {
+                continue;
+            }
+            if execute {
+                let (confirmed, signature) = self.send_instructions(instructions).await?;
+                if !confirmed {
+                    return Err(anyhow!(
+                        "Open orders account {address} was not confirmed ({signature})"
+                    ));
+                }
+                info!("[*] Created open orders account {}", address);
+                pool.push(address);
+                created.push((address, OrderReturnType::Signature(signature)));
+            } else {
+                planned += 1;
+                created.push((address, OrderReturnType::Instructions(instructions)));
+            }
+        }
+
+        self.open_orders_pool = pool;
+        self.pool_cursor = 0;
+
+        Ok((lamports, created))
+    }
+
+    /// Returns the lamports funding a new open orders account: the rent exemption of
+    /// `OPEN_ORDERS_ACCOUNT_LEN` bytes, queried with `getMinimumBalanceForRentExemption` so a
+    /// change of the rent schedule is followed, plus `open_orders_extra_lamports`.
+    pub async fn open_orders_account_lamports(&self) -> Result<u64> {
+        let rent = self
+            .rpc_client
+            .inner()
+            .get_minimum_balance_for_rent_exemption(OPEN_ORDERS_ACCOUNT_LEN)
+            .await?;
+        Ok(rent + self.open_orders_extra_lamports)
+    }
+
+    /// Creates one more open orders account of the owner on this market.
+    ///
+    /// The account is derived like the pooled ones, see [`OBClient::ensure_open_orders_pool`],
+    /// at the first pool index with no account yet, and funded with
+    /// [`OBClient::open_orders_account_lamports`]. It is not added to the pool nor made the
+    /// client's account.
+    ///
+    /// # Arguments
+    ///
+    /// * `execute` - Whether to create the account or only build its instructions.
+    ///
+    /// # Returns
+    ///
+    /// The new account, the lamports funding it, and the signature or instructions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the accounts cannot be listed or the creation is not confirmed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     ob_client.open_orders_extra_lamports = 100_000;
+    ///     let (oo_key, lamports, _) = ob_client.create_open_orders_account(true).await?;
+    ///     println!("created {} with {} lamports", oo_key, lamports);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn create_open_orders_account(
+        &mut self,
+        execute: bool,
+    ) -> Result<(Pubkey, u64, OrderReturnType)> {
+        if execute {
+            self.signer()?;
+        }
+        let existing = self.open_orders_accounts().await?;
+        let lamports = self.open_orders_account_lamports().await?;
+
+        let mut index = 0;
+        let (address, instructions) = loop {
+            let (address, instructions) = self.create_open_orders_instructions(index, lamports)?;
+            if !existing.contains(&address) {
+                break (address, instructions);
+            }
+            index += 1;
         };
+        if !execute {
+            return Ok((
+                address,
+                lamports,
+                OrderReturnType::Instructions(instructions),
+            ));
+        }
 
-        Ok((bids_address, asks_address, self.open_orders.clone()))
-    }
-
-    /// Fetches the raw event queue header so callers can see if cranking is needed.
-    pub async fn fetch_event_queue_stats(&self) -> Result<EventQueueStats> {
-        let account = self
-            .rpc_client
-            .inner()
-            .get_account(&self.market_info.event_queue)
-            .await?;
-
-        let header_len = std::mem::size_of::<EventQueueHeader>();
-        let head_pad = ACCOUNT_HEAD_PADDING.len();
-        let tail_pad = ACCOUNT_TAIL_PADDING.len();
-        if account.data.len() < head_pad + header_len + tail_pad {
+        let (confirmed, signature) = self.send_instructions(instructions).await?;
+        if !confirmed {
             return Err(anyhow!(
-                "Event queue account too small ({} bytes)",
-                account.data.len()
+                "Open orders account {address} was not confirmed ({signature})"
             ));
         }
-        let usable = &account.data[head_pad..account.data.len() - tail_pad];
-        let header_bytes = &usable[..header_len];
+        info!(
+            "[*] Created open orders account {} with {} lamports",
+            address, lamports
+        );
+        Ok((address, lamports, OrderReturnType::Signature(signature)))
+    }
 
-        let stats = EventQueueStats {
-            account_flags: Self::read_u64_field(header_bytes, 0)?,
-            head: Self::read_u64_field(header_bytes, 8)?,
-            count: Self::read_u64_field(header_bytes, 16)?,
-            seq_num: Self::read_u64_field(header_bytes, 24)?,
-        };
+    /// Builds the instructions creating and initializing the `index`th pooled open orders
+    /// account, funded with `lamports`, and returns its address with them.
+    fn create_open_orders_instructions(
+        &self,
+        index: usize,
+        lamports: u64,
+    ) -> Result<(Pubkey, Vec<Instruction>)> {
+        let seed = self.open_orders_pool_seed(index);
+        let address =
+            Pubkey::create_with_seed(&self.owner_key, &seed, &self.market_info.program_id)?;
+        let instructions = vec![
+            solana_sdk::system_instruction::create_account_with_seed(
+                &self.owner_key,
+                &address,
+                &self.owner_key,
+                &seed,
+                lamports,
+                OPEN_ORDERS_ACCOUNT_LEN as u64,
+                &self.market_info.program_id,
+            ),
+            openbook_dex::instruction::init_open_orders(
+                &self.market_info.program_id,
+                &address,
+                &self.owner_key,
+                &self.market_info.market_address,
+                None,
+            )?,
+        ];
+        Ok((address, instructions))
+    }
 
-        Ok(stats)
+    /// Seed of the `index`th pooled open orders account, unique per market and at most the 32
+    /// bytes `create_with_seed` accepts.
+    fn open_orders_pool_seed(&self, index: usize) -> String {
+        let market = self.market_info.market_address.to_string();
+        format!("{}-{index}", &market[..20])
     }
 
-    /// Collects unique open orders accounts referenced in the pending event queue.
-    pub async fn collect_event_queue_open_orders(
-        &self,
-        max_accounts: usize,
-    ) -> Result<Vec<Pubkey>> {
-        let event_queue_account = self
-            .rpc_client
-            .inner()
-            .get_account(&self.market_info.event_queue)
-            .await?;
-        let head_pad = ACCOUNT_HEAD_PADDING.len();
-        let tail_pad = ACCOUNT_TAIL_PADDING.len();
-        if event_queue_account.data.len() < head_pad + tail_pad {
-            return Ok(Vec::new());
-        }
-        let usable =
-            &event_queue_account.data[head_pad..event_queue_account.data.len() - tail_pad];
-        let header_len = std::mem::size_of::<EventQueueHeader>();
-        if usable.len() < header_len {
-            return Ok(Vec::new());
-        }
-        let head = Self::read_u64_field(usable, 8)? as usize;
-        let count = Self::read_u64_field(usable, 16)? as usize;
-        let events_bytes = &usable[header_len..];
-        let event_size = std::mem::size_of::<openbook_dex::state::Event>();
-        if event_size == 0 {
-            return Ok(Vec::new());
+    /// Switches to the next open orders account of the pool, round-robin, and returns it.
+    ///
+    /// Orders built afterwards go through that account. The open orders state of the client is
+    /// still that of the previous account until the book is reloaded. With an empty pool the
+    /// configured account is kept.
+    pub fn next_open_orders(&mut self) -> Pubkey {
+        if let Some(&oo_key) = self.open_orders_pool.get(self.pool_cursor) {
+            self.pool_cursor = (self.pool_cursor + 1) % self.open_orders_pool.len();
+            self.open_orders.oo_key = oo_key;
         }
-        let capacity = events_bytes.len() / event_size;
-        if capacity == 0 {
-            return Ok(Vec::new());
+        self.open_orders.oo_key
+    }
+
+    /// Cancels the resting orders of every open orders account the owner holds on this market.
+    ///
+    /// The accounts are listed on chain, so orders left by other instances of a strategy are
+    /// cancelled too, not only those of `OOS_KEY`. The book is reloaded first and each account's
+    /// orders are cancelled `MAX_CANCEL_ORDERS` per transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `execute` - Whether to send the cancels or only build them.
+    ///
+    /// # Returns
+    ///
+    /// For each open orders account with resting orders, one `OrderReturnType` per transaction:
+    /// its signature when `execute` is set, its instructions otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     for (open_orders, cancels) in ob_client.cancel_all_accounts(true).await? {
+    ///         println!("{}: {:?}", open_orders, cancels);
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn cancel_all_accounts(
+        &mut self,
+        execute: bool,
+    ) -> Result<Vec<(Pubkey, Vec<OrderReturnType>)>> {
+        if execute {
+            self.signer()?;
         }
+        let accounts = self.open_orders_accounts().await?;
+        self.load_bids_asks_info().await?;
 
-        let mut seen = HashSet::new();
-        let mut owners = Vec::new();
-        let max_events = count.min(capacity);
-        for offset in 0..max_events {
-            let idx = (head + offset) % capacity;
-            let start = idx * event_size;
-            let end = start + event_size;
-            if end > events_bytes.len() {
-                break;
+        let mut results = Vec::new();
+        for oo_key in accounts {
+            let orders: Vec<(Side, u128)> = self
+                .order_book
+                .bids
+                .iter()
+                .filter(|order| order.owner == oo_key)
+                .map(|order| (Side::Bid, order.order_id))
+                .chain(
+                    self.order_book
+                        .asks
+                        .iter()
+                        .filter(|order| order.owner == oo_key)
+                        .map(|order| (Side::Ask, order.order_id)),
+                )
+                .collect();
+            if orders.is_empty() {
+                continue;
             }
-            let event = Self::event_from_bytes(&events_bytes[start..end])?;
-            let owner = Pubkey::from(u64_slice_to_pubkey(event.owner));
-            if seen.insert(owner) {
-                owners.push(owner);
-                if max_accounts > 0 && owners.len() >= max_accounts {
-                    break;
+
+            let mut transactions = Vec::new();
+            for chunk in orders.chunks(MAX_CANCEL_ORDERS) {
+                let instructions = self.cancel_instructions(oo_key, chunk)?;
+                if execute {
+                    let (_, signature) = self.send_instructions(instructions).await?;
+                    transactions.push(OrderReturnType::Signature(signature));
+                } else {
+                    transactions.push(OrderReturnType::Instructions(instructions));
                 }
             }
+            results.push((oo_key, transactions));
         }
 
-        Ok(owners)
+        Ok(results)
     }
 
-    fn read_u64_field(bytes: &[u8], offset: usize) -> Result<u64> {
-        let end = offset + 8;
-        let chunk = bytes
-            .get(offset..end)
-            .ok_or_else(|| anyhow!("Missing bytes in event queue header"))?;
-        let arr: [u8; 8] = chunk
-            .try_into()
-            .map_err(|_| anyhow!("Failed to parse event queue header"))?;
-        Ok(u64::from_le_bytes(arr))
+    /// Cancels this client's resting orders whose client order id is within `[low, high]`.
+    ///
+    /// Strategies that encode themselves in the high bits of their client order ids can share
+    /// one open orders account and be cancelled independently. The book is reloaded first, and
+    /// matching orders are cancelled with `CancelOrdersByClientIds`, 8 per transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `low` - The lowest client order id to cancel, inclusive.
+    /// * `high` - The highest client order id to cancel, inclusive.
+    /// * `execute` - Whether to send the cancels or only build them.
+    ///
+    /// # Returns
+    ///
+    /// The cancelled orders, and one signature or instruction set per transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `low` is above `high`, or if the book cannot be read or a
+    /// transaction cannot be sent.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     // Every id whose top byte is 0x01.
+    ///     let (orders, transactions) = ob_client
+    ///         .cancel_orders_by_client_id_range(0x0100_0000_0000_0000, 0x01ff_ffff_ffff_ffff, false)
+    ///         .await?;
+    ///
+    ///     println!("{:?} {:?}", orders, transactions);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn cancel_orders_by_client_id_range(
+        &mut self,
+        low: u64,
+        high: u64,
+        execute: bool,
+    ) -> Result<(Vec<BookOrder>, Vec<OrderReturnType>)> {
+        if low > high {
+            return Err(anyhow!("Invalid client order id range {low} to {high}"));
+        }
+        if execute {
+            self.signer()?;
+        }
+        self.load_bids_asks_info().await?;
+
+        let oo_key = self.open_orders.oo_key;
+        // Zero pads the id list of the instruction, so an order with that id cannot be matched.
+        let orders: Vec<BookOrder> = self
+            .order_book
+            .bids
+            .iter()
+            .chain(&self.order_book.asks)
+            .filter(|order| {
+                order.owner == oo_key
+                    && order.client_order_id != 0
+                    && (low..=high).contains(&order.client_order_id)
+            })
+            .copied()
+            .collect();
+
+        let mut transactions = Vec::new();
+        for chunk in orders.chunks(MAX_CLIENT_IDS_PER_CANCEL) {
+            let mut client_order_ids = [0u64; MAX_CLIENT_IDS_PER_CANCEL];
+            for (slot, order) in client_order_ids.iter_mut().zip(chunk) {
+                *slot = order.client_order_id;
+            }
+            let instructions = vec![
+                openbook_dex::instruction::cancel_orders_by_client_order_ids(
+                    &self.market_info.program_id,
+                    &self.market_info.market_address,
+                    &self.market_info.bids_address,
+                    &self.market_info.asks_address,
+                    &oo_key,
+                    &self.owner_key,
+                    &self.market_info.event_queue,
+                    client_order_ids,
+                )?,
+            ];
+            if execute {
+                let (_, signature) = self.send_instructions(instructions).await?;
+                transactions.push(OrderReturnType::Signature(signature));
+            } else {
+                transactions.push(OrderReturnType::Instructions(instructions));
+            }
+        }
+
+        Ok((orders, transactions))
     }
 
-    fn event_from_bytes(bytes: &[u8]) -> Result<openbook_dex::state::Event> {
-        if bytes.len() < std::mem::size_of::<openbook_dex::state::Event>() {
-            return Err(anyhow!("Incomplete event record"));
+    /// Cancels this client's resting orders priced within `[min_price, max_price]`.
+    ///
+    /// The book is reloaded first, and orders of `OOS_KEY` inside the band are cancelled
+    /// `MAX_CANCEL_ORDERS` per transaction. Use it to pull only the quotes that went stale, for
+    /// example those too far from the mid, and leave the others resting.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_price` - The lowest price to cancel, inclusive.
+    /// * `max_price` - The highest price to cancel, inclusive.
+    /// * `side` - Only cancel orders of this side. `None` cancels both.
+    /// * `execute` - Whether to send the cancels or only build them.
+    ///
+    /// # Returns
+    ///
+    /// The cancelled orders, and one signature or instruction set per transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `min_price` is above `max_price`, or if the book cannot be read or a
+    /// transaction cannot be sent.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::matching::Side;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let (orders, transactions) = ob_client
+    ///         .cancel_in_price_range(0.0, 1.9, Some(Side::Bid), false)
+    ///         .await?;
+    ///
+    ///     println!("{:?} {:?}", orders, transactions);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn cancel_in_price_range(
+        &mut self,
+        min_price: f64,
+        max_price: f64,
+        side: Option<Side>,
+        execute: bool,
+    ) -> Result<(Vec<BookOrder>, Vec<OrderReturnType>)> {
+        if min_price.is_nan() || max_price.is_nan() || min_price > max_price {
+            return Err(anyhow!("Invalid price range {min_price} to {max_price}"));
+        }
+        if execute {
+            self.signer()?;
+        }
+        self.load_bids_asks_info().await?;
+
+        let oo_key = self.open_orders.oo_key;
+        let in_range = |order: &&BookOrder| {
+            order.owner == oo_key && order.price >= min_price && order.price <= max_price
+        };
+        let mut orders: Vec<(Side, BookOrder)> = Vec::new();
+        if side != Some(Side::Ask) {
+            orders.extend(
+                self.order_book
+                    .bids
+                    .iter()
+                    .filter(in_range)
+                    .map(|order| (Side::Bid, *order)),
+            );
+        }
+        if side != Some(Side::Bid) {
+            orders.extend(
+                self.order_book
+                    .asks
+                    .iter()
+                    .filter(in_range)
+                    .map(|order| (Side::Ask, *order)),
+            );
+        }
+
+        let ids: Vec<(Side, u128)> = orders
+            .iter()
+            .map(|(side, order)| (*side, order.order_id))
+            .collect();
+        let mut transactions = Vec::new();
+        for chunk in ids.chunks(MAX_CANCEL_ORDERS) {
+            let instructions = self.cancel_instructions(oo_key, chunk)?;
+            if execute {
+                let (_, signature) = self.send_instructions(instructions).await?;
+                transactions.push(OrderReturnType::Signature(signature));
+            } else {
+                transactions.push(OrderReturnType::Instructions(instructions));
+            }
         }
-        // SAFETY: Event is #[repr(packed)] and Copy, so read_unaligned is safe.
-        let event =
-            unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const openbook_dex::state::Event) };
-        Ok(event)
+
+        Ok((
+            orders.into_iter().map(|(_, order)| order).collect(),
+            transactions,
+        ))
     }
 
-    /// Processes bids information to find the maximum bid price.
+    /// Cancels this client's orders by the ids read from the bids and asks slabs, rather than
+    /// from the order id array of the open orders account.
     ///
-    /// This function removes bids from the provided `Slab` to find the maximum bid price.
+    /// The book is re-read, which also refreshes `open_orders` and `order_book`, and the open
+    /// orders account is fetched to cross-check both sources: every order id found in only one
+    /// of them is logged. Only what rests on the book is cancelled, since an id the account
+    /// lists without a matching leaf has nothing left to cancel.
     ///
     /// # Arguments
     ///
-    /// * `&self` - A reference to the `OBClient` struct.
-    /// * `bids` - A mutable reference to the `Slab` containing bids information.
+    /// * `execute` - Whether to send the cancels, or only build their instructions.
     ///
     /// # Returns
     ///
-    /// A `Result` containing the maximum bid price if successful, or an error if processing bids fails.
+    /// A `BookCancelResult` with the orders cancelled, the ids each source lacked and one
+    /// entry per cancel transaction of up to `MAX_CANCEL_ORDERS` instructions.
     ///
     /// # Errors
     ///
-    /// This function may return an error if there is an issue with processing the bids information.
-    pub fn process_bids(&self, bids: &mut RefMut<Slab>) -> Result<(Vec<u128>, Vec<f64>, u64)> {
-        let mut max_bid = 0;
-        let mut open_bids = Vec::new();
-        let mut open_bids_prices = Vec::new();
-        loop {
-            let node = bids.remove_max();
-            match node {
-                Some(node) => {
-                    let owner = node.owner();
-                    let bytes = u64_slice_to_pubkey(owner);
-                    let owner_address = Pubkey::from(bytes);
+    /// Returns an error if the book or the open orders account cannot be read, or if a cancel
+    /// transaction fails to send.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let result = ob_client.cancel_orders_by_book_read(true).await?;
+    ///     if !result.in_sync() {
+    ///         println!("book only: {:?}", result.book_only);
+    ///         println!("account only: {:?}", result.account_only);
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn cancel_orders_by_book_read(&mut self, execute: bool) -> Result<BookCancelResult> {
+        if execute {
+            self.signer()?;
+        }
+        self.load_bids_asks_info().await?;
+        let oo_key = self.open_orders.oo_key;
+        let (data, slot) = self.fetch_account_raw(oo_key).await?;
+        let account = OoSnapshot::from_account_data(
+            slot,
+            &data,
+            self.market_info.coin_decimals,
+            self.market_info.pc_decimals,
+        )?;
 
-                    let order_id = node.order_id();
-                    let price_raw = node.price().get();
-                    let ui_price = price_raw as f64 / 1e4;
+        let orders: Vec<(Side, BookOrder)> = [Side::Bid, Side::Ask]
+            .into_iter()
+            .flat_map(|side| {
+                self.order_book
+                    .side(side)
+                    .iter()
+                    .filter(|order| order.owner == oo_key)
+                    .map(move |order| (side, *order))
+            })
+            .collect();
+        let on_book: HashSet<u128> = orders.iter().map(|(_, order)| order.order_id).collect();
+        let in_account: HashSet<u128> = account.orders.iter().map(|order| order.order_id).collect();
+        let book_only: Vec<u128> = orders
+            .iter()
+            .map(|(_, order)| order.order_id)
+            .filter(|order_id| !in_account.contains(order_id))
+            .collect();
+        let account_only: Vec<u128> = account
+            .orders
+            .iter()
+            .map(|order| order.order_id)
+            .filter(|order_id| !on_book.contains(order_id))
+            .collect();
+        for order_id in &book_only {
+            warn!(
+                "[*] Order {} rests on the book but is not in open orders account {}",
+                order_id, oo_key
+            );
+        }
+        for order_id in &account_only {
+            warn!(
+                "[*] Order {} is in open orders account {} but not on the book",
+                order_id, oo_key
+            );
+        }
 
-                    debug!("[*] Bid: {price_raw}");
+        let ids: Vec<(Side, u128)> = orders
+            .iter()
+            .map(|(side, order)| (*side, order.order_id))
+            .collect();
+        let mut transactions = Vec::new();
+        for chunk in ids.chunks(MAX_CANCEL_ORDERS) {
+            let instructions = self.cancel_instructions(oo_key, chunk)?;
+            if execute {
+                let (_, signature) = self.send_instructions(instructions).await?;
+                transactions.push(OrderReturnType::Signature(signature));
+            } else {
+                transactions.push(OrderReturnType::Instructions(instructions));
+            }
+        }
 
-                    if max_bid == 0 {
-                        max_bid = price_raw;
-                    }
+        Ok(BookCancelResult {
+            orders: orders.into_iter().map(|(_, order)| order).collect(),
+            book_only,
+            account_only,
+            transactions,
+        })
+    }
 
-                    if owner_address == self.open_orders.oo_key {
-                        open_bids.push(order_id);
-                        open_bids_prices.push(ui_price);
-                    }
+    /// Compares the owner's resting orders with the desired quotes, so a requote only touches
+    /// what changed.
+    ///
+    /// A resting order is kept when a desired quote on its side rounds to the same price and
+    /// size in lots, as it would be placed; partially filled orders therefore no longer match.
+    /// Resting orders come from the last loaded book snapshot, call `load_bids_asks_info`
+    /// first for a fresh view.
+    ///
+    /// # Arguments
+    ///
+    /// * `bids` - The desired bids.
+    /// * `asks` - The desired asks.
+    ///
+    /// # Returns
+    ///
+    /// A `QuotePlan` with the orders to keep, to cancel and to place. Desired quotes rounding to
+    /// zero lots are left out.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    /// use openbook::v1::orders::PlaceSpec;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let bids = [PlaceSpec { size_quote: 5.0, price: 2.1 }];
+    ///     let asks = [PlaceSpec { size_quote: 5.0, price: 2.3 }];
+    ///     let plan = ob_client.quote_diff(&bids, &asks);
+    ///     let transactions = ob_client.apply_quote_plan(&plan, true).await?;
+    ///
+    ///     println!("Requoted in {} transactions", transactions.len());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn quote_diff(&self, bids: &[PlaceSpec], asks: &[PlaceSpec]) -> QuotePlan {
+        let price_factor = self.market_info.price_factor();
+        let mut plan = QuotePlan::default();
+
+        for (side, desired) in [(Side::Bid, bids), (Side::Ask, asks)] {
+            let mut resting: Vec<BookOrder> = self
+                .order_book
+                .side(side)
+                .iter()
+                .filter(|order| order.owner == self.open_orders.oo_key)
+                .copied()
+                .collect();
+
+            for spec in desired {
+                let price_lots = (spec.price * price_factor) as u64;
+                let base_lots = self
+                    .order_base_lots(spec.size_quote, spec.price)
+                    .unwrap_or(0);
+                if price_lots == 0 || base_lots == 0 {
+                    warn!(
+                        "[*] {:?} quote {} @ {} rounds to zero lots, skipped",
+                        side, spec.size_quote, spec.price
+                    );
+                    continue;
                 }
-                None => {
-                    break;
+                match resting.iter().position(|order| {
+                    order.price_lots == price_lots && order.quantity_lots == base_lots
+                }) {
+                    Some(index) => plan.keep.push((side, resting.swap_remove(index))),
+                    None => plan.place.push((side, *spec)),
                 }
             }
+
+            plan.cancel
+                .extend(resting.into_iter().map(|order| (side, order)));
         }
-        Ok((open_bids, open_bids_prices, max_bid))
+
+        plan
     }
 
-    /// Processes asks information to fetch asks info.
+    /// Sends the cancels then the placements of `plan`, `MAX_CANCEL_ORDERS` cancels or
+    /// `MAX_PLACE_ORDERS` orders per transaction. Kept orders are not touched.
     ///
-    /// This function iteratively removes asks from the provided `Slab` until
-    /// it finds all asks.
+    /// Under `CancelPolicy::RequireReplacement` the cancels of each side are instead sent in
+    /// the transactions placing that side's new orders, so an order is only ever removed
+    /// atomically with its replacement.
     ///
     /// # Arguments
     ///
-    /// * `&self` - A reference to the `OBClient` struct.
-    /// * `asks` - A mutable reference to the `Slab` containing asks information.
+    /// * `plan` - The plan returned by `quote_diff`.
+    /// * `execute` - Whether to send the transactions or only build them.
     ///
     /// # Returns
     ///
-    /// A `Result` containing a tuple of `(open_asks, open_asks_prices, min_ask)` if successful,
-    /// or an error if processing asks fails.
-    pub fn process_asks(&self, asks: &mut RefMut<Slab>) -> Result<(Vec<u128>, Vec<f64>, u64)> {
-        let mut min_ask = 0;
-        let mut open_asks = Vec::new();
-        let mut open_asks_prices = Vec::new();
-        loop {
-            let node = asks.remove_min();
-            match node {
-                Some(node) => {
-                    let owner = node.owner();
-                    let bytes = u64_slice_to_pubkey(owner);
-                    let owner_address = Pubkey::from(bytes);
-
-                    let order_id = node.order_id();
-                    let price_raw = node.price().get();
-                    let ui_price = price_raw as f64 / 1e4;
+    /// One `OrderReturnType` per transaction, cancels first. Empty if the plan is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an order cannot be built or a transaction fails to send, or if the
+    /// cancel policy requires a replacement the plan does not provide.
+    pub async fn apply_quote_plan(
+        &self,
+        plan: &QuotePlan,
+        execute: bool,
+    ) -> Result<Vec<OrderReturnType>> {
+        if execute && !plan.is_empty() {
+            self.signer()?;
+        }
+        for side in [Side::Bid, Side::Ask] {
+            let cancelled = plan.cancel.iter().filter(|(s, _)| *s == side).count();
+            let placed = plan.place.iter().filter(|(s, _)| *s == side).count();
+            if placed > 0 {
+                self.check_order_count(side, cancelled, placed).await?;
+            }
+        }
+        if self.cancel_policy == CancelPolicy::RequireReplacement {
+            return self.apply_quote_plan_replacing(plan, execute).await;
+        }
 
-                    debug!("[*] Ask: {price_raw}");
+        let mut transactions = Vec::new();
+        let cancels: Vec<(Side, u128)> = plan
+            .cancel
+            .iter()
+            .map(|(side, order)| (*side, order.order_id))
+            .collect();
+        for chunk in cancels.chunks(MAX_CANCEL_ORDERS) {
+            let instructions = self.cancel_instructions(self.open_orders.oo_key, chunk)?;
+            if execute {
+                let (_, signature) = self.send_instructions(instructions).await?;
+                transactions.push(OrderReturnType::Signature(signature));
+            } else {
+                transactions.push(OrderReturnType::Instructions(instructions));
+            }
+        }
 
-                    if min_ask == 0 {
-                        min_ask = price_raw;
+        let mut orders = Vec::with_capacity(plan.place.len());
+        let mut instructions = Vec::with_capacity(plan.place.len());
+        for (side, spec) in &plan.place {
+            if let Some((instruction, order)) =
+                self.new_order_instruction(*side, spec.size_quote, spec.price)?
+            {
+                instructions.push(instruction);
+                orders.push(order);
+            }
+        }
+        for (chunk, chunk_orders) in instructions
+            .chunks(MAX_PLACE_ORDERS)
+            .zip(orders.chunks(MAX_PLACE_ORDERS))
+        {
+            if execute {
+                let (confirmed, signature) = self.send_instructions(chunk.to_vec()).await?;
+                if confirmed {
+                    for order in chunk_orders {
+                        self.order_state.insert(order, None, get_unix_secs());
                     }
+                }
+                transactions.push(OrderReturnType::Signature(signature));
+            } else {
+                transactions.push(OrderReturnType::Instructions(chunk.to_vec()));
+            }
+        }
 
-                    if owner_address == self.open_orders.oo_key {
-                        open_asks.push(order_id);
-                        open_asks_prices.push(ui_price);
-                    }
+        Ok(transactions)
+    }
+
+    /// Applies `plan` with every cancel in a transaction that also places an order on its side.
+    ///
+    /// The new orders of a side are spread evenly over as many transactions as its cancels
+    /// need, so each transaction holds at most `MAX_CANCEL_ORDERS` cancels and
+    /// `MAX_PLACE_ORDERS` placements.
+    async fn apply_quote_plan_replacing(
+        &self,
+        plan: &QuotePlan,
+        execute: bool,
+    ) -> Result<Vec<OrderReturnType>> {
+        let mut transactions = Vec::new();
+        for side in [Side::Bid, Side::Ask] {
+            let cancels: Vec<(Side, u128)> = plan
+                .cancel
+                .iter()
+                .filter(|(s, _)| *s == side)
+                .map(|(side, order)| (*side, order.order_id))
+                .collect();
+            let mut places = Vec::new();
+            for (_, spec) in plan.place.iter().filter(|(s, _)| *s == side) {
+                if let Some(place) =
+                    self.new_order_instruction(side, spec.size_quote, spec.price)?
+                {
+                    places.push(place);
                 }
-                None => {
-                    break;
+            }
+
+            let cancel_chunks = cancels.len().div_ceil(MAX_CANCEL_ORDERS);
+            if cancel_chunks > places.len() {
+                return Err(anyhow!(
+                    "Refusing to cancel {} {:?} orders with {} replacements, the cancel policy \
+                     requires a new order in every cancel transaction",
+                    cancels.len(),
+                    side,
+                    places.len()
+                ));
+            }
+            let count = cancel_chunks.max(places.len().div_ceil(MAX_PLACE_ORDERS));
+            let mut places = places.into_iter();
+            for i in 0..count {
+                let take = places.len().div_ceil(count - i);
+                let (place_instructions, orders): (Vec<_>, Vec<_>) =
+                    places.by_ref().take(take).unzip();
+                let mut instructions = match cancels.chunks(MAX_CANCEL_ORDERS).nth(i) {
+                    Some(chunk) => self.cancel_instructions(self.open_orders.oo_key, chunk)?,
+                    None => Vec::new(),
+                };
+                instructions.extend(place_instructions);
+
+                if execute {
+                    let (confirmed, signature) = self.send_instructions(instructions).await?;
+                    if confirmed {
+                        for order in &orders {
+                            self.order_state.insert(order, None, get_unix_secs());
+                        }
+                    }
+                    transactions.push(OrderReturnType::Signature(signature));
+                } else {
+                    transactions.push(OrderReturnType::Instructions(instructions));
                 }
             }
         }
-        Ok((open_asks, open_asks_prices, min_ask))
+
+        Ok(transactions)
     }
 
-    /// Loads the open bids from the market.
+    /// Cancels every resting order and re-reads the book until none remain.
     ///
-    /// # Returns
+    /// A confirmed cancel does not always leave the account flat: the RPC node may serve a
+    /// stale book, or an order may have been placed in the meantime. Each round reloads the
+    /// book and cancels whatever still rests, so the returned count is read from the book
+    /// rather than trusted from cancel confirmations.
     ///
-    /// The open bids stored in the market.
+    /// # Arguments
     ///
-    /// # Errors
+    /// * `max_rounds` - The maximum number of cancel rounds.
     ///
-    /// Returns an error if there is an issue with loading the bids.
+    /// # Returns
+    ///
+    /// The number of the owner's orders still resting after the last round, `0` once flat.
     ///
     /// # Examples
     ///
@@ -568,26 +7085,42 @@ impl OBClient {
     ///
     ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
     ///
-    ///     let result = ob_client.load_bids()?;
+    ///     let remaining = ob_client.ensure_flat(3).await?;
     ///
-    ///     println!("{:?}", result);
+    ///     println!("Orders left on the book: {}", remaining);
     ///
     ///     Ok(())
     /// }
     /// ```
-    pub fn load_bids(&mut self) -> Result<Vec<u128>, ProgramError> {
-        Ok(self.open_orders.open_bids.clone())
+    pub async fn ensure_flat(&mut self, max_rounds: usize) -> Result<usize> {
+        self.signer()?;
+        for round in 1..=max_rounds {
+            self.load_bids_asks_info().await?;
+            let resting = self.open_orders.open_bids.len() + self.open_orders.open_asks.len();
+            if resting == 0 {
+                return Ok(0);
+            }
+            info!(
+                "[*] Cancel round {}/{}: {} orders resting",
+                round, max_rounds, resting
+            );
+            self.cancel_orders_chunked(usize::MAX, MAX_CANCEL_ORDERS)
+                .await?;
+        }
+
+        self.load_bids_asks_info().await?;
+        Ok(self.open_orders.open_bids.len() + self.open_orders.open_asks.len())
     }
 
-    /// Loads the open asks from the market.
+    /// Cancels every open order of this client and settles its funds.
     ///
-    /// # Returns
-    ///
-    /// The open asks stored in the market.
+    /// The book is re-read first so orders placed since the last snapshot are cancelled too.
+    /// Cancels are sent `MAX_CANCEL_ORDERS` per transaction, see `cancel_orders_chunked`.
     ///
-    /// # Errors
+    /// # Returns
     ///
-    /// Returns an error if there is an issue with loading the asks.
+    /// The per-chunk cancel results and the settle signature. Use `FlattenResult::is_flat`
+    /// to check that every cancel was confirmed.
     ///
     /// # Examples
     ///
@@ -603,31 +7136,53 @@ impl OBClient {
     ///
     ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
     ///
-    ///     let result = ob_client.load_asks()?;
+    ///     let result = ob_client.flatten().await?;
     ///
-    ///     println!("{:?}", result);
+    ///     println!("flat: {}", result.is_flat());
     ///
     ///     Ok(())
     /// }
     /// ```
-    pub fn load_asks(&mut self) -> Result<Vec<u128>, ProgramError> {
-        Ok(self.open_orders.open_asks.clone())
+    pub async fn flatten(&mut self) -> Result<FlattenResult> {
+        self.signer()?;
+        self.load_bids_asks_info().await?;
+
+        let cancels = self
+            .cancel_orders_chunked(usize::MAX, MAX_CANCEL_ORDERS)
+            .await?
+            .chunks;
+        let settle_signature = match self.settle_balance(true).await? {
+            Some(OrderReturnType::Signature(signature)) if signature != Signature::default() => {
+                Some(signature)
+            }
+            _ => None,
+        };
+
+        Ok(FlattenResult {
+            cancels,
+            settle_signature,
+        })
     }
-    /// Places a limit order on the market.
+
+    /// Settles the balance for a user in the market.
+    ///
+    /// The v1 `SettleFunds` instruction takes no amount: it always moves every free base and
+    /// quote token of the open orders account to the wallet, so a partial settlement is not
+    /// possible, nor is leaving a reserve behind. Funds only come back into the account through
+    /// new orders, which use the free balance before pulling from the wallet, so funds held in
+    /// the wallet back orders just as well. With
+    /// `create_missing_atas` set, missing associated token accounts are created in the same
+    /// transaction.
     ///
     /// # Arguments
     ///
     /// * `&self` - A reference to the `OBClient` struct.
-    /// * `target_amount_quote` - The target amount in quote currency for the order.
-    /// * `side` - The side of the order (buy or sell).
-    /// * `best_offset_usdc` - The best offset in USDC for the order.
     /// * `execute` - A boolean indicating whether to execute the order immediately.
-    /// * `target_price` - The target price for the order.
     ///
     /// # Returns
     ///
     /// A `Result` containing the transaction signature if successful,
-    /// or an error if placing the limit order fails.
+    /// or an error if settling the balance fails.
     ///
     /// # Errors
     ///
@@ -636,7 +7191,6 @@ impl OBClient {
     /// # Examples
     ///
     /// ```rust
-    /// use openbook::matching::Side;
     /// use openbook::v1::orders::OrderReturnType;
     /// use openbook::commitment_config::CommitmentConfig;
     /// use openbook::v1::ob_client::OBClient;
@@ -649,25 +7203,15 @@ impl OBClient {
     ///
     ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
     ///
-    ///     let target_amount_quote = 5.0;
-    ///     let side = Side::Bid; // or Side::Ask
-    ///     let best_offset_usdc = 5.0;
-    ///     let execute = true;
-    ///     let target_price = 2.1;
-    ///
-    ///     if let Some(ord_ret_type) = ob_client.place_limit_order(
-    ///         target_amount_quote,
-    ///         side,
-    ///         best_offset_usdc,
-    ///         execute,
-    ///         target_price
-    ///     ).await?
+    ///     if let Some(ord_ret_type) = ob_client
+    ///         .settle_balance(true)
+    ///         .await?
     ///     {
     ///         match ord_ret_type {
     ///             OrderReturnType::Instructions(insts) => {
     ///                 println!("[*] Got Instructions: {:?}", insts);
     ///             }
-    ///             OrderReturnType::Signature(sign) => {
+    ///             OrderReturnType::Signature(sign) | OrderReturnType::Placed(sign, _) => {
     ///                 println!("[*] Transaction successful, signature: {:?}", sign);
     ///             }
     ///         }
@@ -676,119 +7220,261 @@ impl OBClient {
     ///     Ok(())
     /// }
     /// ```
-    pub async fn place_limit_order(
-        &self,
-        target_amount_quote: f64,
-        side: Side,
-        best_offset_usdc: f64,
-        execute: bool,
-        target_price: f64,
-    ) -> Result<Option<OrderReturnType>, Error> {
-        // coin: base
-        // pc: quote
-        let base_d_factor = 10u32.pow(self.market_info.coin_decimals as u32) as f64;
-        let quote_d_factor = 10u32.pow(self.market_info.pc_decimals as u32) as f64;
-        let base_lot_factor = self.market_info.coin_lot_size as f64;
-        let quote_lot_factor = self.market_info.pc_lot_size as f64;
+    pub async fn settle_balance(&self, execute: bool) -> Result<Option<OrderReturnType>, Error> {
+        let ix = self.settle_instruction(&self.base_ata, &self.quote_ata)?;
 
-        let price_factor = quote_d_factor * base_lot_factor / base_d_factor / quote_lot_factor;
+        let mut instructions = if self.create_missing_atas {
+            self.missing_ata_instructions().await?
+        } else {
+            Vec::new()
+        };
+        instructions.push(ix);
 
-        let (input_ata, price) = match side {
-            Side::Bid => {
-                let mut price = self.open_orders.max_bid as f64 / price_factor - best_offset_usdc;
-                if execute {
-                    price = target_price;
-                }
+        if !execute {
+            return Ok(Some(OrderReturnType::Instructions(instructions)));
+        }
+
+        let (_, signature) = self.send_instructions(instructions).await?;
+
+        Ok(Some(OrderReturnType::Signature(signature)))
+    }
 
-                (&self.quote_ata, price)
+    /// Settles the free balances of the open orders account to the associated token accounts
+    /// of another wallet, e.g. a cold wallet.
+    ///
+    /// The base and quote accounts of `wallet` are derived under the configured token and
+    /// associated token account programs. Those that do not exist yet are created in the same
+    /// transaction, idempotently and paid by the owner; those that do must hold the market's
+    /// mint for `wallet`. Like `settle_balance`, everything free is moved.
+    ///
+    /// # Arguments
+    ///
+    /// * `wallet` - The wallet receiving the funds. It must be a system account, or not exist
+    ///   yet and be a regular (on curve) address.
+    /// * `execute` - Whether to send the transaction, or only build its instructions.
+    ///
+    /// # Returns
+    ///
+    /// The instructions, or the signature of the settle transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `wallet` is not a system account, if one of its derived accounts
+    /// exists but is not its token account for the market's mint, or if the transaction fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///     let cold_wallet = "BqpVsNuznx4tJPNC4RNd2FmoFzMu4wPbQMMwEHZiyV6B".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let settle = ob_client.settle_to_wallet(cold_wallet, true).await?;
+    ///     println!("{:?}", settle);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn settle_to_wallet(&self, wallet: Pubkey, execute: bool) -> Result<OrderReturnType> {
+        let wallet_account = self
+            .rpc_client
+            .inner()
+            .get_account_with_commitment(&wallet, self.rpc_client.inner().commitment())
+            .await?
+            .value;
+        match wallet_account {
+            Some(account) if account.owner != system_program::id() => {
+                return Err(anyhow!(
+                    "Wallet {wallet} is owned by {}, not the system program",
+                    account.owner
+                ));
             }
-            Side::Ask => {
-                let mut price = self.open_orders.min_ask as f64 / price_factor + best_offset_usdc;
-                if execute {
-                    price = target_price;
-                }
+            None if !wallet.is_on_curve() => {
+                return Err(anyhow!(
+                    "Wallet {wallet} does not exist and is not a regular address"
+                ));
+            }
+            _ => {}
+        }
 
-                (&self.base_ata, price)
+        let mints = [self.market_info.base_mint, self.market_info.quote_mint];
+        let accounts = mints.map(|mint| self.wallet_token_address(&wallet, &mint));
+        let fetched = self.rpc_client.fetch_multiple_accounts(&accounts).await?;
+
+        let mut instructions = Vec::new();
+        for ((account, mint), fetched) in accounts.iter().zip(&mints).zip(fetched) {
+            match fetched {
+                Some(fetched) => {
+                    let holder = |range: std::ops::Range<usize>| {
+                        fetched
+                            .data
+                            .get(range)
+                            .and_then(|bytes| Pubkey::try_from(bytes).ok())
+                    };
+                    if fetched.owner != self.token_program
+                        || holder(0..32) != Some(*mint)
+                        || holder(32..64) != Some(wallet)
+                    {
+                        return Err(anyhow!(
+                            "Account {account} is not the token account of {wallet} for mint {mint}"
+                        ));
+                    }
+                }
+                None => {
+                    debug!("[*] Creating token account {} of {}", account, wallet);
+                    instructions.push(self.create_ata_instruction(&wallet, account, mint));
+                }
             }
-        };
+        }
+        instructions.push(self.settle_instruction(&accounts[0], &accounts[1])?);
 
-        let limit_price_lots = (price * price_factor) as u64;
-        let target_amount_base = target_amount_quote / price;
+        if !execute {
+            return Ok(OrderReturnType::Instructions(instructions));
+        }
 
-        let target_base_lots = (target_amount_base * base_d_factor / base_lot_factor) as u64;
-        let target_quote_lots_w_fee =
-            (target_base_lots as f64 * quote_lot_factor * limit_price_lots as f64) as u64;
+        let (_, signature) = self.send_instructions(instructions).await?;
 
-        debug!("[*] Using limit price lots: {:?}", limit_price_lots);
-        debug!("[*] Using target base lots: {:?}", target_base_lots);
+        Ok(OrderReturnType::Signature(signature))
+    }
 
-        if target_base_lots == 0 {
-            debug!(
-                "[*] Got zero base lots, and quote: {:?}",
-                target_amount_quote
-            );
-            return Ok(None);
+    /// Builds the `settle_funds` instruction of the open orders account, paying out to the
+    /// given base and quote token accounts.
+    fn settle_instruction(
+        &self,
+        base_wallet: &Pubkey,
+        quote_wallet: &Pubkey,
+    ) -> Result<Instruction> {
+        Ok(openbook_dex::instruction::settle_funds(
+            &self.market_info.program_id,
+            &self.market_info.market_address,
+            &self.token_program,
+            &self.open_orders.oo_key,
+            &self.owner_key,
+            &self.market_info.coin_vault,
+            base_wallet,
+            &self.market_info.pc_vault,
+            quote_wallet,
+            None,
+            &self.vault_signer(),
+        )?)
+    }
+
+    /// Creates a new transaction to match orders in the market.
+    ///
+    /// The program matches new orders as they are placed, so a match only has work to do while
+    /// requests are pending in the request queue. With `skip_empty_match` set, the default,
+    /// the queue is read first and no transaction is sent when it is empty or the market has
+    /// no request queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The maximum number of orders to match.
+    ///
+    /// # Returns
+    ///
+    /// Whether the match transaction was confirmed, and its last signature. An unconfirmed
+    /// transaction is rebuilt with a fresh blockhash and resent up to `match_retries` times.
+    /// `None` if the match was skipped because there was nothing to match.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request queue cannot be read, if there is an issue with
+    /// transaction creation, or if the last attempt failed to send.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::v1::orders::OrderReturnType;
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     match ob_client.match_orders_transaction(100).await? {
+    ///         Some((confirmed, signature)) => println!("{signature}: confirmed {confirmed}"),
+    ///         None => println!("Nothing to match"),
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn match_orders_transaction(&self, limit: u16) -> Result<Option<(bool, Signature)>> {
+        if self.skip_empty_match {
+            match self.request_queue_status().await?.pending() {
+                Some(0) | None => {
+                    debug!("[*] No pending requests, nothing to match");
+                    return Ok(None);
+                }
+                Some(pending) => debug!("[*] {} pending requests to match", pending),
+            }
         }
 
-        let limit_price = NonZeroU64::new(limit_price_lots).unwrap();
-        let max_coin_qty = NonZeroU64::new(target_base_lots).unwrap();
-        let max_native_pc_qty_including_fees = NonZeroU64::new(target_quote_lots_w_fee).unwrap();
-
-        let place_order_ix = openbook_dex::instruction::new_order(
+        let ix = openbook_dex::instruction::match_orders(
+            &self.market_info.program_id,
             &self.market_info.market_address,
-            &self.open_orders.oo_key,
             &self.market_info.request_queue,
-            &self.market_info.event_queue,
             &self.market_info.bids_address,
             &self.market_info.asks_address,
-            input_ata,
-            &self.owner.pubkey(),
+            &self.market_info.event_queue,
             &self.market_info.coin_vault,
             &self.market_info.pc_vault,
-            &SPL_TOKEN_ID.parse()?,
-            &rent::ID,
-            None,
-            &self.market_info.program_id,
-            side,
-            limit_price,
-            max_coin_qty,
-            OrderType::PostOnly,
-            random::<u64>(),
-            SelfTradeBehavior::AbortTransaction,
-            u16::MAX,
-            max_native_pc_qty_including_fees,
-            (get_unix_secs() + 30) as i64,
-        )?;
+            limit,
+        )
+        .unwrap();
 
-        let instructions = vec![place_order_ix];
+        let instructions = vec![ix];
 
-        if !execute {
-            return Ok(Some(OrderReturnType::Instructions(instructions)));
+        let mut attempt = 0;
+        loop {
+            let result = self.send_instructions(instructions.clone()).await;
+
+            match result {
+                Ok((true, signature)) => return Ok(Some((true, signature))),
+                Ok(ret) if attempt >= self.match_retries => return Ok(Some(ret)),
+                Err(err) if attempt >= self.match_retries => return Err(err),
+                Ok((_, signature)) => {
+                    warn!(
+                        "[*] Match transaction {} not confirmed, retrying",
+                        signature
+                    );
+                }
+                Err(err) => warn!("[*] Match transaction failed: {}, retrying", err),
+            }
+            attempt += 1;
         }
-
-        let (_, signature) = self
-            .rpc_client
-            .send_and_confirm((*self.owner).insecure_clone(), instructions)
-            .await?;
-
-        Ok(Some(OrderReturnType::Signature(signature)))
     }
 
-    /// Cancels up to `MAX_CANCEL_ORDERS` limit orders in the market.
+    /// Executes a combination of canceling all limit orders, settling balance, and placing new bid and ask orders.
     ///
     /// # Arguments
     ///
-    /// * `&self` - A reference to the `OBClient` struct.
-    /// * `execute` - A boolean indicating whether to execute the order immediately.
+    /// * `target_size_usdc_ask` - The target size in USDC for the ask order.
+    /// * `target_size_usdc_bid` - The target size in USDC for the bid order.
+    /// * `bid_price_jlp_usdc` - The bid price in JLP/USDC.
+    /// * `ask_price_jlp_usdc` - The ask price in JLP/USDC.
     ///
     /// # Returns
     ///
-    /// A `Result` containing the transaction signature if successful,
-    /// or an error if canceling all orders fails.
+    /// A `Result` indicating success or failure.
     ///
     /// # Errors
     ///
-    /// This function may return an error if there is an issue with creating or sending the transaction.
+    /// When `max_book_move_bps` is set, returns an error without sending anything if the book
+    /// mid moved more than that since the last snapshot the prices were computed from.
     ///
     /// # Examples
     ///
@@ -805,150 +7491,196 @@ impl OBClient {
     ///
     ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
     ///
-    ///     if let Some(ord_ret_type) = ob_client
-    ///         .cancel_orders(true)
-    ///         .await?
-    ///     {
-    ///         match ord_ret_type {
-    ///             OrderReturnType::Instructions(insts) => {
-    ///                 println!("[*] Got Instructions: {:?}", insts);
-    ///             }
-    ///             OrderReturnType::Signature(sign) => {
-    ///                 println!("[*] Transaction successful, signature: {:?}", sign);
-    ///             }
-    ///         }
-    ///     }
+    ///     let target_size_usdc_ask = 0.5;
+    ///     let target_size_usdc_bid = 1.0;
+    ///     let bid_price_jlp_usdc = 1.5;
+    ///     let ask_price_jlp_usdc = 2.5;
+    ///
+    ///     let result = ob_client.cancel_settle_place(target_size_usdc_ask, target_size_usdc_bid, bid_price_jlp_usdc, ask_price_jlp_usdc).await?;
+    ///
+    ///     println!("{:?}", result);
     ///
     ///     Ok(())
     /// }
     /// ```
-    pub async fn cancel_orders(&self, execute: bool) -> Result<Option<OrderReturnType>, Error> {
-        if execute {
-            let mut processed = 0;
-            let mut last_signature = None;
-
-            for oid in &self.open_orders.open_bids {
-                if processed >= MAX_CANCEL_ORDERS {
-                    break;
-                }
+    pub async fn cancel_settle_place(
+        &mut self,
+        target_size_usdc_ask: f64,
+        target_size_usdc_bid: f64,
+        bid_price_jlp_usdc: f64,
+        ask_price_jlp_usdc: f64,
+    ) -> Result<(bool, Signature)> {
+        if let Some(max_move_bps) = self.max_book_move_bps {
+            self.ensure_book_unmoved(max_move_bps).await?;
+        }
 
-                let ix = openbook_dex::instruction::cancel_order(
-                    &self.market_info.program_id,
-                    &self.market_info.market_address,
-                    &self.market_info.bids_address,
-                    &self.market_info.asks_address,
-                    &self.open_orders.oo_key,
-                    &self.owner.pubkey(),
-                    &self.market_info.event_queue,
-                    Side::Bid,
-                    *oid,
-                )?;
+        let mut instructions = Vec::new();
 
-                let (_, signature) = self
-                    .rpc_client
-                    .send_and_confirm((*self.owner).insecure_clone(), vec![ix])
-                    .await?;
+        // Set the fee instruction, the compute unit limit is added when sending
+        let fee_ix =
+            ComputeBudgetInstruction::set_compute_unit_price(self.compute_unit_price().await?);
+        instructions.push(fee_ix);
 
-                last_signature = Some(signature);
-                processed += 1;
+        // Cancel all limit orders
+        if let Some(ord_ret_type) = self.cancel_orders(false).await? {
+            match ord_ret_type {
+                OrderReturnType::Instructions(insts) => {
+                    instructions.extend(insts);
+                }
+                OrderReturnType::Signature(_) | OrderReturnType::Placed(..) => {}
             }
+        }
 
-            for oid in &self.open_orders.open_asks {
-                if processed >= MAX_CANCEL_ORDERS {
-                    break;
+        // Settle balance
+        if let Some(ord_ret_type) = self.settle_balance(false).await? {
+            match ord_ret_type {
+                OrderReturnType::Instructions(insts) => {
+                    instructions.extend(insts);
                 }
-
-                let ix = openbook_dex::instruction::cancel_order(
-                    &self.market_info.program_id,
-                    &self.market_info.market_address,
-                    &self.market_info.bids_address,
-                    &self.market_info.asks_address,
-                    &self.open_orders.oo_key,
-                    &self.owner.pubkey(),
-                    &self.market_info.event_queue,
-                    Side::Ask,
-                    *oid,
-                )?;
-
-                let (_, signature) = self
-                    .rpc_client
-                    .send_and_confirm((*self.owner).insecure_clone(), vec![ix])
-                    .await?;
-
-                last_signature = Some(signature);
-                processed += 1;
+                OrderReturnType::Signature(_) | OrderReturnType::Placed(..) => {}
             }
-
-            return Ok(last_signature.map(OrderReturnType::Signature));
         }
 
-        let mut ixs = Vec::new();
-
-        for oid in &self.open_orders.open_bids {
-            if ixs.len() >= MAX_CANCEL_ORDERS {
-                break;
-            }
+        // Cancels built above free their slots before the new orders count
+        let (bids_cancelled, asks_cancelled) = self.cancelled_by_cancel_orders();
+        self.check_order_count(Side::Bid, bids_cancelled, 1).await?;
+        self.check_order_count(Side::Ask, asks_cancelled, 1).await?;
 
-            let ix = openbook_dex::instruction::cancel_order(
-                &self.market_info.program_id,
-                &self.market_info.market_address,
-                &self.market_info.bids_address,
-                &self.market_info.asks_address,
-                &self.open_orders.oo_key,
-                &self.owner.pubkey(),
-                &self.market_info.event_queue,
+        // Place bid order
+        if let Some(ord_ret_type) = self
+            .place_limit_order(
+                target_size_usdc_bid,
                 Side::Bid,
-                *oid,
-            )?;
-            ixs.push(ix);
-        }
-
-        for oid in &self.open_orders.open_asks {
-            if ixs.len() >= MAX_CANCEL_ORDERS {
-                break;
+                0.,
+                false,
+                bid_price_jlp_usdc,
+            )
+            .await?
+        {
+            match ord_ret_type {
+                OrderReturnType::Instructions(insts) => {
+                    instructions.extend(insts);
+                }
+                OrderReturnType::Signature(_) | OrderReturnType::Placed(..) => {}
             }
-
-            let ix = openbook_dex::instruction::cancel_order(
-                &self.market_info.program_id,
-                &self.market_info.market_address,
-                &self.market_info.bids_address,
-                &self.market_info.asks_address,
-                &self.open_orders.oo_key,
-                &self.owner.pubkey(),
-                &self.market_info.event_queue,
-                Side::Ask,
-                *oid,
-            )?;
-            ixs.push(ix);
         }
 
-        if ixs.is_empty() {
-            return Ok(None);
+        // Place ask order
+        if let Some(ord_ret_type) = self
+            .place_limit_order(
+                target_size_usdc_ask,
+                Side::Ask,
+                0.,
+                false,
+                ask_price_jlp_usdc,
+            )
+            .await?
+        {
+            match ord_ret_type {
+                OrderReturnType::Instructions(insts) => {
+                    instructions.extend(insts);
+                }
+                OrderReturnType::Signature(_) | OrderReturnType::Placed(..) => {}
+            }
         }
 
-        Ok(Some(OrderReturnType::Instructions(ixs)))
+        self.check_cancels_replaced(&instructions, bids_cancelled, asks_cancelled)?;
+        self.send_instructions(instructions).await
     }
 
-    /// Settles the balance for a user in the market.
+    /// Quotes both sides around `mid`, shifted by the current inventory, in one cancel/settle/place cycle.
+    ///
+    /// Both quotes are moved by `skew_factor * inventory_base` basis points: down when long base
+    /// so the ask fills first, up when short so the bid fills first. The spread between them stays
+    /// `2 * half_spread_bps`.
     ///
     /// # Arguments
     ///
-    /// * `&self` - A reference to the `OBClient` struct.
-    /// * `execute` - A boolean indicating whether to execute the order immediately.
+    /// * `mid` - The reference mid price.
+    /// * `half_spread_bps` - Distance of each quote from the skewed mid, in bps.
+    /// * `size_quote` - The size of each order in quote currency.
+    /// * `inventory_base` - Current base inventory relative to the target (positive when long).
+    /// * `skew_factor` - Shift applied per unit of base inventory, in bps.
     ///
     /// # Returns
     ///
-    /// A `Result` containing the transaction signature if successful,
-    /// or an error if settling the balance fails.
+    /// Whether the transaction holding both orders was confirmed, and its signature.
     ///
     /// # Errors
     ///
-    /// This function may return an error if there is an issue with creating or sending the transaction.
+    /// Returns an error if `mid` is not positive, `half_spread_bps` is negative, or the skew
+    /// pushes a quote to or below zero.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let result = ob_client.place_skewed(2.0, 20.0, 1.0, 0.5, 10.0).await?;
+    ///
+    ///     println!("{:?}", result);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn place_skewed(
+        &mut self,
+        mid: f64,
+        half_spread_bps: f64,
+        size_quote: f64,
+        inventory_base: f64,
+        skew_factor: f64,
+    ) -> Result<(bool, Signature)> {
+        if mid.is_nan() || mid <= 0.0 {
+            return Err(anyhow!("Mid price must be positive, got {mid}"));
+        }
+        if half_spread_bps.is_nan() || half_spread_bps < 0.0 {
+            return Err(anyhow!(
+                "Half spread must not be negative, got {half_spread_bps}"
+            ));
+        }
+
+        let skewed_mid = mid * (1.0 - skew_factor * inventory_base / 10_000.0);
+        let bid_price = skewed_mid * (1.0 - half_spread_bps / 10_000.0);
+        let ask_price = skewed_mid * (1.0 + half_spread_bps / 10_000.0);
+        if bid_price.is_nan() || bid_price <= 0.0 {
+            return Err(anyhow!(
+                "Skewed bid price must be positive, got {bid_price} (mid {mid}, inventory {inventory_base})"
+            ));
+        }
+
+        debug!(
+            "[*] Skewed quotes => mid: {}, skewed mid: {}, bid: {}, ask: {}",
+            mid, skewed_mid, bid_price, ask_price
+        );
+
+        self.cancel_settle_place(size_quote, size_quote, bid_price, ask_price)
+            .await
+    }
+
+    /// Sets an external reference price (e.g. from an oracle) that orders are checked against.
+    ///
+    /// Once set, every limit and market order priced more than `max_ref_deviation_bps` away from
+    /// it is refused before anything is sent, a guard independent of the book against a bug in
+    /// the caller's pricing. The price is not refreshed by the client: a bot should set it again
+    /// on every quoting cycle.
+    ///
+    /// # Arguments
+    ///
+    /// * `price` - The reference price, in quote UI units per base unit.
     ///
     /// # Examples
     ///
     /// ```rust
-    /// use openbook::v1::orders::OrderReturnType;
     /// use openbook::commitment_config::CommitmentConfig;
     /// use openbook::v1::ob_client::OBClient;
     ///
@@ -960,70 +7692,71 @@ impl OBClient {
     ///
     ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
     ///
-    ///     if let Some(ord_ret_type) = ob_client
-    ///         .settle_balance(true)
-    ///         .await?
-    ///     {
-    ///         match ord_ret_type {
-    ///             OrderReturnType::Instructions(insts) => {
-    ///                 println!("[*] Got Instructions: {:?}", insts);
-    ///             }
-    ///             OrderReturnType::Signature(sign) => {
-    ///                 println!("[*] Transaction successful, signature: {:?}", sign);
-    ///             }
-    ///         }
-    ///     }
+    ///     ob_client.set_reference_price(2.15);
+    ///     ob_client.max_ref_deviation_bps = 50.0;
     ///
     ///     Ok(())
     /// }
     /// ```
-    pub async fn settle_balance(&self, execute: bool) -> Result<Option<OrderReturnType>, Error> {
-        let ix = openbook_dex::instruction::settle_funds(
-            &self.market_info.program_id,
-            &self.market_info.market_address,
-            &SPL_TOKEN_ID.parse()?,
-            &self.open_orders.oo_key,
-            &self.owner.pubkey(),
-            &self.market_info.coin_vault,
-            &self.base_ata,
-            &self.market_info.pc_vault,
-            &self.quote_ata,
-            None,
-            &self.market_info.vault_signer_key,
-        )?;
+    pub fn set_reference_price(&mut self, price: f64) {
+        self.reference_price = Some(price);
+    }
 
-        let instructions = vec![ix];
+    /// Errors if `price` deviates from the reference price by more than `max_ref_deviation_bps`.
+    fn check_reference_price(&self, price: f64) -> Result<()> {
+        let Some(reference) = self.reference_price else {
+            return Ok(());
+        };
 
-        if !execute {
-            return Ok(Some(OrderReturnType::Instructions(instructions)));
+        let deviation_bps = (price - reference).abs() / reference * 10_000.0;
+        if deviation_bps.is_nan() || deviation_bps > self.max_ref_deviation_bps {
+            return Err(anyhow!(
+                "Order price {price} is {deviation_bps:.2} bps away from the reference price {reference}, max is {}",
+                self.max_ref_deviation_bps
+            ));
         }
 
-        let (_, signature) = self
-            .rpc_client
-            .send_and_confirm((*self.owner).insecure_clone(), instructions)
-            .await?;
-
-        Ok(Some(OrderReturnType::Signature(signature)))
+        Ok(())
     }
 
-    /// Creates a new transaction to match orders in the market.
+    /// Refuses to place limit orders that would leave more than `n` resting on one side.
     ///
-    /// # Arguments
+    /// The count is read from the open orders account before each placement, so a loop that
+    /// keeps placing without its orders being cancelled or filled is stopped once the cap is
+    /// reached, even if the book is never reloaded. Orders
+    /// cancelled in the same transaction, as by the cancel / settle / place combos, or planned
+    /// for cancellation by [`OBClient::apply_quote_plan`], no longer count. Market orders never
+    /// rest and are not limited.
     ///
-    /// * `limit` - The maximum number of orders to match.
+    /// # Examples
     ///
-    /// # Returns
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
     ///
-    /// A transaction for matching orders.
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
     ///
-    /// # Errors
+    ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
     ///
-    /// Returns an error if there is an issue with transaction creation or sending.
+    ///     ob_client.set_max_orders_per_side(5);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_max_orders_per_side(&mut self, n: usize) {
+        self.max_orders_per_side = Some(n);
+    }
+
+    /// Sets whether `match_orders_transaction` reads the request queue first and skips the
+    /// transaction when nothing is pending, saving its fee. On by default.
     ///
     /// # Examples
     ///
     /// ```rust
-    /// use openbook::v1::orders::OrderReturnType;
     /// use openbook::commitment_config::CommitmentConfig;
     /// use openbook::v1::ob_client::OBClient;
     ///
@@ -1035,51 +7768,24 @@ impl OBClient {
     ///
     ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
     ///
-    ///     let result = ob_client.match_orders_transaction(100).await?;
-    ///
-    ///     println!("{:?}", result);
+    ///     ob_client.set_skip_empty_match(false);
     ///
     ///     Ok(())
     /// }
     /// ```
-    pub async fn match_orders_transaction(&self, limit: u16) -> Result<(bool, Signature)> {
-        let ix = openbook_dex::instruction::match_orders(
-            &self.market_info.program_id,
-            &self.market_info.market_address,
-            &self.market_info.request_queue,
-            &self.market_info.bids_address,
-            &self.market_info.asks_address,
-            &self.market_info.event_queue,
-            &self.market_info.coin_vault,
-            &self.market_info.pc_vault,
-            limit,
-        )
-        .unwrap();
-
-        let instructions = vec![ix];
-
-        self.rpc_client
-            .send_and_confirm((*self.owner).insecure_clone(), instructions)
-            .await
+    pub fn set_skip_empty_match(&mut self, skip: bool) {
+        self.skip_empty_match = skip;
     }
 
-    /// Executes a combination of canceling all limit orders, settling balance, and placing new bid and ask orders.
-    ///
-    /// # Arguments
-    ///
-    /// * `target_size_usdc_ask` - The target size in USDC for the ask order.
-    /// * `target_size_usdc_bid` - The target size in USDC for the bid order.
-    /// * `bid_price_jlp_usdc` - The bid price in JLP/USDC.
-    /// * `ask_price_jlp_usdc` - The ask price in JLP/USDC.
-    ///
-    /// # Returns
+    /// Rounds orders below the market's minimum size up to one base lot instead of refusing them.
     ///
-    /// A `Result` indicating success or failure.
+    /// By default a limit or market order whose size rounds to zero base lots fails with the
+    /// minimum size in the error, see [`OBClient::order_base_lots`]. Rounding up places a
+    /// slightly larger order than asked.
     ///
     /// # Examples
     ///
     /// ```rust
-    /// use openbook::v1::orders::OrderReturnType;
     /// use openbook::commitment_config::CommitmentConfig;
     /// use openbook::v1::ob_client::OBClient;
     ///
@@ -1091,107 +7797,124 @@ impl OBClient {
     ///
     ///     let mut ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
     ///
-    ///     let target_size_usdc_ask = 0.5;
-    ///     let target_size_usdc_bid = 1.0;
-    ///     let bid_price_jlp_usdc = 1.5;
-    ///     let ask_price_jlp_usdc = 2.5;
-    ///
-    ///     let result = ob_client.cancel_settle_place(target_size_usdc_ask, target_size_usdc_bid, bid_price_jlp_usdc, ask_price_jlp_usdc).await?;
-    ///
-    ///     println!("{:?}", result);
+    ///     ob_client.set_round_up_to_min_size(true);
     ///
     ///     Ok(())
     /// }
     /// ```
-    pub async fn cancel_settle_place(
-        &mut self,
-        target_size_usdc_ask: f64,
-        target_size_usdc_bid: f64,
-        bid_price_jlp_usdc: f64,
-        ask_price_jlp_usdc: f64,
-    ) -> Result<(bool, Signature)> {
-        let mut instructions = Vec::new();
+    pub fn set_round_up_to_min_size(&mut self, round_up: bool) {
+        self.round_up_to_min_size = round_up;
+    }
 
-        // Fetch recent prioritization fees
-        let r = self
+    /// Errors if `new` orders on `side` would leave more than `max_orders_per_side` resting once
+    /// `cancelled` of the resting ones are gone.
+    ///
+    /// The resting orders are counted on the book side as it is now, not from the last book
+    /// load, which placements do not refresh. Slots of the open orders account still held by
+    /// filled or cancelled orders whose events are not consumed yet are not counted.
+    async fn check_order_count(&self, side: Side, cancelled: usize, new: usize) -> Result<()> {
+        let Some(max) = self.max_orders_per_side else {
+            return Ok(());
+        };
+        let book_side = match side {
+            Side::Bid => self.market_info.bids_address,
+            Side::Ask => self.market_info.asks_address,
+        };
+        let mut accounts = self
             .rpc_client
-            .inner()
-            .get_recent_prioritization_fees(&[])
-            .await?;
-        let mut max_fee = 1;
-        for f in r {
-            if f.prioritization_fee > max_fee {
-                max_fee = f.prioritization_fee;
-            }
+            .fetch_multiple_accounts(&[self.market_info.market_address, book_side])
+            .await?
+            .into_iter();
+        let mut next = |name: &str| {
+            accounts
+                .next()
+                .flatten()
+                .ok_or_else(|| anyhow!("The {name} account of the market is missing"))
+        };
+        let market_account = next("market")?;
+        let book_account = next("book side")?;
+        let resting = self
+            .decode_book_side(&market_account, side, &book_account)?
+            .iter()
+            .filter(|order| order.owner == self.open_orders.oo_key)
+            .count()
+            .saturating_sub(cancelled);
+        if resting + new > max {
+            return Err(anyhow!(
+                "Refusing to place {new} {side:?} order(s): {resting} already resting on that side, max is {max}"
+            ));
         }
+        Ok(())
+    }
 
-        // Set compute budget and fee instructions
-        let budget_ix = ComputeBudgetInstruction::set_compute_unit_limit(1_000_000);
-        let fee_ix = ComputeBudgetInstruction::set_compute_unit_price(max_fee);
-        instructions.push(budget_ix);
-        instructions.push(fee_ix);
-
-        // Cancel all limit orders
-        if let Some(ord_ret_type) = self.cancel_orders(false).await? {
-            match ord_ret_type {
-                OrderReturnType::Instructions(insts) => {
-                    instructions.extend(insts);
-                }
-                OrderReturnType::Signature(_) => {}
-            }
+    /// Errors under `CancelPolicy::RequireReplacement` if `instructions` cancel orders on a side
+    /// without placing a new order on it.
+    fn check_cancels_replaced(
+        &self,
+        instructions: &[Instruction],
+        bids_cancelled: usize,
+        asks_cancelled: usize,
+    ) -> Result<()> {
+        if self.cancel_policy != CancelPolicy::RequireReplacement {
+            return Ok(());
         }
-
-        // Settle balance
-        if let Some(ord_ret_type) = self.settle_balance(false).await? {
-            match ord_ret_type {
-                OrderReturnType::Instructions(insts) => {
-                    instructions.extend(insts);
-                }
-                OrderReturnType::Signature(_) => {}
+        for (side, cancelled) in [(Side::Bid, bids_cancelled), (Side::Ask, asks_cancelled)] {
+            let replaced = instructions.iter().any(|ix| {
+                ix.program_id == self.market_info.program_id
+                    && matches!(
+                        MarketInstruction::unpack(&ix.data),
+                        Some(MarketInstruction::NewOrderV3(order)) if order.side == side
+                    )
+            });
+            if cancelled > 0 && !replaced {
+                return Err(anyhow!(
+                    "Refusing to cancel {} {:?} orders without a new {:?} order in the same \
+                     transaction, the cancel policy requires a replacement",
+                    cancelled,
+                    side,
+                    side
+                ));
             }
         }
+        Ok(())
+    }
 
-        // Place bid order
-        if let Some(ord_ret_type) = self
-            .place_limit_order(
-                target_size_usdc_bid,
-                Side::Bid,
-                0.,
-                false,
-                bid_price_jlp_usdc,
-            )
-            .await?
-        {
-            match ord_ret_type {
-                OrderReturnType::Instructions(insts) => {
-                    instructions.extend(insts);
-                }
-                OrderReturnType::Signature(_) => {}
-            }
-        }
+    /// Number of bids and asks `cancel_orders` cancels, at most `MAX_CANCEL_ORDERS` bids first.
+    fn cancelled_by_cancel_orders(&self) -> (usize, usize) {
+        let bids = self.open_orders.open_bids.len().min(MAX_CANCEL_ORDERS);
+        let asks = self
+            .open_orders
+            .open_asks
+            .len()
+            .min(MAX_CANCEL_ORDERS - bids);
+        (bids, asks)
+    }
 
-        // Place ask order
-        if let Some(ord_ret_type) = self
-            .place_limit_order(
-                target_size_usdc_ask,
-                Side::Ask,
-                0.,
-                false,
-                ask_price_jlp_usdc,
-            )
-            .await?
-        {
-            match ord_ret_type {
-                OrderReturnType::Instructions(insts) => {
-                    instructions.extend(insts);
-                }
-                OrderReturnType::Signature(_) => {}
-            }
+    /// Re-reads the book and errors if its mid moved more than `max_move_bps` since the snapshot.
+    async fn ensure_book_unmoved(&mut self, max_move_bps: f64) -> Result<()> {
+        let snapshot_mid = self
+            .order_book
+            .mid()
+            .ok_or_else(|| anyhow!("No book snapshot to compare against"))?;
+
+        self.load_bids_asks_info().await?;
+        let current_mid = self
+            .order_book
+            .mid()
+            .ok_or_else(|| anyhow!("Book has an empty side, refusing to requote"))?;
+
+        let moved_bps = (current_mid - snapshot_mid).abs() / snapshot_mid * 10_000.0;
+        debug!(
+            "[*] Book mid moved {:.2} bps ({} -> {})",
+            moved_bps, snapshot_mid, current_mid
+        );
+        if moved_bps > max_move_bps {
+            return Err(anyhow!(
+                "Book mid moved {moved_bps:.2} bps since the prices were computed ({snapshot_mid} -> {current_mid}), max is {max_move_bps}"
+            ));
         }
 
-        self.rpc_client
-            .send_and_confirm((*self.owner).insecure_clone(), instructions)
-            .await
+        Ok(())
     }
 
     /// Executes a combination of canceling all limit orders, settling balance, and placing a bid order.
@@ -1234,23 +7957,9 @@ impl OBClient {
     ) -> Result<(bool, Signature)> {
         let mut instructions = Vec::new();
 
-        // Fetch recent prioritization fees
-        let r = self
-            .rpc_client
-            .inner()
-            .get_recent_prioritization_fees(&[])
-            .await?;
-        let mut max_fee = 1;
-        for f in r {
-            if f.prioritization_fee > max_fee {
-                max_fee = f.prioritization_fee;
-            }
-        }
-
-        // Set compute budget and fee instructions
-        let budget_ix = ComputeBudgetInstruction::set_compute_unit_limit(800_000);
-        let fee_ix = ComputeBudgetInstruction::set_compute_unit_price(max_fee);
-        instructions.push(budget_ix);
+        // Set the fee instruction, the compute unit limit is added when sending
+        let fee_ix =
+            ComputeBudgetInstruction::set_compute_unit_price(self.compute_unit_price().await?);
         instructions.push(fee_ix);
 
         // Cancel all limit orders
@@ -1259,7 +7968,7 @@ impl OBClient {
                 OrderReturnType::Instructions(insts) => {
                     instructions.extend(insts);
                 }
-                OrderReturnType::Signature(_) => {}
+                OrderReturnType::Signature(_) | OrderReturnType::Placed(..) => {}
             }
         }
 
@@ -1269,10 +7978,13 @@ impl OBClient {
                 OrderReturnType::Instructions(insts) => {
                     instructions.extend(insts);
                 }
-                OrderReturnType::Signature(_) => {}
+                OrderReturnType::Signature(_) | OrderReturnType::Placed(..) => {}
             }
         }
 
+        let (bids_cancelled, asks_cancelled) = self.cancelled_by_cancel_orders();
+        self.check_order_count(Side::Bid, bids_cancelled, 1).await?;
+
         // Place bid order
         if let Some(ord_ret_type) = self
             .place_limit_order(
@@ -1288,13 +8000,12 @@ impl OBClient {
                 OrderReturnType::Instructions(insts) => {
                     instructions.extend(insts);
                 }
-                OrderReturnType::Signature(_) => {}
+                OrderReturnType::Signature(_) | OrderReturnType::Placed(..) => {}
             }
         }
 
-        self.rpc_client
-            .send_and_confirm((*self.owner).insecure_clone(), instructions)
-            .await
+        self.check_cancels_replaced(&instructions, bids_cancelled, asks_cancelled)?;
+        self.send_instructions(instructions).await
     }
 
     /// Executes a combination of canceling all limit orders, settling balance, and placing an ask order.
@@ -1337,24 +8048,9 @@ impl OBClient {
     ) -> Result<(bool, Signature)> {
         let mut instructions = Vec::new();
 
-        // Fetch recent prioritization fees
-        let r = self
-            .rpc_client
-            .inner()
-            .get_recent_prioritization_fees(&[])
-            .await?;
-
-        let mut max_fee = 1;
-        for f in r {
-            if f.prioritization_fee > max_fee {
-                max_fee = f.prioritization_fee;
-            }
-        }
-
-        // Set compute budget and fee instructions
-        let budget_ix = ComputeBudgetInstruction::set_compute_unit_limit(800_000);
-        let fee_ix = ComputeBudgetInstruction::set_compute_unit_price(max_fee);
-        instructions.push(budget_ix);
+        // Set the fee instruction, the compute unit limit is added when sending
+        let fee_ix =
+            ComputeBudgetInstruction::set_compute_unit_price(self.compute_unit_price().await?);
         instructions.push(fee_ix);
 
         // Cancel all limit orders
@@ -1363,7 +8059,7 @@ impl OBClient {
                 OrderReturnType::Instructions(insts) => {
                     instructions.extend(insts);
                 }
-                OrderReturnType::Signature(_) => {}
+                OrderReturnType::Signature(_) | OrderReturnType::Placed(..) => {}
             }
         }
 
@@ -1373,10 +8069,13 @@ impl OBClient {
                 OrderReturnType::Instructions(insts) => {
                     instructions.extend(insts);
                 }
-                OrderReturnType::Signature(_) => {}
+                OrderReturnType::Signature(_) | OrderReturnType::Placed(..) => {}
             }
         }
 
+        let (bids_cancelled, asks_cancelled) = self.cancelled_by_cancel_orders();
+        self.check_order_count(Side::Ask, asks_cancelled, 1).await?;
+
         // Place ask order
         if let Some(ord_ret_type) = self
             .place_limit_order(
@@ -1392,13 +8091,12 @@ impl OBClient {
                 OrderReturnType::Instructions(insts) => {
                     instructions.extend(insts);
                 }
-                OrderReturnType::Signature(_) => {}
+                OrderReturnType::Signature(_) | OrderReturnType::Placed(..) => {}
             }
         }
 
-        self.rpc_client
-            .send_and_confirm((*self.owner).insecure_clone(), instructions)
-            .await
+        self.check_cancels_replaced(&instructions, bids_cancelled, asks_cancelled)?;
+        self.send_instructions(instructions).await
     }
 
     /// Executes a combination of canceling all limit orders and settling balance.
@@ -1432,23 +8130,9 @@ impl OBClient {
     pub async fn cancel_settle(&mut self) -> Result<(bool, Signature)> {
         let mut instructions = Vec::new();
 
-        // Fetch recent prioritization fees
-        let r = self
-            .rpc_client
-            .inner()
-            .get_recent_prioritization_fees(&[])
-            .await?;
-        let mut max_fee = 1;
-        for f in r {
-            if f.prioritization_fee > max_fee {
-                max_fee = f.prioritization_fee;
-            }
-        }
-
-        // Set compute budget and fee instructions
-        let budget_ix = ComputeBudgetInstruction::set_compute_unit_limit(800_000);
-        let fee_ix = ComputeBudgetInstruction::set_compute_unit_price(max_fee);
-        instructions.push(budget_ix);
+        // Set the fee instruction, the compute unit limit is added when sending
+        let fee_ix =
+            ComputeBudgetInstruction::set_compute_unit_price(self.compute_unit_price().await?);
         instructions.push(fee_ix);
 
         // Cancel all limit orders
@@ -1457,7 +8141,7 @@ impl OBClient {
                 OrderReturnType::Instructions(insts) => {
                     instructions.extend(insts);
                 }
-                OrderReturnType::Signature(_) => {}
+                OrderReturnType::Signature(_) | OrderReturnType::Placed(..) => {}
             }
         }
 
@@ -1467,13 +8151,11 @@ impl OBClient {
                 OrderReturnType::Instructions(insts) => {
                     instructions.extend(insts);
                 }
-                OrderReturnType::Signature(_) => {}
+                OrderReturnType::Signature(_) | OrderReturnType::Placed(..) => {}
             }
         }
 
-        self.rpc_client
-            .send_and_confirm((*self.owner).insecure_clone(), instructions)
-            .await
+        self.send_instructions(instructions).await
     }
 
     /// Consumes events from the market for specified open orders accounts.
@@ -1486,7 +8168,9 @@ impl OBClient {
     ///
     /// # Returns
     ///
-    /// A `Result` containing the `Signature` of the transaction or a `Error` if an error occurs.
+    /// Whether the transaction was confirmed, its signature, and the number of events it
+    /// consumed, see [`OBClient::consumed_events`]. A count below `limit` means the queue was
+    /// drained or an event of an account not passed in stopped the crank.
     ///
     /// # Examples
     ///
@@ -1505,9 +8189,10 @@ impl OBClient {
     ///
     ///     let open_orders_accounts = vec![ob_client.open_orders.oo_key];
     ///     let limit = 10;
-    ///     let result = ob_client.consume_events_instruction(open_orders_accounts, limit).await?;
+    ///     let (confirmed, signature, consumed) =
+    ///         ob_client.consume_events_instruction(open_orders_accounts, limit).await?;
     ///
-    ///     println!("{:?}", result);
+    ///     println!("{} {} consumed {}", confirmed, signature, consumed);
     ///
     ///     Ok(())
     /// }
@@ -1516,7 +8201,7 @@ impl OBClient {
         &self,
         open_orders_accounts: Vec<Pubkey>,
         limit: u16,
-    ) -> Result<(bool, Signature)> {
+    ) -> Result<(bool, Signature, u64)> {
         let ix = openbook_dex::instruction::consume_events(
             &self.market_info.program_id,
             open_orders_accounts.iter().collect(),
@@ -1528,9 +8213,7 @@ impl OBClient {
         )
         .unwrap();
 
-        self.rpc_client
-            .send_and_confirm((*self.owner).insecure_clone(), vec![ix])
-            .await
+        self.consumed_events(ix, limit).await
     }
 
     /// Consumes permissioned events from the market for specified open orders accounts.
@@ -1543,7 +8226,9 @@ impl OBClient {
     ///
     /// # Returns
     ///
-    /// A `Result` containing the `Signature` of the transaction or a `Error` if an error occurs.
+    /// Whether the transaction was confirmed, its signature, and the number of events it
+    /// consumed, see [`OBClient::consumed_events`]. A count below `limit` means the queue was
+    /// drained or an event of an account not passed in stopped the crank.
     ///
     /// # Examples
     ///
@@ -1562,9 +8247,11 @@ impl OBClient {
     ///
     ///     let open_orders_accounts = vec![ob_client.open_orders.oo_key];
     ///     let limit = 10;
-    ///     let result = ob_client.consume_events_permissioned_instruction(open_orders_accounts, limit).await?;
+    ///     let (confirmed, signature, consumed) = ob_client
+    ///         .consume_events_permissioned_instruction(open_orders_accounts, limit)
+    ///         .await?;
     ///
-    ///     println!("{:?}", result);
+    ///     println!("{} {} consumed {}", confirmed, signature, consumed);
     ///
     ///     Ok(())
     /// }
@@ -1573,7 +8260,7 @@ impl OBClient {
         &self,
         open_orders_accounts: Vec<Pubkey>,
         limit: u16,
-    ) -> Result<(bool, Signature)> {
+    ) -> Result<(bool, Signature, u64)> {
         let ix = openbook_dex::instruction::consume_events_permissioned(
             &self.market_info.program_id,
             open_orders_accounts.iter().collect(),
@@ -1584,9 +8271,102 @@ impl OBClient {
         )
         .unwrap();
 
-        self.rpc_client
-            .send_and_confirm((*self.owner).insecure_clone(), vec![ix])
-            .await
+        self.consumed_events(ix, limit).await
+    }
+
+    /// Consumes events like [`OBClient::consume_events_instruction`], and returns what was
+    /// consumed as audit records.
+    ///
+    /// The whole queue is read and its pending events decoded before sending. Once confirmed,
+    /// the head sequence number of the queue read again tells which of them left it: those
+    /// numbered below it are returned. Events consumed by another cranker in the same window are
+    /// included, since they left the queue too; events pushed after the first read and consumed
+    /// right away cannot be decoded any more and are only logged as missing.
+    ///
+    /// # Returns
+    ///
+    /// Whether the transaction was confirmed, its signature, and the consumed events in queue
+    /// order. No event is returned if the transaction was not confirmed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use openbook::commitment_config::CommitmentConfig;
+    /// use openbook::v1::ob_client::OBClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let commitment = CommitmentConfig::confirmed();
+    ///
+    ///     let market_id = "8BnEgHoWFysVcuFFX7QztDmzuH8r5ZFvyP3sYwn1XTh6".parse()?;
+    ///
+    ///     let ob_client = OBClient::new(commitment, market_id, true, 1000).await?;
+    ///
+    ///     let open_orders = ob_client.collect_event_queue_open_orders(10).await?;
+    ///     let (_, signature, events) =
+    ///         ob_client.consume_events_audited(open_orders, 10).await?;
+    ///     for event in events {
+    ///         println!("{} {:?}", signature, event);
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn consume_events_audited(
+        &self,
+        open_orders_accounts: Vec<Pubkey>,
+        limit: u16,
+    ) -> Result<(bool, Signature, Vec<AuditEvent>)> {
+        let ix = openbook_dex::instruction::consume_events(
+            &self.market_info.program_id,
+            open_orders_accounts.iter().collect(),
+            &self.market_info.market_address,
+            &self.market_info.event_queue,
+            &self.market_info.coin_vault,
+            &self.market_info.pc_vault,
+            limit,
+        )?;
+
+        let data = self.fetch_event_queue_data().await?;
+        let queue = EventQueueView::from_account_data(&data)?;
+        let mut events = queue.audit_events(
+            queue.stats.count as usize,
+            self.market_info.coin_decimals,
+            self.market_info.pc_decimals,
+        );
+        let (confirmed, signature) = self.send_instructions(vec![ix]).await?;
+        if !confirmed {
+            return Ok((false, signature, Vec::new()));
+        }
+
+        // Sequence numbers below the head of the queue now are no longer pending.
+        let after = self.fetch_event_queue_stats().await?;
+        let head_seq_num = |stats: &EventQueueStats| stats.seq_num.wrapping_sub(stats.count);
+        let before_head = head_seq_num(&queue.stats);
+        let left = head_seq_num(&after).wrapping_sub(before_head);
+        events.retain(|event| event.seq_num.wrapping_sub(before_head) < left);
+        if (events.len() as u64) < left {
+            warn!(
+                "[*] {} of the {} events that left the queue were pushed after it was read",
+                left - events.len() as u64,
+                left
+            );
+        }
+
+        Ok((confirmed, signature, events))
+    }
+
+    /// Sends a consume events instruction and counts the events it consumed, see
+    /// [`consumed_count`]. The count is `0` if the transaction was not confirmed.
+    async fn consumed_events(&self, ix: Instruction, limit: u16) -> Result<(bool, Signature, u64)> {
+        let before = self.fetch_event_queue_stats().await?;
+        let (confirmed, signature) = self.send_instructions(vec![ix]).await?;
+        if !confirmed {
+            return Ok((false, signature, 0));
+        }
+        let after = self.fetch_event_queue_stats().await?;
+
+        Ok((confirmed, signature, consumed_count(&before, &after, limit)))
     }
 
     /// Loads open orders accounts for the owner, filtering them based on bids and asks.
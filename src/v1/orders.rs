@@ -1,9 +1,15 @@
 //! This module contains structs and functions related to open orders on the Solana blockchain.
 
 use crate::rpc::Rpc;
-use crate::v1::traits::OpenOrdersT;
-use anyhow::{Error, Result};
+use crate::utils::serialize_pubkey;
+use crate::v1::{book::BookOrder, ob_client::OPEN_ORDERS_ACCOUNT_LEN, traits::OpenOrdersT};
+use anyhow::{anyhow, Error, Result};
 use borsh::{BorshDeserialize, BorshSerialize};
+use openbook_dex::{
+    instruction::SelfTradeBehavior,
+    matching::{OrderType, Side},
+};
+use serde::{Deserialize, Serialize};
 use solana_client::rpc_config::RpcSendTransactionConfig;
 use solana_sdk::{
     compute_budget::ComputeBudgetInstruction,
@@ -13,12 +19,417 @@ use solana_sdk::{
     signer::keypair::Keypair,
     transaction::Transaction,
 };
-use std::fmt::{Debug, Formatter};
+use std::{
+    collections::BTreeMap,
+    fmt::{Debug, Formatter},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 use tracing::{debug, error};
 
-#[derive(Clone, Default, BorshDeserialize, BorshSerialize)]
+/// Offset of `native_coin_free` in an open orders account: the 5 padding bytes, the account
+/// flags, the market and the owner come first. It is followed by `native_coin_total`,
+/// `native_pc_free` and `native_pc_total`, all little endian `u64`.
+const NATIVE_BALANCES_OFFSET: usize = 5 + 8 + 32 + 32;
+
+/// Offset of `free_slot_bits` in an open orders account, right after the four balances. It is
+/// followed by `is_bid_bits`, the 128 `u128` order ids and the 128 `u64` client order ids.
+const FREE_SLOT_BITS_OFFSET: usize = NATIVE_BALANCES_OFFSET + 4 * 8;
+const IS_BID_BITS_OFFSET: usize = FREE_SLOT_BITS_OFFSET + 16;
+const ORDERS_OFFSET: usize = FREE_SLOT_BITS_OFFSET + 2 * 16;
+const CLIENT_ORDER_IDS_OFFSET: usize = ORDERS_OFFSET + 128 * 16;
+
+/// Returns the order id of the order resting with `client_order_id` in a raw open orders
+/// account, or `None` if no used slot holds it.
+pub(crate) fn find_resting_order_id(data: &[u8], client_order_id: u64) -> Option<u128> {
+    let free_slot_bits = u128::from_le_bytes(
+        data.get(FREE_SLOT_BITS_OFFSET..FREE_SLOT_BITS_OFFSET + 16)?
+            .try_into()
+            .ok()?,
+    );
+    (0..128).find_map(|slot| {
+        if free_slot_bits & (1u128 << slot) != 0 {
+            return None;
+        }
+        let start = CLIENT_ORDER_IDS_OFFSET + slot * 8;
+        let id = u64::from_le_bytes(data.get(start..start + 8)?.try_into().ok()?);
+        if id != client_order_id {
+            return None;
+        }
+        let start = ORDERS_OFFSET + slot * 16;
+        Some(u128::from_le_bytes(
+            data.get(start..start + 16)?.try_into().ok()?,
+        ))
+    })
+}
+
+/// Builds the order id the program gives an order of `side` at `price_lots` with sequence
+/// number `seq`.
+///
+/// The price in quote lots per base lot takes the high 64 bits and the sequence number the low
+/// 64 bits. Bids store the bitwise complement of the sequence number, so that on the bids slab,
+/// sorted by order id, older bids at the same price still come first.
+///
+/// # Examples
+///
+/// ```rust
+/// use openbook::matching::Side;
+/// use openbook::v1::orders::{decode_order_id, encode_order_id};
+///
+/// let ask = encode_order_id(1_234, Side::Ask, 7);
+/// assert_eq!(ask, (1_234u128 << 64) | 7);
+/// assert_eq!(decode_order_id(ask, Side::Ask), (1_234, 7));
+///
+/// let bid = encode_order_id(1_234, Side::Bid, 7);
+/// assert_eq!(bid, (1_234u128 << 64) | (!7u64) as u128);
+/// assert_eq!(decode_order_id(bid, Side::Bid), (1_234, 7));
+///
+/// // An older bid at the same price sorts above a newer one.
+/// assert!(encode_order_id(1_234, Side::Bid, 6) > bid);
+/// ```
+pub fn encode_order_id(price_lots: u64, side: Side, seq: u64) -> u128 {
+    let low = match side {
+        Side::Bid => !seq,
+        Side::Ask => seq,
+    };
+    ((price_lots as u128) << 64) | low as u128
+}
+
+/// Splits an order id of `side` into its price in quote lots per base lot and its sequence
+/// number, the inverse of [`encode_order_id`].
+pub fn decode_order_id(order_id: u128, side: Side) -> (u64, u64) {
+    let price_lots = (order_id >> 64) as u64;
+    let low = order_id as u64;
+    let seq = match side {
+        Side::Bid => !low,
+        Side::Ask => low,
+    };
+    (price_lots, seq)
+}
+
+/// Parsing and naming of the order parameter enums of the DEX program, `Side`, `OrderType`
+/// and `SelfTradeBehavior`, so the CLI and library users accept the same names.
+///
+/// The enums live in `openbook_dex`, which rules out implementing `FromStr` and `Display` on
+/// them here; `from_name` and `name` play those roles.
+///
+/// # Examples
+///
+/// ```rust
+/// use openbook::instruction::SelfTradeBehavior;
+/// use openbook::matching::{OrderType, Side};
+/// use openbook::v1::orders::NamedParam;
+///
+/// for side in [Side::Bid, Side::Ask] {
+///     assert_eq!(Side::from_name(side.name()).unwrap(), side);
+/// }
+/// for order_type in [OrderType::Limit, OrderType::ImmediateOrCancel, OrderType::PostOnly] {
+///     assert_eq!(OrderType::from_name(order_type.name()).unwrap(), order_type);
+/// }
+/// for behavior in [
+///     SelfTradeBehavior::DecrementTake,
+///     SelfTradeBehavior::CancelProvide,
+///     SelfTradeBehavior::AbortTransaction,
+/// ] {
+///     assert_eq!(SelfTradeBehavior::from_name(behavior.name()).unwrap(), behavior);
+/// }
+///
+/// // Names are case insensitive, anything else is an error rather than a default.
+/// assert_eq!(Side::from_name("ASK").unwrap(), Side::Ask);
+/// assert_eq!(
+///     Side::from_name("buy").unwrap_err().to_string(),
+///     "Invalid side \"buy\", expected one of: bid, ask"
+/// );
+/// ```
+pub trait NamedParam: Copy + PartialEq + 'static {
+    /// What the value stands for, used in error messages.
+    const KIND: &'static str;
+    /// Every value with its name.
+    const NAMES: &'static [(&'static str, Self)];
+
+    /// Parses a name of `NAMES`, ignoring case.
+    fn from_name(input: &str) -> Result<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(input.trim()))
+            .map(|(_, value)| *value)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::NAMES.iter().map(|(name, _)| *name).collect();
+                anyhow!(
+                    "Invalid {} {:?}, expected one of: {}",
+                    Self::KIND,
+                    input,
+                    names.join(", ")
+                )
+            })
+    }
+
+    /// Returns the name `from_name` parses back into this value.
+    fn name(&self) -> &'static str {
+        Self::NAMES
+            .iter()
+            .find(|(_, value)| value == self)
+            .map(|(name, _)| *name)
+            .unwrap_or_default()
+    }
+}
+
+impl NamedParam for Side {
+    const KIND: &'static str = "side";
+    const NAMES: &'static [(&'static str, Self)] = &[("bid", Side::Bid), ("ask", Side::Ask)];
+}
+
+impl NamedParam for OrderType {
+    const KIND: &'static str = "order type";
+    const NAMES: &'static [(&'static str, Self)] = &[
+        ("limit", OrderType::Limit),
+        ("ioc", OrderType::ImmediateOrCancel),
+        ("post-only", OrderType::PostOnly),
+    ];
+}
+
+impl NamedParam for SelfTradeBehavior {
+    const KIND: &'static str = "self trade behavior";
+    const NAMES: &'static [(&'static str, Self)] = &[
+        ("decrement-take", SelfTradeBehavior::DecrementTake),
+        ("cancel-provide", SelfTradeBehavior::CancelProvide),
+        ("abort-transaction", SelfTradeBehavior::AbortTransaction),
+    ];
+}
+
+/// Free and total balances held by an open orders account, in native and UI units.
+///
+/// The free part can be settled right away. The rest is locked by resting orders.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct OoBalances {
+    /// Base not locked by any order, in native units.
+    pub native_coin_free: u64,
+    /// All base held by the account, in native units.
+    pub native_coin_total: u64,
+    /// Quote not locked by any order, in native units.
+    pub native_pc_free: u64,
+    /// All quote held by the account, in native units.
+    pub native_pc_total: u64,
+    /// `native_coin_free` in UI units.
+    pub coin_free: f64,
+    /// `native_coin_total` in UI units.
+    pub coin_total: f64,
+    /// `native_pc_free` in UI units.
+    pub pc_free: f64,
+    /// `native_pc_total` in UI units.
+    pub pc_total: f64,
+}
+
+impl OoBalances {
+    /// Decodes the balances of a raw open orders account.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The raw account data, including the head padding.
+    /// * `coin_decimals` - Decimals of the base mint.
+    /// * `pc_decimals` - Decimals of the quote mint.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `data` is too short to be an open orders account.
+    pub fn from_account_data(data: &[u8], coin_decimals: u8, pc_decimals: u8) -> Result<Self> {
+        let field = |index: usize| {
+            let start = NATIVE_BALANCES_OFFSET + index * 8;
+            data.get(start..start + 8)
+                .and_then(|bytes| bytes.try_into().ok())
+                .map(u64::from_le_bytes)
+                .ok_or_else(|| Error::msg("Account data is too short for an open orders account"))
+        };
+        let coin_factor = 10f64.powi(coin_decimals as i32);
+        let pc_factor = 10f64.powi(pc_decimals as i32);
+
+        let native_coin_free = field(0)?;
+        let native_coin_total = field(1)?;
+        let native_pc_free = field(2)?;
+        let native_pc_total = field(3)?;
+        Ok(Self {
+            native_coin_free,
+            native_coin_total,
+            native_pc_free,
+            native_pc_total,
+            coin_free: native_coin_free as f64 / coin_factor,
+            coin_total: native_coin_total as f64 / coin_factor,
+            pc_free: native_pc_free as f64 / pc_factor,
+            pc_total: native_pc_total as f64 / pc_factor,
+        })
+    }
+
+    /// Base locked by resting asks, in UI units.
+    pub fn coin_locked(&self) -> f64 {
+        self.coin_total - self.coin_free
+    }
+
+    /// Quote locked by resting bids, in UI units.
+    pub fn pc_locked(&self) -> f64 {
+        self.pc_total - self.pc_free
+    }
+}
+
+impl std::fmt::Display for OoBalances {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Open orders balances (free / locked / total)")?;
+        writeln!(
+            f,
+            "    base:  {} / {} / {} ({} / {} native)",
+            self.coin_free,
+            self.coin_locked(),
+            self.coin_total,
+            self.native_coin_free,
+            self.native_coin_total
+        )?;
+        write!(
+            f,
+            "    quote: {} / {} / {} ({} / {} native)",
+            self.pc_free,
+            self.pc_locked(),
+            self.pc_total,
+            self.native_pc_free,
+            self.native_pc_total
+        )
+    }
+}
+
+/// Orders and balances of one open orders account, as listed by
+/// `OBClient::all_open_orders_summaries`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct OoSummary {
+    /// The open orders account.
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub open_orders: Pubkey,
+    /// The market the account was initialized for.
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub market: Pubkey,
+    /// Number of order slots in use. A filled or cancelled order keeps its slot until its event
+    /// is consumed.
+    pub resting_orders: u32,
+    /// Free and total balances; the free part is what a settle would move to the wallet.
+    pub balances: OoBalances,
+}
+
+impl OoSummary {
+    /// Decodes the market, used order slots and balances of a raw open orders account.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `data` is too short to be an open orders account.
+    pub fn from_account_data(
+        open_orders: Pubkey,
+        data: &[u8],
+        coin_decimals: u8,
+        pc_decimals: u8,
+    ) -> Result<Self> {
+        let balances = OoBalances::from_account_data(data, coin_decimals, pc_decimals)?;
+        let too_short = || Error::msg("Account data is too short for an open orders account");
+        let market = <[u8; 32]>::try_from(data.get(13..45).ok_or_else(too_short)?)?;
+        let free_slot_bits = data
+            .get(FREE_SLOT_BITS_OFFSET..FREE_SLOT_BITS_OFFSET + 16)
+            .ok_or_else(too_short)?;
+        let free_slot_bits = u128::from_le_bytes(free_slot_bits.try_into()?);
+
+        Ok(Self {
+            open_orders,
+            market: Pubkey::from(market),
+            resting_orders: free_slot_bits.count_zeros(),
+            balances,
+        })
+    }
+}
+
+/// An order held in one of the 128 slots of an open orders account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct OoSlotOrder {
+    /// The slot index, `0..128`.
+    pub slot: u8,
+    /// Whether the order is a bid, from `is_bid_bits`.
+    pub is_bid: bool,
+    /// The order id, price in the high 64 bits.
+    pub order_id: u128,
+    /// The client order id the order was placed with.
+    pub client_order_id: u64,
+}
+
+/// State of an open orders account at a slot, as streamed by `OBClient::subscribe_open_orders`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OoSnapshot {
+    /// The slot the account was read at.
+    pub slot: u64,
+    /// Free and total balances.
+    pub balances: OoBalances,
+    /// Bitmap of the unused order slots, bit `i` set if slot `i` is free.
+    pub free_slot_bits: u128,
+    /// Bitmap of the slots holding bids.
+    pub is_bid_bits: u128,
+    /// The orders of the used slots, in slot order.
+    pub orders: Vec<OoSlotOrder>,
+}
+
+impl OoSnapshot {
+    /// Decodes the balances and order slots of a raw open orders account read at `slot`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `data` is too short to be an open orders account.
+    pub fn from_account_data(
+        slot: u64,
+        data: &[u8],
+        coin_decimals: u8,
+        pc_decimals: u8,
+    ) -> Result<Self> {
+        let balances = OoBalances::from_account_data(data, coin_decimals, pc_decimals)?;
+        let read = |start: usize, len: usize| {
+            data.get(start..start + len)
+                .ok_or_else(|| Error::msg("Account data is too short for an open orders account"))
+        };
+        let read_u128 = |start: usize| -> Result<u128> {
+            Ok(u128::from_le_bytes(read(start, 16)?.try_into()?))
+        };
+        let free_slot_bits = read_u128(FREE_SLOT_BITS_OFFSET)?;
+        let is_bid_bits = read_u128(IS_BID_BITS_OFFSET)?;
+
+        let mut orders = Vec::new();
+        for slot in 0..128u8 {
+            if free_slot_bits & (1u128 << slot) != 0 {
+                continue;
+            }
+            let client_order_id = read(CLIENT_ORDER_IDS_OFFSET + slot as usize * 8, 8)?;
+            orders.push(OoSlotOrder {
+                slot,
+                is_bid: is_bid_bits & (1u128 << slot) != 0,
+                order_id: read_u128(ORDERS_OFFSET + slot as usize * 16)?,
+                client_order_id: u64::from_le_bytes(client_order_id.try_into()?),
+            });
+        }
+
+        Ok(Self {
+            slot,
+            balances,
+            free_slot_bits,
+            is_bid_bits,
+            orders,
+        })
+    }
+
+    /// The order ids of the orders on `side`, in slot order.
+    pub fn order_ids(&self, side: Side) -> Vec<u128> {
+        self.orders
+            .iter()
+            .filter(|order| order.is_bid == (side == Side::Bid))
+            .map(|order| order.order_id)
+            .collect()
+    }
+}
+
+#[derive(Clone, Default, BorshDeserialize, BorshSerialize, Serialize)]
 pub struct OpenOrders {
     /// The public key of the open orders account.
+    #[serde(serialize_with = "serialize_pubkey")]
     pub oo_key: Pubkey,
 
     /// The minimum ask price in the open orders account.
@@ -34,9 +445,11 @@ pub struct OpenOrders {
     pub open_bids: Vec<u128>,
 
     /// The public key of the bids associated with the open orders account.
+    #[serde(serialize_with = "serialize_pubkey")]
     pub bids_address: Pubkey,
 
     /// The public key of the asks associated with the open orders account.
+    #[serde(serialize_with = "serialize_pubkey")]
     pub asks_address: Pubkey,
 
     /// Vector containing the prices of open asks in the open orders account.
@@ -129,18 +542,23 @@ impl OpenOrdersT for OpenOrders {
         market_account: Pubkey,
     ) -> Result<Pubkey, Error> {
         let new_account_address = Keypair::new();
-        let space = 0;
+        let space = OPEN_ORDERS_ACCOUNT_LEN;
         let minimum_balance = connection
             .inner()
             .get_minimum_balance_for_rent_exemption(space)
             .await?;
+        debug!(
+            "[*] Funding the open orders account with {} lamports",
+            minimum_balance
+        );
 
+        // The program only initializes accounts it owns, of the open orders size.
         let instruction = solana_sdk::system_instruction::create_account(
             &keypair.pubkey(),
             &new_account_address.pubkey(),
             minimum_balance,
             space as u64,
-            &keypair.pubkey(),
+            &program_id,
         );
         let init_ix = openbook_dex::instruction::init_open_orders(
             &program_id,
@@ -214,4 +632,377 @@ pub struct OpenOrdersCacheEntry {
 pub enum OrderReturnType {
     Instructions(Vec<Instruction>),
     Signature(Signature),
+    /// A limit order was sent, with the values it was submitted with.
+    Placed(Signature, SubmittedOrder),
+}
+
+/// Exact values of a limit order as sent to the program, after rounding to lots and ticks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SubmittedOrder {
+    /// The side of the order.
+    pub side: Side,
+    /// The client order id attached to the order.
+    pub client_order_id: u64,
+    /// The limit price in quote lots per base lot.
+    pub price_lots: u64,
+    /// The order size in base lots.
+    pub base_lots: u64,
+    /// The most native quote the order may lock or spend, fees included.
+    pub max_native_quote: u64,
+    /// The limit price on the tick grid, in UI units.
+    pub price: f64,
+    /// The order size on the lot grid, in base UI units.
+    pub size: f64,
+    /// `price * size`, in quote UI units.
+    pub notional: f64,
+    /// Unix timestamp after which the program rejects the order.
+    pub max_ts: i64,
+    /// What became of the order, read once its placement is confirmed. `None` if it was not
+    /// sent, not confirmed, or could not be read.
+    pub outcome: Option<OrderOutcome>,
+}
+
+/// What became of a limit order once its placement was confirmed, see
+/// `OBClient::order_outcome`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrderOutcome {
+    /// The whole order rests on the book.
+    Resting {
+        /// The order id on the book.
+        order_id: u128,
+    },
+    /// The order matched entirely, on placement or later as a maker, and nothing rests.
+    FullyFilled {
+        /// Base bought or sold, in UI units.
+        base: f64,
+        /// Quote paid or received, fees included, in UI units.
+        quote: f64,
+    },
+    /// Part of the order matched and the rest rests on the book.
+    PartiallyFilled {
+        /// Base bought or sold, in UI units.
+        filled: f64,
+        /// The order id of the remainder on the book.
+        resting_order_id: u128,
+    },
+    /// The order neither rests nor has fills in the event queue: as a post-only order it would
+    /// have crossed the book and was dropped by the program. An order whose fills a crank
+    /// already consumed looks the same.
+    PostOnlyRejected,
+}
+
+impl std::fmt::Display for OrderOutcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderOutcome::Resting { order_id } => write!(f, "resting, order id {}", order_id),
+            OrderOutcome::FullyFilled { base, quote } => {
+                write!(f, "fully filled, {} base for {} quote", base, quote)
+            }
+            OrderOutcome::PartiallyFilled {
+                filled,
+                resting_order_id,
+            } => write!(
+                f,
+                "partially filled, {} base filled, rest resting as order id {}",
+                filled, resting_order_id
+            ),
+            OrderOutcome::PostOnlyRejected => write!(f, "rejected, post-only order would cross"),
+        }
+    }
+}
+
+/// An order placed by this client, as remembered by [`OrderState`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TrackedOrder {
+    /// Whether the order is a bid.
+    pub is_bid: bool,
+    /// The limit price in quote lots per base lot.
+    pub price_lots: u64,
+    /// The order size in base lots when placed.
+    pub base_lots: u64,
+    /// The limit price in UI units.
+    pub price: f64,
+    /// The order size in base UI units when placed.
+    pub size: f64,
+    /// The on-book order id, once seen in the open orders account.
+    pub order_id: Option<u128>,
+    /// Unix timestamp of the placement.
+    pub placed_at: u64,
+}
+
+/// The orders placed by the client keyed by client order id, shared by its clones and, with a
+/// state file, kept across runs.
+///
+/// The file is rewritten after every change, so a restarted process can still find and cancel
+/// by client order id the orders placed before a crash.
+#[derive(Debug, Clone, Default)]
+pub struct OrderState {
+    path: Option<PathBuf>,
+    orders: Arc<Mutex<BTreeMap<u64, TrackedOrder>>>,
+}
+
+impl OrderState {
+    /// Loads the orders saved at `path`, or starts empty if the file does not exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let orders = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|e| Error::msg(format!("Invalid state file {}: {}", path.display(), e)))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => {
+                return Err(Error::msg(format!(
+                    "Unable to read state file {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+        Ok(Self {
+            path: Some(path),
+            orders: Arc::new(Mutex::new(orders)),
+        })
+    }
+
+    /// Returns a copy of the tracked orders, by client order id.
+    pub fn orders(&self) -> BTreeMap<u64, TrackedOrder> {
+        self.orders
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Records a placed order and saves the state.
+    pub fn insert(&self, order: &SubmittedOrder, order_id: Option<u128>, placed_at: u64) {
+        self.update(|orders| {
+            orders.insert(
+                order.client_order_id,
+                TrackedOrder {
+                    is_bid: order.side == Side::Bid,
+                    price_lots: order.price_lots,
+                    base_lots: order.base_lots,
+                    price: order.price,
+                    size: order.size,
+                    order_id,
+                    placed_at,
+                },
+            );
+        });
+    }
+
+    /// Forgets an order and saves the state.
+    pub fn remove(&self, client_order_id: u64) -> Option<TrackedOrder> {
+        let mut removed = None;
+        self.update(|orders| removed = orders.remove(&client_order_id));
+        removed
+    }
+
+    /// Applies `f` to the tracked orders and saves the state.
+    pub fn update(&self, f: impl FnOnce(&mut BTreeMap<u64, TrackedOrder>)) {
+        let mut orders = self.orders.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut orders);
+        if let Err(err) = self.save(&orders) {
+            error!("[*] Unable to save the order state: {}", err);
+        }
+    }
+
+    /// Writes `orders` to the state file, through a temporary file so a crash mid-write
+    /// leaves the previous state intact.
+    fn save(&self, orders: &BTreeMap<u64, TrackedOrder>) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(orders)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// One side of an `OBClient::place_both` quote.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlaceSpec {
+    /// The order size in quote currency.
+    pub size_quote: f64,
+    /// The limit price.
+    pub price: f64,
+}
+
+impl FromStr for PlaceSpec {
+    type Err = Error;
+
+    /// Parses `SIZE@PRICE`, the size in quote currency.
+    fn from_str(s: &str) -> Result<Self> {
+        let (size_quote, price) = s
+            .split_once('@')
+            .ok_or_else(|| anyhow!("Invalid quote {s:?}, expected SIZE@PRICE"))?;
+        Ok(Self {
+            size_quote: size_quote
+                .trim()
+                .parse()
+                .map_err(|e| anyhow!("Invalid size in {s:?}: {e}"))?,
+            price: price
+                .trim()
+                .parse()
+                .map_err(|e| anyhow!("Invalid price in {s:?}: {e}"))?,
+        })
+    }
+}
+
+/// Difference between the owner's resting orders and a desired set of quotes, see
+/// `OBClient::quote_diff`.
+#[derive(Debug, Clone, Default)]
+pub struct QuotePlan {
+    /// Resting orders matching a desired quote, left untouched.
+    pub keep: Vec<(Side, BookOrder)>,
+    /// Resting orders matching no desired quote.
+    pub cancel: Vec<(Side, BookOrder)>,
+    /// Desired quotes not resting yet.
+    pub place: Vec<(Side, PlaceSpec)>,
+}
+
+impl QuotePlan {
+    /// Returns `true` if the resting orders already match the desired quotes.
+    pub fn is_empty(&self) -> bool {
+        self.cancel.is_empty() && self.place.is_empty()
+    }
+}
+
+/// Outcome of `OBClient::place_both`.
+#[derive(Debug)]
+pub struct PlaceBothResult {
+    /// The bid as submitted.
+    pub bid: SubmittedOrder,
+    /// The ask as submitted.
+    pub ask: SubmittedOrder,
+    /// The signature, or the instructions if not executed.
+    pub transaction: OrderReturnType,
+}
+
+impl PlaceBothResult {
+    /// Returns the client order ids of the bid and the ask.
+    pub fn client_order_ids(&self) -> (u64, u64) {
+        (self.bid.client_order_id, self.ask.client_order_id)
+    }
+}
+
+/// Outcome of `OBClient::place_laddered`.
+#[derive(Debug)]
+pub struct LadderResult {
+    /// The orders of the ladder, nearest to the base price first.
+    pub orders: Vec<SubmittedOrder>,
+    /// One signature or instruction set per transaction, in sending order.
+    pub transactions: Vec<OrderReturnType>,
+}
+
+impl LadderResult {
+    /// Returns the client order ids of the ladder's orders.
+    pub fn client_order_ids(&self) -> Vec<u64> {
+        self.orders
+            .iter()
+            .map(|order| order.client_order_id)
+            .collect()
+    }
+}
+
+/// Outcome of one transaction sent by `OBClient::cancel_orders_chunked`.
+#[derive(Debug, Clone)]
+pub struct CancelChunkResult {
+    /// The ids of the orders cancelled by this chunk.
+    pub order_ids: Vec<u128>,
+    /// The client order ids of the same orders, `0` for an order missing from the last book
+    /// snapshot.
+    pub client_order_ids: Vec<u64>,
+    /// The signature of the last attempt, `None` if it could not be sent.
+    pub signature: Option<Signature>,
+    /// How many times the chunk was sent.
+    pub attempts: u32,
+    /// Whether the chunk was confirmed.
+    pub confirmed: bool,
+}
+
+/// Outcome of `OBClient::cancel_orders_chunked`.
+#[derive(Debug, Clone, Default)]
+pub struct ChunkedCancelResult {
+    /// One result per chunk, in sending order.
+    pub chunks: Vec<CancelChunkResult>,
+    /// The client order ids of this client's orders still on the book once every chunk was
+    /// sent, re-read from the book. `None` if the book could not be read.
+    pub still_resting: Option<Vec<u64>>,
+}
+
+impl ChunkedCancelResult {
+    /// Returns `true` if every chunk was confirmed.
+    pub fn all_confirmed(&self) -> bool {
+        self.chunks.iter().all(|chunk| chunk.confirmed)
+    }
+
+    /// Returns the ids of the orders of the chunks that were not confirmed.
+    ///
+    /// Some of them may have been cancelled anyway, check `still_resting` before retrying.
+    pub fn unconfirmed_order_ids(&self) -> Vec<u128> {
+        self.chunks
+            .iter()
+            .filter(|chunk| !chunk.confirmed)
+            .flat_map(|chunk| chunk.order_ids.iter().copied())
+            .collect()
+    }
+}
+
+/// Outcome of `OBClient::cancel_orders_by_book_read`.
+#[derive(Debug, Clone, Default)]
+pub struct BookCancelResult {
+    /// The orders of the open orders account found on the book, cancelled by their on-book id.
+    pub orders: Vec<BookOrder>,
+    /// Ids of orders resting on the book that the open orders account does not list.
+    pub book_only: Vec<u128>,
+    /// Ids the open orders account lists that are not on the book. They are not cancelled.
+    pub account_only: Vec<u128>,
+    /// The instructions, or signatures, of the cancel transactions.
+    pub transactions: Vec<OrderReturnType>,
+}
+
+impl BookCancelResult {
+    /// Returns `true` if the book and the open orders account listed the same orders.
+    pub fn in_sync(&self) -> bool {
+        self.book_only.is_empty() && self.account_only.is_empty()
+    }
+}
+
+/// Stage latencies of `OBClient::ping_fill`.
+#[derive(Debug, Clone, Default)]
+pub struct PingFillReport {
+    /// The signature of the marketable order.
+    pub signature: Signature,
+    /// From signing the order to the node accepting it.
+    pub send: Duration,
+    /// From the node accepting the order to its confirmation.
+    pub confirm: Duration,
+    /// Time to consume the fill events, `None` if the order left none to consume.
+    pub crank: Option<Duration>,
+    /// Time to settle the fill back to the wallet.
+    pub settle: Duration,
+    /// From signing the order to the confirmed settle.
+    pub total: Duration,
+    /// Whether the order matched, read from the event queue before cranking.
+    pub filled: bool,
+}
+
+/// Outcome of `OBClient::flatten`.
+#[derive(Debug, Clone, Default)]
+pub struct FlattenResult {
+    /// One result per cancel transaction, in sending order.
+    pub cancels: Vec<CancelChunkResult>,
+    /// The signature of the settle transaction, `None` if it could not be sent.
+    pub settle_signature: Option<Signature>,
+}
+
+impl FlattenResult {
+    /// Returns `true` if every cancel transaction was confirmed.
+    pub fn is_flat(&self) -> bool {
+        self.cancels.iter().all(|chunk| chunk.confirmed)
+    }
 }
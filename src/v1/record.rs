@@ -0,0 +1,106 @@
+//! This module writes periodic order book captures to rotating JSON lines files.
+//!
+//! Each line of a file is one [`BookRecord`]. Files are named
+//! `<market>-<period start>.jsonl`, the period start being a unix timestamp in seconds
+//! rounded down to the rotation period, so a capture can be stopped and restarted and keeps
+//! appending to the file of the current period.
+
+use crate::utils::serialize_pubkey;
+use crate::v1::{book::BookOrder, event_queue::AuditEvent};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Version of the [`BookRecord`] schema, written in every record. Bumped whenever a field is
+/// renamed, removed or changes meaning; adding a field keeps it.
+pub const RECORD_SCHEMA_VERSION: u32 = 1;
+
+/// Default time covered by one record file.
+pub const DEFAULT_ROTATE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// One capture of the order book, one line of a record file.
+#[derive(Debug, Clone, Serialize)]
+pub struct BookRecord {
+    /// The schema version, [`RECORD_SCHEMA_VERSION`].
+    pub version: u32,
+    /// When the capture was taken, in unix milliseconds.
+    pub ts_ms: u64,
+    /// The market the book belongs to.
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub market: Pubkey,
+    /// Resting bids, best price first.
+    pub bids: Vec<BookOrder>,
+    /// Resting asks, best price first.
+    pub asks: Vec<BookOrder>,
+    /// Fill events pushed to the event queue since the previous record, oldest first. The
+    /// first record holds every fill still pending in the queue. Fills consumed by a crank
+    /// between two captures are missed.
+    pub fills: Vec<AuditEvent>,
+}
+
+/// Appends [`BookRecord`]s to files in a directory, starting a new file every rotation period.
+#[derive(Debug)]
+pub struct RecordWriter {
+    dir: PathBuf,
+    market: Pubkey,
+    rotate_secs: u64,
+    current: Option<(u64, PathBuf, BufWriter<File>)>,
+}
+
+impl RecordWriter {
+    /// Creates a writer for `market` records in `dir`, creating the directory if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `rotate_every` is under a second or the directory cannot be created.
+    pub fn new(dir: impl AsRef<Path>, market: Pubkey, rotate_every: Duration) -> Result<Self> {
+        let rotate_secs = rotate_every.as_secs();
+        if rotate_secs == 0 {
+            return Err(anyhow!(
+                "Record files must cover at least one second, got {rotate_every:?}"
+            ));
+        }
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+
+        Ok(Self {
+            dir,
+            market,
+            rotate_secs,
+            current: None,
+        })
+    }
+
+    /// Returns the file the last record was written to, `None` before the first one.
+    pub fn current_path(&self) -> Option<&Path> {
+        self.current.as_ref().map(|(_, path, _)| path.as_path())
+    }
+
+    /// Appends `record` as one JSON line, to the file of the period its timestamp falls in.
+    ///
+    /// Every line is flushed, so a capture that is killed loses at most the record being
+    /// written.
+    pub fn write(&mut self, record: &BookRecord) -> Result<()> {
+        let period = record.ts_ms / 1_000 / self.rotate_secs * self.rotate_secs;
+        if self.current.as_ref().map(|(start, _, _)| *start) != Some(period) {
+            if let Some((_, _, mut file)) = self.current.take() {
+                file.flush()?;
+            }
+            let path = self.dir.join(format!("{}-{}.jsonl", self.market, period));
+            let file = OpenOptions::new().create(true).append(true).open(&path)?;
+            self.current = Some((period, path, BufWriter::new(file)));
+        }
+
+        let (_, _, file) = self.current.as_mut().expect("record file opened above");
+        serde_json::to_writer(&mut *file, record)?;
+        file.write_all(b"\n")?;
+        file.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,486 @@
+//! End-to-end tests against a `solana-test-validator` running the OpenBook program.
+//!
+//! Built with `--features localnet-tests`. The program binary is read from `OPENBOOK_PROGRAM_SO`,
+//! the test is skipped when it is not set. See the README for how to dump it.
+#![cfg(feature = "localnet-tests")]
+
+use openbook::commitment_config::CommitmentConfig;
+use openbook::matching::Side;
+use openbook::pubkey::Pubkey;
+use openbook::rpc_client::RpcClient;
+use openbook::signature::Signature;
+use openbook::state::gen_vault_signer_key;
+use openbook::v1::ob_client::{OBClient, PROGRAM_ID_ENV, SRM_PROGRAM_ID};
+use openbook::v1::orders::{OrderOutcome, OrderReturnType};
+use solana_sdk::{
+    instruction::Instruction, program_pack::Pack, signature::Signer, signer::keypair::Keypair,
+    system_instruction, transaction::Transaction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account,
+};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+const PROGRAM_SO_ENV: &str = "OPENBOOK_PROGRAM_SO";
+const RPC_PORT: u16 = 18899;
+
+// Account sizes used by the reference market listing script, paddings included.
+const MARKET_LEN: usize = 388;
+const REQUEST_QUEUE_LEN: usize = 5120 + 12;
+const EVENT_QUEUE_LEN: usize = 262144 + 12;
+const SLAB_LEN: usize = 65536 + 12;
+const OPEN_ORDERS_LEN: usize = 3228;
+
+const BASE_DECIMALS: u8 = 9;
+const QUOTE_DECIMALS: u8 = 6;
+const BASE_LOT_SIZE: u64 = 1_000_000;
+const QUOTE_LOT_SIZE: u64 = 1;
+
+/// A running `solana-test-validator`, killed when dropped.
+struct Validator {
+    child: Child,
+    ledger: PathBuf,
+}
+
+impl Validator {
+    fn start(program_id: &Pubkey, program_so: &str) -> Self {
+        let ledger = std::env::temp_dir().join(format!("openbook-localnet-{}", std::process::id()));
+        let child = Command::new("solana-test-validator")
+            .arg("--reset")
+            .arg("--quiet")
+            .arg("--ledger")
+            .arg(&ledger)
+            .arg("--rpc-port")
+            .arg(RPC_PORT.to_string())
+            .arg("--bpf-program")
+            .arg(program_id.to_string())
+            .arg(program_so)
+            .stdout(Stdio::null())
+            .spawn()
+            .expect("solana-test-validator must be in PATH");
+        Self { child, ledger }
+    }
+
+    fn url(&self) -> String {
+        format!("http://127.0.0.1:{RPC_PORT}")
+    }
+}
+
+impl Drop for Validator {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = std::fs::remove_dir_all(&self.ledger);
+    }
+}
+
+async fn wait_until_healthy(rpc: &RpcClient) {
+    for _ in 0..120 {
+        if rpc.get_health().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    panic!("solana-test-validator did not become healthy");
+}
+
+async fn send(rpc: &RpcClient, payer: &Keypair, ixs: &[Instruction], signers: &[&Keypair]) {
+    let blockhash = rpc.get_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![payer];
+    all_signers.extend_from_slice(signers);
+    let tx =
+        Transaction::new_signed_with_payer(ixs, Some(&payer.pubkey()), &all_signers, blockhash);
+    rpc.send_and_confirm_transaction(&tx).await.unwrap();
+}
+
+/// Builds the instruction creating `account` with `len` bytes owned by `owner`.
+async fn create_account_ix(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    account: &Keypair,
+    len: usize,
+    owner: &Pubkey,
+) -> Instruction {
+    let lamports = rpc
+        .get_minimum_balance_for_rent_exemption(len)
+        .await
+        .unwrap();
+    system_instruction::create_account(
+        &payer.pubkey(),
+        &account.pubkey(),
+        lamports,
+        len as u64,
+        owner,
+    )
+}
+
+async fn create_mint(rpc: &RpcClient, payer: &Keypair, decimals: u8) -> Pubkey {
+    let mint = Keypair::new();
+    let ixs = [
+        create_account_ix(
+            rpc,
+            payer,
+            &mint,
+            spl_token::state::Mint::LEN,
+            &spl_token::id(),
+        )
+        .await,
+        spl_token::instruction::initialize_mint(
+            &spl_token::id(),
+            &mint.pubkey(),
+            &payer.pubkey(),
+            None,
+            decimals,
+        )
+        .unwrap(),
+    ];
+    send(rpc, payer, &ixs, &[&mint]).await;
+    mint.pubkey()
+}
+
+async fn create_token_account(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> Pubkey {
+    let account = Keypair::new();
+    let ixs = [
+        create_account_ix(
+            rpc,
+            payer,
+            &account,
+            spl_token::state::Account::LEN,
+            &spl_token::id(),
+        )
+        .await,
+        spl_token::instruction::initialize_account(
+            &spl_token::id(),
+            &account.pubkey(),
+            mint,
+            owner,
+        )
+        .unwrap(),
+    ];
+    send(rpc, payer, &ixs, &[&account]).await;
+    account.pubkey()
+}
+
+/// Creates the payer's associated token account for `mint` and funds it with `amount`.
+async fn fund_wallet(rpc: &RpcClient, payer: &Keypair, mint: &Pubkey, amount: u64) {
+    let ata = get_associated_token_address(&payer.pubkey(), mint);
+    let ixs = [
+        create_associated_token_account(&payer.pubkey(), &payer.pubkey(), mint, &spl_token::id()),
+        spl_token::instruction::mint_to(&spl_token::id(), mint, &ata, &payer.pubkey(), &[], amount)
+            .unwrap(),
+    ];
+    send(rpc, payer, &ixs, &[]).await;
+}
+
+/// Lists a permissionless market for `base_mint` / `quote_mint` and returns its address.
+async fn list_market(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+) -> Pubkey {
+    let market = Keypair::new();
+    let request_queue = Keypair::new();
+    let event_queue = Keypair::new();
+    let bids = Keypair::new();
+    let asks = Keypair::new();
+
+    let (vault_signer_nonce, vault_signer) = (0..100)
+        .find_map(|nonce| {
+            gen_vault_signer_key(nonce, &market.pubkey(), program_id)
+                .ok()
+                .map(|key| (nonce, key))
+        })
+        .unwrap();
+    let base_vault = create_token_account(rpc, payer, base_mint, &vault_signer).await;
+    let quote_vault = create_token_account(rpc, payer, quote_mint, &vault_signer).await;
+
+    let ixs = [
+        create_account_ix(rpc, payer, &market, MARKET_LEN, program_id).await,
+        create_account_ix(rpc, payer, &request_queue, REQUEST_QUEUE_LEN, program_id).await,
+    ];
+    send(rpc, payer, &ixs, &[&market, &request_queue]).await;
+
+    let ixs = [
+        create_account_ix(rpc, payer, &event_queue, EVENT_QUEUE_LEN, program_id).await,
+        create_account_ix(rpc, payer, &bids, SLAB_LEN, program_id).await,
+        create_account_ix(rpc, payer, &asks, SLAB_LEN, program_id).await,
+    ];
+    send(rpc, payer, &ixs, &[&event_queue, &bids, &asks]).await;
+
+    let init_ix = openbook_dex::instruction::initialize_market(
+        &market.pubkey(),
+        program_id,
+        base_mint,
+        quote_mint,
+        &base_vault,
+        &quote_vault,
+        None,
+        None,
+        None,
+        &bids.pubkey(),
+        &asks.pubkey(),
+        &request_queue.pubkey(),
+        &event_queue.pubkey(),
+        BASE_LOT_SIZE,
+        QUOTE_LOT_SIZE,
+        vault_signer_nonce,
+        100,
+    )
+    .unwrap();
+    send(rpc, payer, &[init_ix], &[]).await;
+
+    market.pubkey()
+}
+
+/// Creates and initializes an open orders account of `payer` on `market`.
+async fn init_open_orders(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    market: &Pubkey,
+) -> Pubkey {
+    let open_orders = Keypair::new();
+    let ixs = [
+        create_account_ix(rpc, payer, &open_orders, OPEN_ORDERS_LEN, program_id).await,
+        openbook_dex::instruction::init_open_orders(
+            program_id,
+            &open_orders.pubkey(),
+            &payer.pubkey(),
+            market,
+            None,
+        )
+        .unwrap(),
+    ];
+    send(rpc, payer, &ixs, &[&open_orders]).await;
+    open_orders.pubkey()
+}
+
+async fn assert_confirmed(ob_client: &OBClient, signature: Signature) {
+    let confirmed = ob_client
+        .confirm_signature(
+            &signature,
+            CommitmentConfig::confirmed(),
+            Duration::from_secs(30),
+        )
+        .await
+        .unwrap();
+    assert!(confirmed, "transaction {signature} was not confirmed");
+}
+
+#[tokio::test]
+async fn test_place_cancel_consume_settle_on_local_validator() {
+    let Ok(program_so) = std::env::var(PROGRAM_SO_ENV) else {
+        eprintln!("{PROGRAM_SO_ENV} is not set, skipping the local validator test");
+        return;
+    };
+    let program_id: Pubkey = SRM_PROGRAM_ID.parse().unwrap();
+
+    let validator = Validator::start(&program_id, &program_so);
+    let rpc = RpcClient::new_with_commitment(validator.url(), CommitmentConfig::confirmed());
+    wait_until_healthy(&rpc).await;
+
+    let payer = Keypair::new();
+    let airdrop = rpc
+        .request_airdrop(&payer.pubkey(), 100_000_000_000)
+        .await
+        .unwrap();
+    while !rpc.confirm_transaction(&airdrop).await.unwrap() {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+
+    let base_mint = create_mint(&rpc, &payer, BASE_DECIMALS).await;
+    let quote_mint = create_mint(&rpc, &payer, QUOTE_DECIMALS).await;
+    fund_wallet(
+        &rpc,
+        &payer,
+        &base_mint,
+        1_000 * 10u64.pow(BASE_DECIMALS as u32),
+    )
+    .await;
+    fund_wallet(
+        &rpc,
+        &payer,
+        &quote_mint,
+        1_000 * 10u64.pow(QUOTE_DECIMALS as u32),
+    )
+    .await;
+
+    let market = list_market(&rpc, &payer, &program_id, &base_mint, &quote_mint).await;
+    let open_orders = init_open_orders(&rpc, &payer, &program_id, &market).await;
+
+    // The client reads its configuration from the environment.
+    let key_path = validator.ledger.join("payer.json");
+    std::fs::write(
+        &key_path,
+        serde_json::to_string(&payer.to_bytes().to_vec()).unwrap(),
+    )
+    .unwrap();
+    std::env::set_var("RPC_URL", validator.url());
+    std::env::set_var("KEY_PATH", &key_path);
+    std::env::set_var("OOS_KEY", open_orders.to_string());
+    std::env::set_var(PROGRAM_ID_ENV, program_id.to_string());
+
+    let mut ob_client = OBClient::new(CommitmentConfig::confirmed(), market, true, 0)
+        .await
+        .unwrap();
+    assert_eq!(ob_client.market_info.coin_decimals, BASE_DECIMALS);
+    assert_eq!(ob_client.market_info.pc_decimals, QUOTE_DECIMALS);
+    assert_eq!(ob_client.open_orders.oo_key, open_orders);
+    // Nothing has traded on the new market, so it has not accrued any fees.
+    assert_eq!(ob_client.accrued_fees().await.unwrap(), (0.0, 0.0));
+
+    // Place one resting order on each side.
+    for (side, price) in [(Side::Bid, 1.0), (Side::Ask, 2.0)] {
+        let Some(OrderReturnType::Placed(signature, order)) = ob_client
+            .place_limit_order(5.0, side, 0.0, true, price)
+            .await
+            .unwrap()
+        else {
+            panic!("expected a signature for the {side:?} order");
+        };
+        assert_eq!(order.side, side);
+        assert_eq!(order.price, price);
+        assert_eq!(order.notional, order.price * order.size);
+        assert!(matches!(order.outcome, Some(OrderOutcome::Resting { .. })));
+        assert_confirmed(&ob_client, signature).await;
+    }
+
+    // A post-only bid crossing the resting ask is dropped without filling.
+    let Some(OrderReturnType::Placed(_, order)) = ob_client
+        .place_limit_order(5.0, Side::Bid, 0.0, true, 2.5)
+        .await
+        .unwrap()
+    else {
+        panic!("expected a signature for the crossing order");
+    };
+    assert_eq!(order.outcome, Some(OrderOutcome::PostOnlyRejected));
+    // The cap counts the orders resting now, although the book was not reloaded since.
+    ob_client.set_max_orders_per_side(1);
+    let err = ob_client
+        .place_limit_order(5.0, Side::Bid, 0.0, true, 0.5)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("1 already resting"), "{err}");
+    ob_client.max_orders_per_side = None;
+    ob_client.load_bids_asks_info().await.unwrap();
+    assert_eq!(ob_client.open_orders.open_bids.len(), 1);
+    assert_eq!(ob_client.open_orders.open_asks.len(), 1);
+    assert_eq!(ob_client.order_book.best_bid().unwrap().price, 1.0);
+    assert_eq!(ob_client.order_book.best_ask().unwrap().price, 2.0);
+    // The valid prices around the mid of 1.5 are one tick apart.
+    let ladder = ob_client
+        .market_info
+        .tick_ladder(ob_client.order_book.top().mid().unwrap(), 3);
+    assert_eq!(ladder.above.len(), 3);
+    assert_eq!(ladder.below.len(), 3);
+    assert!(ladder.below[0].1 < 1.5 && 1.5 < ladder.above[0].1);
+    assert_eq!(ladder.above[1].0, ladder.above[0].0 + 1);
+    assert_eq!(ladder.below[1].0 + 1, ladder.below[0].0);
+
+    // One base lot is the smallest order: just below it is refused, or rounded up on request.
+    let min_quote = ob_client.market_info.min_order_size() * 2.0;
+    assert_eq!(ob_client.order_base_lots(min_quote, 2.0).unwrap(), 1);
+    assert_eq!(ob_client.quote_to_base_size(min_quote * 0.99, 2.0), 0);
+    let err = ob_client
+        .place_limit_order(min_quote * 0.99, Side::Ask, 0.0, false, 2.0)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("minimum order size"), "{err}");
+    assert_eq!(ob_client.order_base_lots(0.0, 2.0).unwrap(), 0);
+    ob_client.set_round_up_to_min_size(true);
+    assert_eq!(ob_client.order_base_lots(min_quote * 0.99, 2.0).unwrap(), 1);
+    ob_client.set_round_up_to_min_size(false);
+
+    // Nothing waits in the request queue, so the match is skipped unless forced.
+    assert_eq!(ob_client.match_orders_transaction(10).await.unwrap(), None);
+    ob_client.set_skip_empty_match(false);
+    let (confirmed, _) = ob_client
+        .match_orders_transaction(10)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(confirmed);
+    ob_client.set_skip_empty_match(true);
+
+    // Cancelling pushes `Out` events that the crank has to consume.
+    let result = ob_client.cancel_orders_chunked(10, 5).await.unwrap();
+    assert!(result.all_confirmed());
+    assert_eq!(result.still_resting, Some(vec![]));
+    assert!(ob_client.open_orders.open_bids.is_empty());
+    assert!(ob_client.open_orders.open_asks.is_empty());
+
+    let pending = ob_client.fetch_event_queue_stats().await.unwrap().count;
+    assert!(pending > 0, "cancels should leave events to consume");
+    let owners = ob_client.collect_event_queue_open_orders(10).await.unwrap();
+    assert_eq!(owners, vec![open_orders]);
+    let (confirmed, _, consumed) = ob_client
+        .consume_events_instruction(owners, 10)
+        .await
+        .unwrap();
+    assert!(confirmed);
+    assert_eq!(consumed, pending.min(10));
+    assert_eq!(ob_client.fetch_event_queue_stats().await.unwrap().count, 0);
+
+    // Settling returns every released token to the wallet.
+    let Some(OrderReturnType::Signature(signature)) = ob_client.settle_balance(true).await.unwrap()
+    else {
+        panic!("expected a signature for the settle");
+    };
+    assert_confirmed(&ob_client, signature).await;
+    let balances = ob_client.wallet_balances().await.unwrap();
+    assert_eq!(balances.base, 1_000.0);
+    assert_eq!(balances.quote, 1_000.0);
+
+    // Asks are funded in base, bids keep room for the taker fee.
+    let max_sell = ob_client.max_order_size(Side::Ask, 2.0).await.unwrap();
+    assert_eq!(max_sell, 2_000.0);
+    let max_buy = ob_client.max_order_size(Side::Bid, 1.0).await.unwrap();
+    assert!(max_buy < 1_000.0 && max_buy > 990.0, "{max_buy}");
+
+    // The existing account heads the pool, which is topped up to three accounts.
+    let (_, created) = ob_client.ensure_open_orders_pool(3, true).await.unwrap();
+    assert_eq!(created.len(), 2);
+    assert_eq!(ob_client.open_orders_pool.len(), 3);
+    assert_eq!(ob_client.open_orders_pool[0], open_orders);
+    let (_, created) = ob_client.ensure_open_orders_pool(3, true).await.unwrap();
+    assert!(created.is_empty());
+    assert_eq!(ob_client.open_orders_pool.len(), 3);
+
+    // A bid from another account of the pool crosses our resting ask, which fills as maker.
+    let Some(OrderReturnType::Placed(signature, ask)) = ob_client
+        .place_limit_order(5.0, Side::Ask, 0.0, true, 2.0)
+        .await
+        .unwrap()
+    else {
+        panic!("expected a signature for the resting ask");
+    };
+    assert_confirmed(&ob_client, signature).await;
+    ob_client.open_orders.oo_key = ob_client.open_orders_pool[1];
+    let Some(OrderReturnType::Signature(signature)) = ob_client
+        .place_market_order(Side::Bid, 6.0, 0.0, true, true)
+        .await
+        .unwrap()
+    else {
+        panic!("expected a signature for the crossing bid");
+    };
+    assert_confirmed(&ob_client, signature).await;
+    ob_client.open_orders.oo_key = open_orders;
+    let outcome = ob_client.order_outcome(&ask).await.unwrap();
+    assert!(
+        matches!(outcome, OrderOutcome::FullyFilled { base, .. } if base == ask.size),
+        "{outcome}"
+    );
+    // The taker paid its fee in quote, which the market accrued.
+    let (base_fees, quote_fees) = ob_client.accrued_fees().await.unwrap();
+    assert_eq!(base_fees, 0.0);
+    assert!(quote_fees > 0.0, "{quote_fees}");
+}